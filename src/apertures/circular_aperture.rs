@@ -0,0 +1,27 @@
+use crate::math::vec::Vec2d;
+
+use std::f64::consts::PI;
+
+use super::Aperture;
+
+pub struct CircularAperture
+{
+}
+
+impl CircularAperture
+{
+	pub fn new() -> Self
+	{
+		Self{}
+	}
+}
+
+impl Aperture for CircularAperture
+{
+	fn sample(&self, u: Vec2d) -> Vec2d
+	{
+		let r = f64::sqrt(u.x());
+		let phi = 2.0 * PI * u.y();
+		Vec2d::new(r * f64::cos(phi), r * f64::sin(phi))
+	}
+}
@@ -0,0 +1,97 @@
+use crate::math::
+{
+	distribution::Distribution2D,
+	vec::Vec2d,
+};
+
+use super::Aperture;
+
+/*
+ * grayscale image of the opening covering the square around the unit disk, points are sampled by brightness
+ * pixels reaching outside the disk are dropped so samples stay inside it
+ */
+pub struct ImageAperture
+{
+	distribution: Distribution2D,
+}
+
+impl ImageAperture
+{
+	pub fn new(filename: &str) -> Self
+	{
+		let img = image::open(filename).expect("can't load file").to_luma8();
+		let (width, height) = img.dimensions();
+		let func: Vec::<f64> = img.pixels().map(|pixel| pixel[0] as f64 / 255.0).collect();
+		Self::from_pixels(&func, width as usize, height as usize)
+	}
+
+	/* brightness of width * height pixels, row by row from the top */
+	pub fn from_pixels(pixels: &[f64], width: usize, height: usize) -> Self
+	{
+		/* coordinate of the pixel edge farthest from the center, in [-1, 1] */
+		let farthest = |i: usize, n: usize| f64::max(f64::abs(2.0 * i as f64 / n as f64 - 1.0), f64::abs(2.0 * (i + 1) as f64 / n as f64 - 1.0));
+		let func: Vec::<f64> = pixels.iter().enumerate().map(|(i, pixel)|
+		{
+			let (x, y) = (farthest(i % width, width), farthest(i / width, height));
+			if x * x + y * y <= 1.0 { *pixel } else { 0.0 }
+		}).collect();
+
+		let distribution = Distribution2D::new(&func, width, height);
+		assert!(distribution.integral() > 0.0, "aperture image is black inside the unit disk");
+		Self{distribution}
+	}
+}
+
+impl Aperture for ImageAperture
+{
+	fn sample(&self, u: Vec2d) -> Vec2d
+	{
+		/* the first row of the image is the top of the opening */
+		let (p, _pdf) = self.distribution.sample_continuous(u);
+		Vec2d::new(p.x() * 2.0 - 1.0, 1.0 - p.y() * 2.0)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	const N: usize = 64;
+
+	fn samples(aperture: &ImageAperture) -> impl Iterator<Item = Vec2d> + '_
+	{
+		(0..N * N).map(move |i| aperture.sample(Vec2d::new(((i % N) as f64 + 0.5) / N as f64, ((i / N) as f64 + 0.5) / N as f64)))
+	}
+
+	#[test]
+	fn test_white_image_stays_inside_the_disk()
+	{
+		let aperture = ImageAperture::from_pixels(&[1.0; 16 * 16], 16, 16);
+		assert!(samples(&aperture).all(|p| Vec2d::dot(p, p) <= 1.0));
+	}
+
+	#[test]
+	fn test_samples_follow_brightness()
+	{
+		/* the top half of a 4x4 image is bright, only the two middle pixels of its second row are inside the disk */
+		let mut pixels = [0.0; 16];
+		pixels[..8].fill(1.0);
+		let aperture = ImageAperture::from_pixels(&pixels, 4, 4);
+		assert!(samples(&aperture).all(|p| (0.0..=0.5).contains(&p.y()) && f64::abs(p.x()) <= 0.5));
+	}
+
+	#[test]
+	#[should_panic]
+	fn test_black_disk_is_rejected()
+	{
+		/* the four corner pixels are the only bright ones and all of them are outside the disk */
+		let mut pixels = [0.0; 16];
+		for i in [0, 3, 12, 15]
+		{
+			pixels[i] = 1.0;
+		}
+		ImageAperture::from_pixels(&pixels, 4, 4);
+	}
+}
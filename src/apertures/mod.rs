@@ -0,0 +1,12 @@
+pub mod circular_aperture;
+pub mod image_aperture;
+pub mod polygonal_aperture;
+
+use crate::math::vec::Vec2d;
+
+/* shape of the lens opening, which is the shape of out of focus highlights */
+pub trait Aperture: Send + Sync
+{
+	/* point of the opening inside the unit disk from a uniform sample in [0, 1)² */
+	fn sample(&self, u: Vec2d) -> Vec2d;
+}
@@ -0,0 +1,86 @@
+use crate::math::vec::Vec2d;
+
+use std::f64::consts::PI;
+
+use super::Aperture;
+
+/* regular polygon formed by the blades of the diaphragm, rotation is in degrees */
+pub struct PolygonalAperture
+{
+	blades: u32,
+	rotation: f64,
+}
+
+impl PolygonalAperture
+{
+	pub fn new(blades: u32, rotation: f64) -> Self
+	{
+		Self{blades: u32::max(blades, 3), rotation: f64::to_radians(rotation)}
+	}
+
+	fn vertex(&self, i: u32) -> Vec2d
+	{
+		let phi = self.rotation + 2.0 * PI * i as f64 / self.blades as f64;
+		Vec2d::new(f64::cos(phi), f64::sin(phi))
+	}
+}
+
+impl Aperture for PolygonalAperture
+{
+	/* the polygon is made of equal triangles around the center, pick one and sample it uniformly */
+	fn sample(&self, u: Vec2d) -> Vec2d
+	{
+		let x = u.x() * self.blades as f64;
+		let i = u32::min(x as u32, self.blades - 1);
+		let u0 = x - i as f64;
+
+		let s = f64::sqrt(u0);
+		self.vertex(i) * (s * (1.0 - u.y())) + self.vertex(i + 1) * (s * u.y())
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	const N: usize = 64;
+
+	#[test]
+	fn test_samples_stay_inside_the_polygon()
+	{
+		/* inside a regular polygon means within the apothem along every edge normal */
+		let aperture = PolygonalAperture::new(5, 18.0);
+		let apothem = f64::cos(PI / 5.0);
+		for i in 0..N * N
+		{
+			let p = aperture.sample(Vec2d::new(((i % N) as f64 + 0.5) / N as f64, ((i / N) as f64 + 0.5) / N as f64));
+			for edge in 0..5
+			{
+				let normal = (aperture.vertex(edge) + aperture.vertex(edge + 1)) / (2.0 * apothem);
+				assert!(Vec2d::dot(p, normal) <= apothem + 1e-9);
+			}
+		}
+	}
+
+	#[test]
+	fn test_samples_are_uniform()
+	{
+		/* a uniform polygon is centered, and a disk of half the apothem holds its share of the area */
+		let aperture = PolygonalAperture::new(6, 0.0);
+		let apothem = f64::cos(PI / 6.0);
+		let area = 6.0 * f64::sin(PI / 6.0) * apothem;
+		let points: Vec::<Vec2d> = (0..N * N).map(|i| aperture.sample(Vec2d::new(((i % N) as f64 + 0.5) / N as f64, ((i / N) as f64 + 0.5) / N as f64))).collect();
+		let mean = points.iter().fold(Vec2d::zero(), |sum, p| sum + *p) / (N * N) as f64;
+		let inner = points.iter().filter(|p| Vec2d::dot(**p, **p) < apothem * apothem / 4.0).count();
+		assert!(Vec2d::dot(mean, mean) < 1e-6);
+		assert!((inner as f64 / (N * N) as f64 - PI * apothem * apothem / 4.0 / area).abs() < 0.01);
+	}
+
+	#[test]
+	fn test_fewer_than_three_blades_make_a_triangle()
+	{
+		assert_eq!(PolygonalAperture::new(1, 0.0).blades, 3);
+	}
+}
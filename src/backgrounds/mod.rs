@@ -0,0 +1,24 @@
+pub mod physical_sky;
+pub mod solid_background;
+
+use crate::math::vec::Vec3d;
+
+pub trait Background: Sync + Send
+{
+	fn value(&self, dir: Vec3d) -> Vec3d;
+
+	fn is_sampled(&self) -> bool
+	{
+		false
+	}
+
+	fn pdf_value(&self, _dir: Vec3d) -> f64
+	{
+		0.0
+	}
+
+	fn random(&self) -> Vec3d
+	{
+		Vec3d::new(0.0, 1.0, 0.0)
+	}
+}
@@ -0,0 +1,317 @@
+use crate::math::
+{
+	distribution::Distribution2D,
+	vec::
+	{
+		Vec2d,
+		Vec3d,
+	}
+};
+use crate::onb::Onb;
+
+use rand::Rng;
+
+use std::f64::consts::PI;
+
+use super::Background;
+
+const TABLE_WIDTH: usize = 256;
+const TABLE_HEIGHT: usize = 128;
+const SUN_ANGULAR_RADIUS: f64 = 0.004654;
+const SUN_LUMINANCE: f64 = 1.6e6;
+
+/* Preetham, Shirley & Smits, "A Practical Analytic Model for Daylight" */
+pub struct PhysicalSky
+{
+	sun_direction: Vec3d,
+	sun_cos_max: f64,
+	sun_radiance: Vec3d,
+	ground: Vec3d,
+	theta_s: f64,
+	zenith: Vec3d,
+	perez: [[f64; 5]; 3],
+	intensity: f64,
+	distribution: Distribution2D,
+	sun_probability: f64,
+}
+
+impl PhysicalSky
+{
+	pub fn new(sun_direction: Vec3d, turbidity: f64, ground_albedo: Vec3d) -> Self
+	{
+		Self::with_intensity(sun_direction, turbidity, ground_albedo, 0.03)
+	}
+
+	pub fn with_intensity(sun_direction: Vec3d, turbidity: f64, ground_albedo: Vec3d, intensity: f64) -> Self
+	{
+		let sun_direction = Vec3d::normalize(sun_direction);
+		let theta_s = f64::acos(f64::clamp(sun_direction.y(), -1.0, 1.0));
+		let t = turbidity;
+
+		let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+		let zenith_y = (4.0453 * t - 4.9710) * f64::tan(chi) - 0.2155 * t + 2.4192;
+		let th = [theta_s * theta_s * theta_s, theta_s * theta_s, theta_s, 1.0];
+		let zenith_x = t * t * (0.00166 * th[0] - 0.00375 * th[1] + 0.00209 * th[2])
+		             + t * (-0.02903 * th[0] + 0.06377 * th[1] - 0.03202 * th[2] + 0.00394 * th[3])
+		             + (0.11693 * th[0] - 0.21196 * th[1] + 0.06052 * th[2] + 0.25886 * th[3]);
+		let zenith_yy = t * t * (0.00275 * th[0] - 0.00610 * th[1] + 0.00317 * th[2])
+		              + t * (-0.04214 * th[0] + 0.08970 * th[1] - 0.04153 * th[2] + 0.00516 * th[3])
+		              + (0.15346 * th[0] - 0.26756 * th[1] + 0.06670 * th[2] + 0.26688 * th[3]);
+
+		let perez =
+		[
+			[ 0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251,  0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+			[-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+			[-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+		];
+
+		let sun_radiance = Self::sun_radiance(theta_s, turbidity) * intensity;
+
+		let mut sky = Self
+		{
+			sun_direction,
+			sun_cos_max: f64::cos(SUN_ANGULAR_RADIUS),
+			sun_radiance,
+			ground: Vec3d::zero(),
+			theta_s,
+			zenith: Vec3d::new(f64::max(zenith_y, 0.0), zenith_x, zenith_yy),
+			perez,
+			intensity,
+			distribution: Distribution2D::new(&[1.0], 1, 1),
+			sun_probability: 0.0,
+		};
+
+		/* ground radiance from the irradiance it receives from the sky and the sun */
+		let mut irradiance = Vec3d::zero();
+		let n = 64;
+		for i in 0..n
+		{
+			for j in 0..n * 4
+			{
+				let theta = (i as f64 + 0.5) / n as f64 * PI / 2.0;
+				let phi = (j as f64 + 0.5) / (n * 4) as f64 * 2.0 * PI;
+				let dir = Self::direction(theta, phi);
+				irradiance += sky.sky_radiance(dir) * f64::cos(theta) * f64::sin(theta);
+			}
+		}
+		irradiance *= (PI / 2.0 / n as f64) * (2.0 * PI / (n * 4) as f64);
+		let sun_solid_angle = 2.0 * PI * (1.0 - sky.sun_cos_max);
+		irradiance += sky.sun_radiance * sun_solid_angle * f64::max(sun_direction.y(), 0.0);
+		sky.ground = ground_albedo * irradiance / PI;
+
+		let mut func = vec![0.0; TABLE_WIDTH * TABLE_HEIGHT];
+		for v in 0..TABLE_HEIGHT
+		{
+			let theta = (v as f64 + 0.5) / TABLE_HEIGHT as f64 * PI;
+			for u in 0..TABLE_WIDTH
+			{
+				let phi = (u as f64 + 0.5) / TABLE_WIDTH as f64 * 2.0 * PI;
+				func[v * TABLE_WIDTH + u] = sky.sky_radiance(Self::direction(theta, phi)).luminance() * f64::sin(theta);
+			}
+		}
+		sky.distribution = Distribution2D::new(&func, TABLE_WIDTH, TABLE_HEIGHT);
+
+		if sun_direction.y() > 0.0
+		{
+			let sun_power = sky.sun_radiance.luminance() * sun_solid_angle;
+			let sky_power = sky.distribution.integral() * 2.0 * PI * PI;
+			sky.sun_probability = f64::clamp(sun_power / (sun_power + sky_power), 0.25, 0.75);
+		}
+
+		sky
+	}
+
+	fn direction(theta: f64, phi: f64) -> Vec3d
+	{
+		Vec3d::new(f64::sin(theta) * f64::cos(phi), f64::cos(theta), f64::sin(theta) * f64::sin(phi))
+	}
+
+	fn perez(&self, channel: usize, cos_theta: f64, gamma: f64) -> f64
+	{
+		let c = &self.perez[channel];
+		(1.0 + c[0] * f64::exp(c[1] / cos_theta)) * (1.0 + c[2] * f64::exp(c[3] * gamma) + c[4] * f64::cos(gamma) * f64::cos(gamma))
+	}
+
+	fn sun_radiance(theta_s: f64, turbidity: f64) -> Vec3d
+	{
+		if theta_s >= PI / 2.0
+		{
+			return Vec3d::zero();
+		}
+
+		let theta_deg = f64::to_degrees(theta_s);
+		let m = 1.0 / (f64::cos(theta_s) + 0.15 * f64::powf(93.885 - theta_deg, -1.253));
+		let beta = 0.04608365822050 * turbidity - 0.04586025928522;
+		let lambdas = Vec3d::new(0.680, 0.550, 0.440);
+		lambdas.for_each(&|l| f64::exp(-0.008735 * f64::powf(l, -4.08) * m) * f64::exp(-beta * f64::powf(l, -1.3) * m)) * SUN_LUMINANCE
+	}
+
+	fn sky_radiance(&self, dir: Vec3d) -> Vec3d
+	{
+		if dir.y() < 0.0
+		{
+			return self.ground;
+		}
+
+		let cos_theta = f64::max(dir.y(), 0.01);
+		let gamma = f64::acos(f64::clamp(Vec3d::dot(dir, self.sun_direction), -1.0, 1.0));
+		let lum = self.zenith.x() * self.perez(0, cos_theta, gamma) / self.perez(0, 1.0, self.theta_s);
+		let x = self.zenith.y() * self.perez(1, cos_theta, gamma) / self.perez(1, 1.0, self.theta_s);
+		let y = self.zenith.z() * self.perez(2, cos_theta, gamma) / self.perez(2, 1.0, self.theta_s);
+
+		let xx = x / y * lum;
+		let zz = (1.0 - x - y) / y * lum;
+		let rgb = Vec3d::new( 3.2406 * xx - 1.5372 * lum - 0.4986 * zz,
+		                     -0.9689 * xx + 1.8758 * lum + 0.0415 * zz,
+		                      0.0557 * xx - 0.2040 * lum + 1.0570 * zz);
+		Vec3d::max(rgb, Vec3d::zero()) * self.intensity
+	}
+
+	fn in_sun(&self, dir: Vec3d) -> bool
+	{
+		dir.y() >= 0.0 && Vec3d::dot(dir, self.sun_direction) >= self.sun_cos_max
+	}
+}
+
+impl Background for PhysicalSky
+{
+	fn value(&self, dir: Vec3d) -> Vec3d
+	{
+		let dir = Vec3d::normalize(dir);
+		if self.in_sun(dir)
+		{
+			return self.sky_radiance(dir) + self.sun_radiance;
+		}
+
+		self.sky_radiance(dir)
+	}
+
+	fn is_sampled(&self) -> bool
+	{
+		true
+	}
+
+	fn pdf_value(&self, dir: Vec3d) -> f64
+	{
+		let dir = Vec3d::normalize(dir);
+		let mut pdf = 0.0;
+		if self.in_sun(dir)
+		{
+			pdf += self.sun_probability / (2.0 * PI * (1.0 - self.sun_cos_max));
+		}
+
+		let theta = f64::acos(f64::clamp(dir.y(), -1.0, 1.0));
+		let sin_theta = f64::sin(theta);
+		if sin_theta > 0.0
+		{
+			let mut phi = f64::atan2(dir.z(), dir.x());
+			if phi < 0.0
+			{
+				phi += 2.0 * PI;
+			}
+
+			let uv = Vec2d::new(phi / (2.0 * PI), theta / PI);
+			pdf += (1.0 - self.sun_probability) * self.distribution.pdf(uv) / (2.0 * PI * PI * sin_theta);
+		}
+
+		pdf
+	}
+
+	fn random(&self) -> Vec3d
+	{
+		let mut rng = rand::thread_rng();
+		if rng.gen_range(0.0..1.0) < self.sun_probability
+		{
+			let cos_theta = 1.0 - rng.gen_range(0.0..1.0) * (1.0 - self.sun_cos_max);
+			let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+			let phi = 2.0 * PI * rng.gen_range(0.0..1.0);
+			return Onb::from_w(self.sun_direction).local(Vec3d::new(f64::cos(phi) * sin_theta, f64::sin(phi) * sin_theta, cos_theta));
+		}
+
+		let (uv, _pdf) = self.distribution.sample_continuous(Vec2d::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)));
+		Self::direction(uv.y() * PI, uv.x() * 2.0 * PI)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	fn sky() -> PhysicalSky
+	{
+		PhysicalSky::new(Vec3d::new(1.0, 1.0, 0.5), 3.0, Vec3d::newv(0.2))
+	}
+
+	#[test]
+	fn test_sky_pdf_integrates_to_one()
+	{
+		/* the sun cone is too small for the quadrature, its share is the sun probability */
+		let sky = sky();
+		let n = 256;
+		let mut integral = 0.0;
+		for i in 0..n
+		{
+			for j in 0..n * 2
+			{
+				let theta = (i as f64 + 0.5) / n as f64 * PI;
+				let phi = (j as f64 + 0.5) / (n * 2) as f64 * 2.0 * PI;
+				let dir = PhysicalSky::direction(theta, phi);
+				if !sky.in_sun(dir)
+				{
+					integral += sky.pdf_value(dir) * f64::sin(theta);
+				}
+			}
+		}
+		integral *= (PI / n as f64) * (2.0 * PI / (n * 2) as f64);
+		assert!((integral + sky.sun_probability - 1.0).abs() < 0.01);
+	}
+
+	#[test]
+	fn test_sun_is_sampled_with_its_probability()
+	{
+		let sky = sky();
+		let n = 10000;
+		let mut in_sun = 0;
+		for _ in 0..n
+		{
+			let dir = sky.random();
+			assert!(sky.pdf_value(dir) > 0.0);
+			if sky.in_sun(dir)
+			{
+				in_sun += 1;
+			}
+		}
+		assert!((0.25..=0.75).contains(&sky.sun_probability));
+		assert!((in_sun as f64 / n as f64 - sky.sun_probability).abs() < 0.02);
+	}
+
+	#[test]
+	fn test_sky_is_blue_and_the_low_sun_red()
+	{
+		let zenith = sky().value(Vec3d::new(0.0, 1.0, 0.0));
+		assert!(zenith.z() > zenith.x());
+
+		let high = PhysicalSky::sun_radiance(f64::to_radians(20.0), 3.0);
+		let low = PhysicalSky::sun_radiance(f64::to_radians(85.0), 3.0);
+		assert!(low.x() / low.z() > high.x() / high.z());
+		assert!(high.y() > low.y());
+	}
+
+	#[test]
+	fn test_ground_reflects_the_sky()
+	{
+		let down = Vec3d::new(0.3, -1.0, 0.2);
+		assert!(sky().value(down).y() > 0.0);
+		assert_eq!(PhysicalSky::new(Vec3d::new(1.0, 1.0, 0.5), 3.0, Vec3d::zero()).value(down), Vec3d::zero());
+	}
+
+	#[test]
+	fn test_sun_below_the_horizon_is_not_sampled()
+	{
+		let night = PhysicalSky::new(Vec3d::new(1.0, -0.2, 0.0), 3.0, Vec3d::newv(0.2));
+		assert_eq!(night.sun_probability, 0.0);
+		assert_eq!(PhysicalSky::sun_radiance(f64::to_radians(95.0), 3.0), Vec3d::zero());
+	}
+}
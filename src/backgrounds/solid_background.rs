@@ -0,0 +1,24 @@
+use crate::math::vec::Vec3d;
+
+use super::Background;
+
+pub struct SolidBackground
+{
+	color: Vec3d,
+}
+
+impl SolidBackground
+{
+	pub fn new(color: Vec3d) -> Self
+	{
+		Self{color}
+	}
+}
+
+impl Background for SolidBackground
+{
+	fn value(&self, _dir: Vec3d) -> Vec3d
+	{
+		self.color
+	}
+}
@@ -0,0 +1,277 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use super::
+{
+	Bxdf,
+	BsdfSample,
+	LOBE_GLOSSY,
+	LOBE_REFLECTION,
+	LOBE_SPECULAR,
+	LOBE_TRANSMISSION,
+	cos_theta,
+	fresnel::
+	{
+		fr_dielectric,
+		refract,
+	},
+	microfacet::TrowbridgeReitz,
+	same_hemisphere,
+};
+
+/*
+ * dielectric coat over a base bxdf, light crossing the coat is refracted through a smooth interface,
+ * attenuated by both Fresnel transmittances and by absorption along the path inside the coat
+ * color is the transmittance of the coat for one unit of thickness at normal incidence
+ */
+pub struct CoatedBxdf
+{
+	base: Box::<dyn Bxdf>,
+	eta: f64,
+	distribution: TrowbridgeReitz,
+	color: Vec3d,
+	thickness: f64,
+}
+
+impl CoatedBxdf
+{
+	pub fn new(base: Box::<dyn Bxdf>, eta: f64, distribution: TrowbridgeReitz, color: Vec3d, thickness: f64) -> Self
+	{
+		Self{base, eta, distribution, color, thickness}
+	}
+
+	/* probability of sampling the coat instead of the base */
+	fn coat_probability(&self, wo: Vec3d) -> f64
+	{
+		f64::max(fr_dielectric(f64::abs(cos_theta(wo)), self.eta), 0.25)
+	}
+
+	/* direction inside the coat, pointing away from the base */
+	fn inside(&self, w: Vec3d) -> Option<Vec3d>
+	{
+		let (wt, _etap) = refract(w, Vec3d::new(0.0, 0.0, 1.0), self.eta)?;
+		Some(-wt)
+	}
+
+	fn transmittance(&self, wo: Vec3d, wi: Vec3d, wo_inside: Vec3d, wi_inside: Vec3d) -> Vec3d
+	{
+		let fresnel = (1.0 - fr_dielectric(cos_theta(wo), self.eta)) * (1.0 - fr_dielectric(cos_theta(wi), self.eta));
+		let distance = self.thickness * (1.0 / cos_theta(wo_inside) + 1.0 / cos_theta(wi_inside));
+		self.color.for_each(&|c| f64::powf(c, distance)) * fresnel
+	}
+
+	/* solid angle compression of the refraction of wi, also the jacobian of the refracted direction */
+	fn compression(&self, wi: Vec3d, wi_inside: Vec3d) -> f64
+	{
+		cos_theta(wi) / (self.eta * self.eta * cos_theta(wi_inside))
+	}
+
+	fn coat_eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		if self.distribution.effectively_smooth()
+		{
+			return Vec3d::zero();
+		}
+
+		let wm = wo + wi;
+		if Vec3d::dot(wm, wm) == 0.0
+		{
+			return Vec3d::zero();
+		}
+		let wm = Vec3d::normalize(wm);
+
+		let f = fr_dielectric(f64::abs(Vec3d::dot(wo, wm)), self.eta);
+		Vec3d::newv(f * self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * cos_theta(wo)))
+	}
+
+	fn coat_pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		if self.distribution.effectively_smooth()
+		{
+			return 0.0;
+		}
+
+		let wm = wo + wi;
+		if Vec3d::dot(wm, wm) == 0.0
+		{
+			return 0.0;
+		}
+		let wm = Vec3d::normalize(wm);
+
+		self.distribution.pdf(wo, wm) / (4.0 * f64::abs(Vec3d::dot(wo, wm)))
+	}
+}
+
+impl Bxdf for CoatedBxdf
+{
+	fn eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		if !same_hemisphere(wo, wi) || cos_theta(wo) < 0.0
+		{
+			return Vec3d::zero();
+		}
+
+		let mut f = self.coat_eval(wo, wi);
+		if let (Some(wo_inside), Some(wi_inside)) = (self.inside(wo), self.inside(wi))
+		{
+			let base = self.base.eval(wo_inside, wi_inside);
+			f += base * self.transmittance(wo, wi, wo_inside, wi_inside) * self.compression(wi, wi_inside);
+		}
+		f
+	}
+
+	fn sample(&self, wo: Vec3d, uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		if cos_theta(wo) <= 0.0
+		{
+			return None;
+		}
+
+		let p_coat = self.coat_probability(wo);
+		if uc < p_coat
+		{
+			if self.distribution.effectively_smooth()
+			{
+				let wi = Vec3d::new(-wo.x(), -wo.y(), wo.z());
+				return Some(BsdfSample::new(wi, Vec3d::newv(fr_dielectric(cos_theta(wo), self.eta)), p_coat, LOBE_SPECULAR | LOBE_REFLECTION));
+			}
+
+			let wi = Vec3d::reflect(-wo, self.distribution.sample_wm(wo, u));
+			if !same_hemisphere(wo, wi)
+			{
+				return None;
+			}
+
+			return Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi), LOBE_GLOSSY | LOBE_REFLECTION));
+		}
+
+		let wo_inside = self.inside(wo)?;
+		let sample = self.base.sample(wo_inside, (uc - p_coat) / (1.0 - p_coat), u)?;
+		if !same_hemisphere(wo_inside, sample.wi)
+		{
+			return None;
+		}
+
+		/* leave the coat, light arriving beyond the critical angle is totally reflected */
+		let (wi, _etap) = refract(-sample.wi, Vec3d::new(0.0, 0.0, 1.0), self.eta)?;
+		if sample.is_specular()
+		{
+			let f = sample.f * self.transmittance(wo, wi, wo_inside, sample.wi);
+			return Some(BsdfSample::new(wi, f, sample.pdf * (1.0 - p_coat), sample.flags));
+		}
+
+		Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi), sample.flags))
+	}
+
+	fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		if !same_hemisphere(wo, wi) || cos_theta(wo) < 0.0
+		{
+			return 0.0;
+		}
+
+		let p_coat = self.coat_probability(wo);
+		let mut pdf = p_coat * self.coat_pdf(wo, wi);
+		if let (Some(wo_inside), Some(wi_inside)) = (self.inside(wo), self.inside(wi))
+		{
+			pdf += (1.0 - p_coat) * self.base.pdf(wo_inside, wi_inside) * self.compression(wi, wi_inside);
+		}
+		pdf
+	}
+
+	fn flags(&self) -> u32
+	{
+		let coat = if self.distribution.effectively_smooth() { LOBE_SPECULAR } else { LOBE_GLOSSY };
+		(self.base.flags() & !LOBE_TRANSMISSION) | coat | LOBE_REFLECTION
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::bsdf::
+	{
+		LOBE_DIFFUSE,
+		diffuse_bxdf::DiffuseBxdf,
+		uniform_hemisphere,
+	};
+
+	use std::f64::consts::PI;
+
+	const N: usize = 200;
+
+	fn grid() -> impl Iterator<Item = (f64, Vec2d)>
+	{
+		(0..N * N).map(|i| (((i * 7919) % (N * N)) as f64 / (N * N) as f64, Vec2d::new(((i % N) as f64 + 0.5) / N as f64, ((i / N) as f64 + 0.5) / N as f64)))
+	}
+
+	fn wo() -> Vec3d
+	{
+		Vec3d::normalize(Vec3d::new(0.4, 0.1, 0.8))
+	}
+
+	fn coated(roughness: f64, color: Vec3d, thickness: f64) -> CoatedBxdf
+	{
+		CoatedBxdf::new(Box::new(DiffuseBxdf::new(Vec3d::one())), 1.5, TrowbridgeReitz::from_roughness(roughness, roughness), color, thickness)
+	}
+
+	fn albedo(bxdf: &CoatedBxdf) -> Vec3d
+	{
+		grid().filter_map(|(uc, u)| bxdf.sample(wo(), uc, u)).map(|sample| sample.f / sample.pdf).fold(Vec3d::zero(), |sum, f| sum + f) / (N * N) as f64
+	}
+
+	#[test]
+	fn test_flags()
+	{
+		assert_eq!(coated(0.0, Vec3d::one(), 0.0).flags(), LOBE_DIFFUSE | LOBE_SPECULAR | LOBE_REFLECTION);
+		assert_eq!(coated(0.3, Vec3d::one(), 0.0).flags(), LOBE_DIFFUSE | LOBE_GLOSSY | LOBE_REFLECTION);
+	}
+
+	#[test]
+	fn test_sample_matches_eval_and_pdf()
+	{
+		let bxdf = coated(0.3, Vec3d::new(0.9, 0.5, 0.2), 0.5);
+		for (uc, u) in grid().step_by(97)
+		{
+			if let Some(sample) = bxdf.sample(wo(), uc, u)
+			{
+				assert!(Vec3d::length(sample.f - bxdf.eval(wo(), sample.wi)) < 1e-9);
+				assert!((sample.pdf - bxdf.pdf(wo(), sample.wi)).abs() < 1e-9 * sample.pdf);
+			}
+		}
+	}
+
+	#[test]
+	fn test_pdf_integrates_to_kept_fraction()
+	{
+		let bxdf = coated(0.3, Vec3d::one(), 0.0);
+		let integral = grid().map(|(_, u)| bxdf.pdf(wo(), uniform_hemisphere(u)) * 2.0 * PI).sum::<f64>() / (N * N) as f64;
+		let kept = grid().filter(|(uc, u)| bxdf.sample(wo(), *uc, *u).is_some()).count() as f64 / (N * N) as f64;
+		assert!((integral - kept).abs() < 0.01, "{} {}", integral, kept);
+	}
+
+	#[test]
+	fn test_energy_conservation()
+	{
+		/* a white base under a clear coat loses what the coat reflects back down, only about 1 / eta² of it leaves */
+		for roughness in [0.0, 0.3]
+		{
+			let albedo = albedo(&coated(roughness, Vec3d::one(), 0.0));
+			assert!(albedo.x() <= 1.0 && albedo.x() > 0.4, "{} {:?}", roughness, albedo);
+		}
+	}
+
+	#[test]
+	fn test_absorption_tints_the_base()
+	{
+		let clear = albedo(&coated(0.0, Vec3d::one(), 1.0));
+		let tinted = albedo(&coated(0.0, Vec3d::new(1.0, 0.5, 0.1), 1.0));
+		assert!((tinted.x() - clear.x()).abs() < 1e-9);
+		assert!(tinted.x() > tinted.y() && tinted.y() > tinted.z());
+	}
+}
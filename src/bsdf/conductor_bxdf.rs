@@ -0,0 +1,224 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use super::
+{
+	Bxdf,
+	BsdfSample,
+	LOBE_GLOSSY,
+	LOBE_REFLECTION,
+	LOBE_SPECULAR,
+	cos_theta,
+	fresnel::
+	{
+		FilmFresnel,
+		fr_complex_rgb,
+	},
+	microfacet::TrowbridgeReitz,
+	same_hemisphere,
+};
+
+pub struct ConductorBxdf
+{
+	distribution: TrowbridgeReitz,
+	eta: Vec3d,
+	k: Vec3d,
+	film: Option<FilmFresnel>,
+}
+
+impl ConductorBxdf
+{
+	pub fn new(distribution: TrowbridgeReitz, eta: Vec3d, k: Vec3d) -> Self
+	{
+		Self{distribution, eta, k, film: None}
+	}
+
+	pub fn with_film(mut self, film: FilmFresnel) -> Self
+	{
+		self.film = Some(film);
+		self
+	}
+
+	fn fresnel(&self, cos_theta_i: f64) -> Vec3d
+	{
+		match &self.film
+		{
+			None => fr_complex_rgb(cos_theta_i, self.eta, self.k),
+			Some(film) => film.eval(cos_theta_i, self.eta, self.k),
+		}
+	}
+}
+
+impl Bxdf for ConductorBxdf
+{
+	fn eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		if !same_hemisphere(wo, wi) || self.distribution.effectively_smooth()
+		{
+			return Vec3d::zero();
+		}
+
+		let cos_theta_o = f64::abs(cos_theta(wo));
+		let cos_theta_i = f64::abs(cos_theta(wi));
+		if cos_theta_i == 0.0 || cos_theta_o == 0.0
+		{
+			return Vec3d::zero();
+		}
+
+		let wm = wi + wo;
+		if Vec3d::dot(wm, wm) == 0.0
+		{
+			return Vec3d::zero();
+		}
+		let wm = Vec3d::normalize(wm);
+
+		let f = self.fresnel(f64::abs(Vec3d::dot(wo, wm)));
+		f * (self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * cos_theta_o))
+	}
+
+	fn sample(&self, wo: Vec3d, _uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		if self.distribution.effectively_smooth()
+		{
+			let wi = Vec3d::new(-wo.x(), -wo.y(), wo.z());
+			return Some(BsdfSample::new(wi, self.fresnel(f64::abs(cos_theta(wi))), 1.0, LOBE_SPECULAR | LOBE_REFLECTION));
+		}
+
+		if cos_theta(wo) == 0.0
+		{
+			return None;
+		}
+
+		let wm = self.distribution.sample_wm(wo, u);
+		let wi = Vec3d::reflect(-wo, wm);
+		if !same_hemisphere(wo, wi)
+		{
+			return None;
+		}
+
+		Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi), LOBE_GLOSSY | LOBE_REFLECTION))
+	}
+
+	fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		if !same_hemisphere(wo, wi) || self.distribution.effectively_smooth()
+		{
+			return 0.0;
+		}
+
+		let wm = wo + wi;
+		if Vec3d::dot(wm, wm) == 0.0
+		{
+			return 0.0;
+		}
+
+		let mut wm = Vec3d::normalize(wm);
+		if wm.z() < 0.0
+		{
+			wm = -wm;
+		}
+
+		self.distribution.pdf(wo, wm) / (4.0 * f64::abs(Vec3d::dot(wo, wm)))
+	}
+
+	fn flags(&self) -> u32
+	{
+		if self.distribution.effectively_smooth()
+		{
+			LOBE_SPECULAR | LOBE_REFLECTION
+		}
+		else
+		{
+			LOBE_GLOSSY | LOBE_REFLECTION
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::bsdf::uniform_hemisphere;
+
+	use std::f64::consts::PI;
+
+	const N: usize = 200;
+
+	fn grid() -> impl Iterator<Item = Vec2d>
+	{
+		(0..N * N).map(|i| Vec2d::new(((i % N) as f64 + 0.5) / N as f64, ((i / N) as f64 + 0.5) / N as f64))
+	}
+
+	fn wo() -> Vec3d
+	{
+		Vec3d::normalize(Vec3d::new(0.4, 0.1, 0.8))
+	}
+
+	/* gold */
+	fn conductor(roughness: f64) -> ConductorBxdf
+	{
+		ConductorBxdf::new(TrowbridgeReitz::from_roughness(roughness, roughness), Vec3d::new(0.143, 0.374, 1.442), Vec3d::new(3.983, 2.385, 1.603))
+	}
+
+	#[test]
+	fn test_smooth_conductor_is_a_mirror()
+	{
+		let bxdf = conductor(0.0);
+		let sample = bxdf.sample(wo(), 0.5, Vec2d::new(0.3, 0.7)).unwrap();
+		assert_eq!(sample.flags, LOBE_SPECULAR | LOBE_REFLECTION);
+		assert_eq!(sample.wi, Vec3d::new(-wo().x(), -wo().y(), wo().z()));
+		assert_eq!(sample.f, fr_complex_rgb(wo().z(), bxdf.eta, bxdf.k));
+		/* gold reflects red more than blue */
+		assert!(sample.f.x() > sample.f.z());
+	}
+
+	#[test]
+	fn test_sample_matches_eval_and_pdf()
+	{
+		let bxdf = conductor(0.3);
+		assert_eq!(bxdf.flags(), LOBE_GLOSSY | LOBE_REFLECTION);
+		for u in grid().step_by(97)
+		{
+			if let Some(sample) = bxdf.sample(wo(), 0.5, u)
+			{
+				assert!(Vec3d::length(sample.f - bxdf.eval(wo(), sample.wi)) < 1e-9);
+				assert!((sample.pdf - bxdf.pdf(wo(), sample.wi)).abs() < 1e-9 * sample.pdf);
+			}
+		}
+	}
+
+	#[test]
+	fn test_reciprocity()
+	{
+		/* eval includes the cosine of wi */
+		let bxdf = conductor(0.4);
+		let wi = Vec3d::normalize(Vec3d::new(-0.6, 0.3, 0.5));
+		let a = bxdf.eval(wo(), wi) / wi.z();
+		let b = bxdf.eval(wi, wo()) / wo().z();
+		assert!(Vec3d::length(a - b) < 1e-9);
+	}
+
+	#[test]
+	fn test_pdf_integrates_to_kept_fraction()
+	{
+		let bxdf = conductor(0.3);
+		let integral = grid().map(|u| bxdf.pdf(wo(), uniform_hemisphere(u)) * 2.0 * PI).sum::<f64>() / (N * N) as f64;
+		let kept = grid().filter(|u| bxdf.sample(wo(), 0.5, *u).is_some()).count() as f64 / (N * N) as f64;
+		assert!((integral - kept).abs() < 0.01, "{} {}", integral, kept);
+	}
+
+	#[test]
+	fn test_energy_conservation()
+	{
+		for roughness in [0.05, 0.3, 1.0]
+		{
+			let bxdf = conductor(roughness);
+			let albedo = grid().filter_map(|u| bxdf.sample(wo(), 0.5, u)).map(|sample| sample.f / sample.pdf).fold(Vec3d::zero(), |sum, f| sum + f) / (N * N) as f64;
+			assert!(albedo.x() <= 1.0 && albedo.x() > albedo.z(), "{} {:?}", roughness, albedo);
+		}
+	}
+}
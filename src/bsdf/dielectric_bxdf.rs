@@ -0,0 +1,201 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use super::
+{
+	Bxdf,
+	BsdfSample,
+	LOBE_GLOSSY,
+	LOBE_REFLECTION,
+	LOBE_SPECULAR,
+	LOBE_TRANSMISSION,
+	cos_theta,
+	fresnel::
+	{
+		FilmFresnel,
+		fr_dielectric,
+		refract,
+	},
+	microfacet::TrowbridgeReitz,
+	same_hemisphere,
+};
+
+/* smooth or rough dielectric, eta is the index of the far side of the interface relative to the side of the normal */
+pub struct DielectricBxdf
+{
+	eta: f64,
+	distribution: TrowbridgeReitz,
+	tint: Vec3d,
+	film: Option<FilmFresnel>,
+}
+
+impl DielectricBxdf
+{
+	/* tint scales every lobe, used for absorption along the path that reached the interface */
+	pub fn new(eta: f64, distribution: TrowbridgeReitz, tint: Vec3d) -> Self
+	{
+		Self{eta, distribution, tint, film: None}
+	}
+
+	pub fn with_film(mut self, film: FilmFresnel) -> Self
+	{
+		self.film = Some(film);
+		self
+	}
+
+	/* reflectance, colored by interference when there is a film on the interface */
+	fn fresnel(&self, cos_theta_i: f64) -> Vec3d
+	{
+		match &self.film
+		{
+			None => Vec3d::newv(fr_dielectric(cos_theta_i, self.eta)),
+			Some(film) => film.eval(cos_theta_i, Vec3d::newv(self.eta), Vec3d::zero()),
+		}
+	}
+
+	/* probability of sampling reflection over transmission */
+	fn reflection_probability(f: Vec3d) -> f64
+	{
+		(f.x() + f.y() + f.z()) / 3.0
+	}
+
+	fn is_smooth(&self) -> bool
+	{
+		self.eta == 1.0 || self.distribution.effectively_smooth()
+	}
+
+	/* half vector of a reflection or refraction pair, None for back facing microfacets */
+	fn half_vector(&self, wo: Vec3d, wi: Vec3d) -> Option<(Vec3d, f64, bool)>
+	{
+		let cos_theta_o = cos_theta(wo);
+		let cos_theta_i = cos_theta(wi);
+		let reflect = cos_theta_i * cos_theta_o > 0.0;
+		let etap = if reflect { 1.0 } else if cos_theta_o > 0.0 { self.eta } else { 1.0 / self.eta };
+
+		let wm = wi * etap + wo;
+		if cos_theta_i == 0.0 || cos_theta_o == 0.0 || Vec3d::dot(wm, wm) == 0.0
+		{
+			return None;
+		}
+
+		let mut wm = Vec3d::normalize(wm);
+		if wm.z() < 0.0
+		{
+			wm = -wm;
+		}
+
+		if Vec3d::dot(wm, wi) * cos_theta_i < 0.0 || Vec3d::dot(wm, wo) * cos_theta_o < 0.0
+		{
+			return None;
+		}
+
+		Some((wm, etap, reflect))
+	}
+}
+
+impl Bxdf for DielectricBxdf
+{
+	fn eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		if self.is_smooth()
+		{
+			return Vec3d::zero();
+		}
+
+		let (wm, etap, reflect) = match self.half_vector(wo, wi)
+		{
+			None => return Vec3d::zero(),
+			Some(h) => h,
+		};
+
+		let cos_theta_o = cos_theta(wo);
+		let f = self.fresnel(Vec3d::dot(wo, wm));
+		let dg = self.distribution.d(wm) * self.distribution.g(wo, wi);
+		if reflect
+		{
+			return self.tint * f * (dg / f64::abs(4.0 * cos_theta_o));
+		}
+
+		let denom = (Vec3d::dot(wi, wm) + Vec3d::dot(wo, wm) / etap).powi(2) * cos_theta_o;
+		let ft = dg * f64::abs(Vec3d::dot(wi, wm) * Vec3d::dot(wo, wm) / denom);
+		self.tint * (Vec3d::one() - f) * (ft / (etap * etap))
+	}
+
+	fn sample(&self, wo: Vec3d, uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		if self.is_smooth()
+		{
+			let f = self.fresnel(cos_theta(wo));
+			let r = Self::reflection_probability(f);
+			if uc < r
+			{
+				let wi = Vec3d::new(-wo.x(), -wo.y(), wo.z());
+				return Some(BsdfSample::new(wi, self.tint * f, r, LOBE_SPECULAR | LOBE_REFLECTION));
+			}
+
+			let (wi, etap) = refract(wo, Vec3d::new(0.0, 0.0, 1.0), self.eta)?;
+			return Some(BsdfSample::new(wi, self.tint * (Vec3d::one() - f) / (etap * etap), 1.0 - r, LOBE_SPECULAR | LOBE_TRANSMISSION));
+		}
+
+		let wm = self.distribution.sample_wm(wo, u);
+		let r = Self::reflection_probability(self.fresnel(Vec3d::dot(wo, wm)));
+		if uc < r
+		{
+			let wi = Vec3d::reflect(-wo, wm);
+			if !same_hemisphere(wo, wi)
+			{
+				return None;
+			}
+
+			return Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi), LOBE_GLOSSY | LOBE_REFLECTION));
+		}
+
+		let (wi, _etap) = refract(wo, wm, self.eta)?;
+		if same_hemisphere(wo, wi) || cos_theta(wi) == 0.0
+		{
+			return None;
+		}
+
+		Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi), LOBE_GLOSSY | LOBE_TRANSMISSION))
+	}
+
+	fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		if self.is_smooth()
+		{
+			return 0.0;
+		}
+
+		let (wm, etap, reflect) = match self.half_vector(wo, wi)
+		{
+			None => return 0.0,
+			Some(h) => h,
+		};
+
+		let r = Self::reflection_probability(self.fresnel(Vec3d::dot(wo, wm)));
+		if reflect
+		{
+			return self.distribution.pdf(wo, wm) / (4.0 * f64::abs(Vec3d::dot(wo, wm))) * r;
+		}
+
+		let denom = (Vec3d::dot(wi, wm) + Vec3d::dot(wo, wm) / etap).powi(2);
+		let dwm_dwi = f64::abs(Vec3d::dot(wi, wm)) / denom;
+		self.distribution.pdf(wo, wm) * dwm_dwi * (1.0 - r)
+	}
+
+	fn flags(&self) -> u32
+	{
+		let flags = if self.eta == 1.0 && self.film.is_none() { LOBE_TRANSMISSION } else { LOBE_REFLECTION | LOBE_TRANSMISSION };
+		if self.is_smooth()
+		{
+			flags | LOBE_SPECULAR
+		}
+		else
+		{
+			flags | LOBE_GLOSSY
+		}
+	}
+}
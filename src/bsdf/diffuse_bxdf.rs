@@ -0,0 +1,70 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use std::f64::consts::PI;
+
+use super::
+{
+	Bxdf,
+	BsdfSample,
+	LOBE_DIFFUSE,
+	LOBE_REFLECTION,
+	cos_theta,
+	cosine_hemisphere,
+	same_hemisphere,
+};
+
+pub struct DiffuseBxdf
+{
+	r: Vec3d,
+}
+
+impl DiffuseBxdf
+{
+	pub fn new(r: Vec3d) -> Self
+	{
+		Self{r}
+	}
+}
+
+impl Bxdf for DiffuseBxdf
+{
+	fn eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		if !same_hemisphere(wo, wi)
+		{
+			return Vec3d::zero();
+		}
+
+		self.r * (f64::abs(cos_theta(wi)) / PI)
+	}
+
+	fn sample(&self, wo: Vec3d, _uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		let mut wi = cosine_hemisphere(u);
+		if cos_theta(wo) < 0.0
+		{
+			wi = -wi;
+		}
+
+		Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi), LOBE_DIFFUSE | LOBE_REFLECTION))
+	}
+
+	fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		if !same_hemisphere(wo, wi)
+		{
+			return 0.0;
+		}
+
+		f64::abs(cos_theta(wi)) / PI
+	}
+
+	fn flags(&self) -> u32
+	{
+		LOBE_DIFFUSE | LOBE_REFLECTION
+	}
+}
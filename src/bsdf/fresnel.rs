@@ -0,0 +1,355 @@
+use crate::math::vec::Vec3d;
+use crate::spectrum::
+{
+	SampledWavelengths,
+	reflectance_to_rgb,
+};
+
+use std::cell::Cell;
+use std::f64::consts::PI;
+
+#[derive(Debug, Copy, Clone)]
+struct Complex
+{
+	re: f64,
+	im: f64,
+}
+
+impl Complex
+{
+	fn new(re: f64, im: f64) -> Self
+	{
+		Self{re, im}
+	}
+
+	fn add(a: Self, b: Self) -> Self
+	{
+		Self::new(a.re + b.re, a.im + b.im)
+	}
+
+	fn sub(a: Self, b: Self) -> Self
+	{
+		Self::new(a.re - b.re, a.im - b.im)
+	}
+
+	fn mul(a: Self, b: Self) -> Self
+	{
+		Self::new(a.re * b.re - a.im * b.im, a.re * b.im + a.im * b.re)
+	}
+
+	fn div(a: Self, b: Self) -> Self
+	{
+		let scale = 1.0 / (b.re * b.re + b.im * b.im);
+		Self::new(scale * (a.re * b.re + a.im * b.im), scale * (a.im * b.re - a.re * b.im))
+	}
+
+	fn exp(self) -> Self
+	{
+		let r = f64::exp(self.re);
+		Self::new(r * f64::cos(self.im), r * f64::sin(self.im))
+	}
+
+	fn norm(self) -> f64
+	{
+		self.re * self.re + self.im * self.im
+	}
+
+	fn sqrt(self) -> Self
+	{
+		let n = f64::sqrt(self.norm());
+		if n == 0.0
+		{
+			return Self::new(0.0, 0.0);
+		}
+
+		let t1 = f64::sqrt(0.5 * (n + f64::abs(self.re)));
+		let t2 = 0.5 * self.im / t1;
+		if self.re >= 0.0
+		{
+			Self::new(t1, t2)
+		}
+		else
+		{
+			Self::new(f64::abs(t2), f64::copysign(t1, self.im))
+		}
+	}
+}
+
+/* unpolarized Fresnel reflectance of a conductor with complex index of refraction eta + i k */
+pub fn fr_complex(cos_theta_i: f64, eta: f64, k: f64) -> f64
+{
+	let cos_theta_i = f64::clamp(cos_theta_i, 0.0, 1.0);
+	let eta = Complex::new(eta, k);
+	let cos_i = Complex::new(cos_theta_i, 0.0);
+	let sin2_theta_i = Complex::new(1.0 - cos_theta_i * cos_theta_i, 0.0);
+	let sin2_theta_t = Complex::div(sin2_theta_i, Complex::mul(eta, eta));
+	let cos_t = Complex::sub(Complex::new(1.0, 0.0), sin2_theta_t).sqrt();
+
+	let eta_cos_i = Complex::mul(eta, cos_i);
+	let eta_cos_t = Complex::mul(eta, cos_t);
+	let r_parl = Complex::div(Complex::sub(eta_cos_i, cos_t), Complex::add(eta_cos_i, cos_t));
+	let r_perp = Complex::div(Complex::sub(cos_i, eta_cos_t), Complex::add(cos_i, eta_cos_t));
+	(r_parl.norm() + r_perp.norm()) / 2.0
+}
+
+pub fn fr_complex_rgb(cos_theta_i: f64, eta: Vec3d, k: Vec3d) -> Vec3d
+{
+	Vec3d::new(fr_complex(cos_theta_i, eta.x(), k.x()),
+	           fr_complex(cos_theta_i, eta.y(), k.y()),
+	           fr_complex(cos_theta_i, eta.z(), k.z()))
+}
+
+/* unpolarized Fresnel reflectance of a dielectric interface, eta is the relative index of the transmitted side */
+pub fn fr_dielectric(cos_theta_i: f64, eta: f64) -> f64
+{
+	let mut cos_theta_i = f64::clamp(cos_theta_i, -1.0, 1.0);
+	let mut eta = eta;
+	if cos_theta_i < 0.0
+	{
+		eta = 1.0 / eta;
+		cos_theta_i = -cos_theta_i;
+	}
+
+	let sin2_theta_i = 1.0 - cos_theta_i * cos_theta_i;
+	let sin2_theta_t = sin2_theta_i / (eta * eta);
+	if sin2_theta_t >= 1.0
+	{
+		return 1.0;
+	}
+
+	let cos_theta_t = f64::sqrt(f64::max(0.0, 1.0 - sin2_theta_t));
+	let r_parl = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+	let r_perp = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+	(r_parl * r_parl + r_perp * r_perp) / 2.0
+}
+
+/* refracts wi about n, returns the transmitted direction and the relative index along it */
+pub fn refract(wi: Vec3d, n: Vec3d, eta: f64) -> Option<(Vec3d, f64)>
+{
+	let mut cos_theta_i = Vec3d::dot(n, wi);
+	let mut eta = eta;
+	let mut n = n;
+	if cos_theta_i < 0.0
+	{
+		eta = 1.0 / eta;
+		cos_theta_i = -cos_theta_i;
+		n = -n;
+	}
+
+	let sin2_theta_i = f64::max(0.0, 1.0 - cos_theta_i * cos_theta_i);
+	let sin2_theta_t = sin2_theta_i / (eta * eta);
+	if sin2_theta_t >= 1.0
+	{
+		return None;
+	}
+
+	let cos_theta_t = f64::sqrt(1.0 - sin2_theta_t);
+	Some((-wi / eta + n * (cos_theta_i / eta - cos_theta_t), eta))
+}
+
+pub fn schlick_weight(cos_theta: f64) -> f64
+{
+	f64::powi(f64::clamp(1.0 - cos_theta, 0.0, 1.0), 5)
+}
+
+/* Schlick approximation from the reflectance at normal incidence */
+pub fn fr_schlick(f0: Vec3d, cos_theta: f64) -> Vec3d
+{
+	f0 + (Vec3d::one() - f0) * schlick_weight(cos_theta)
+}
+
+/*
+ * thin dielectric film on top of an interface, eta is the index of the film relative to the incident side
+ * and thickness is the optical thickness of the film in nanometers as measured in the incident medium
+ */
+#[derive(Debug, Copy, Clone)]
+pub struct ThinFilm
+{
+	eta: f64,
+	thickness: f64,
+}
+
+impl ThinFilm
+{
+	/* absolute indices of refraction, thickness in nanometers */
+	pub fn new(film_ior: f64, thickness: f64, incident_ior: f64) -> Self
+	{
+		Self{eta: film_ior / incident_ior, thickness: thickness * incident_ior}
+	}
+
+	/* Airy summation of the waves reflected inside the film over a substrate of relative index eta + i k */
+	pub fn reflectance(&self, cos_theta_i: f64, eta: f64, k: f64, lambda: f64) -> f64
+	{
+		/* seen from the substrate side, only meaningful for dielectrics */
+		if cos_theta_i < 0.0
+		{
+			return Self{eta: self.eta / eta, thickness: self.thickness * eta}.reflectance(-cos_theta_i, 1.0 / eta, 0.0, lambda);
+		}
+
+		let cos_theta_i = f64::min(cos_theta_i, 1.0);
+		let one = Complex::new(1.0, 0.0);
+		let sin2_theta_i = Complex::new(1.0 - cos_theta_i * cos_theta_i, 0.0);
+		let cos_t = |n: Complex| Complex::sub(one, Complex::div(sin2_theta_i, Complex::mul(n, n))).sqrt();
+
+		let n1 = one;
+		let n2 = Complex::new(self.eta, 0.0);
+		let n3 = Complex::new(eta, k);
+		let cos1 = Complex::new(cos_theta_i, 0.0);
+		let cos2 = cos_t(n2);
+		let cos3 = cos_t(n3);
+
+		let r_perp = |na: Complex, ca: Complex, nb: Complex, cb: Complex|
+		{
+			Complex::div(Complex::sub(Complex::mul(na, ca), Complex::mul(nb, cb)), Complex::add(Complex::mul(na, ca), Complex::mul(nb, cb)))
+		};
+		let r_parl = |na: Complex, ca: Complex, nb: Complex, cb: Complex|
+		{
+			Complex::div(Complex::sub(Complex::mul(nb, ca), Complex::mul(na, cb)), Complex::add(Complex::mul(nb, ca), Complex::mul(na, cb)))
+		};
+
+		/* phase difference of one round trip through the film */
+		let phase = Complex::mul(Complex::new(0.0, 4.0 * PI * self.thickness / lambda), Complex::mul(n2, cos2)).exp();
+		let airy = |r12: Complex, r23: Complex|
+		{
+			let r23 = Complex::mul(r23, phase);
+			Complex::div(Complex::add(r12, r23), Complex::add(one, Complex::mul(r12, r23))).norm()
+		};
+
+		(airy(r_perp(n1, cos1, n2, cos2), r_perp(n2, cos2, n3, cos3)) + airy(r_parl(n1, cos1, n2, cos2), r_parl(n2, cos2, n3, cos3))) / 2.0
+	}
+
+	/* a substrate index given at red, green and blue wavelengths interpolated in between */
+	fn index_at(c: Vec3d, lambda: f64) -> f64
+	{
+		let t = f64::clamp((lambda - 450.0) / 100.0, 0.0, 2.0);
+		if t < 1.0 { c.z() + (c.y() - c.z()) * t } else { c.y() + (c.x() - c.y()) * (t - 1.0) }
+	}
+
+	pub fn reflectance_rgb(&self, cos_theta_i: f64, eta: Vec3d, k: Vec3d) -> Vec3d
+	{
+		let rgb = reflectance_to_rgb(&|lambda| self.reflectance(cos_theta_i, Self::index_at(eta, lambda), Self::index_at(k, lambda), lambda));
+
+		/* saturated interference colors can fall outside of the rgb gamut */
+		rgb.for_each(&|c| f64::clamp(c, 0.0, 1.0))
+	}
+}
+
+/*
+ * reflectance of a film as seen by a bxdf, at the hero wavelength of a spectral path
+ * or in rgb for rgb paths, where the last angle is remembered as sampling asks for it repeatedly
+ */
+pub struct FilmFresnel
+{
+	film: ThinFilm,
+	lambda: Option<f64>,
+	last: Cell<Option<(f64, Vec3d)>>,
+}
+
+impl FilmFresnel
+{
+	/* interference is wavelength dependent, a spectral path only keeps its hero wavelength */
+	pub fn new(film: ThinFilm, lambda: &mut SampledWavelengths) -> Self
+	{
+		let hero = if lambda.spectral()
+		{
+			lambda.terminate_secondary();
+			Some(lambda.hero())
+		}
+		else
+		{
+			None
+		};
+
+		Self{film, lambda: hero, last: Cell::new(None)}
+	}
+
+	pub fn eval(&self, cos_theta_i: f64, eta: Vec3d, k: Vec3d) -> Vec3d
+	{
+		if let Some(lambda) = self.lambda
+		{
+			return Vec3d::newv(self.film.reflectance(cos_theta_i, ThinFilm::index_at(eta, lambda), ThinFilm::index_at(k, lambda), lambda));
+		}
+
+		if let Some((cos_theta, rgb)) = self.last.get()
+		{
+			if cos_theta == cos_theta_i
+			{
+				return rgb;
+			}
+		}
+
+		let rgb = self.film.reflectance_rgb(cos_theta_i, eta, k);
+		self.last.set(Some((cos_theta_i, rgb)));
+		rgb
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	#[test]
+	fn test_fr_dielectric()
+	{
+		assert!((fr_dielectric(1.0, 1.5) - 0.04).abs() < 1e-12);
+		assert_eq!(fr_dielectric(0.1, 1.0 / 1.5), 1.0);
+		assert!((fr_dielectric(-0.7, 1.5) - fr_dielectric(0.7, 1.0 / 1.5)).abs() < 1e-12);
+	}
+
+	#[test]
+	fn test_fr_complex_without_absorption()
+	{
+		for cos_theta in [0.1, 0.5, 0.9, 1.0]
+		{
+			assert!((fr_complex(cos_theta, 1.5, 0.0) - fr_dielectric(cos_theta, 1.5)).abs() < 1e-12);
+		}
+	}
+
+	#[test]
+	fn test_zero_thickness_film()
+	{
+		let film = ThinFilm::new(1.33, 0.0, 1.0);
+		for cos_theta in [0.1, 0.5, 0.9, 1.0]
+		{
+			for lambda in [400.0, 550.0, 700.0]
+			{
+				assert!((film.reflectance(cos_theta, 1.5, 0.0, lambda) - fr_dielectric(cos_theta, 1.5)).abs() < 1e-9);
+				assert!((film.reflectance(cos_theta, 0.2, 3.9, lambda) - fr_complex(cos_theta, 0.2, 3.9)).abs() < 1e-9);
+			}
+		}
+
+		/* from the substrate side */
+		assert!((film.reflectance(-0.9, 1.5, 0.0, 550.0) - fr_dielectric(-0.9, 1.5)).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_quarter_wave_film()
+	{
+		/* a quarter wave coating with the geometric mean index cancels the reflection */
+		let n = f64::sqrt(1.5);
+		let film = ThinFilm::new(n, 550.0 / (4.0 * n), 1.0);
+		assert!(film.reflectance(1.0, 1.5, 0.0, 550.0) < 1e-9);
+		assert!(film.reflectance(1.0, 1.5, 0.0, 400.0) > 1e-3);
+	}
+
+	#[test]
+	fn test_film_fresnel_wavelengths()
+	{
+		let film = ThinFilm::new(1.33, 300.0, 1.0);
+
+		let mut lambda = SampledWavelengths::sample_uniform(0.3, true);
+		let fresnel = FilmFresnel::new(film, &mut lambda);
+		assert!(lambda.secondary_terminated());
+		let f = fresnel.eval(0.8, Vec3d::newv(1.5), Vec3d::zero());
+		assert_eq!(f, Vec3d::newv(film.reflectance(0.8, 1.5, 0.0, lambda.hero())));
+
+		let mut lambda = SampledWavelengths::sample_uniform(0.3, false);
+		let fresnel = FilmFresnel::new(film, &mut lambda);
+		assert!(!lambda.secondary_terminated());
+		let f = fresnel.eval(0.8, Vec3d::newv(1.5), Vec3d::zero());
+		assert_eq!(f, film.reflectance_rgb(0.8, Vec3d::newv(1.5), Vec3d::zero()));
+		assert_eq!(fresnel.eval(0.8, Vec3d::newv(1.5), Vec3d::zero()), f);
+	}
+}
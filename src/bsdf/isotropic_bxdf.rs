@@ -0,0 +1,84 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use std::f64::consts::PI;
+
+use super::
+{
+	Bxdf,
+	BsdfSample,
+	LOBE_DIFFUSE,
+	LOBE_REFLECTION,
+	uniform_sphere,
+};
+
+/* isotropic phase function of a participating medium, there is no cosine term */
+pub struct IsotropicBxdf
+{
+	albedo: Vec3d,
+}
+
+impl IsotropicBxdf
+{
+	pub fn new(albedo: Vec3d) -> Self
+	{
+		Self{albedo}
+	}
+}
+
+impl Bxdf for IsotropicBxdf
+{
+	fn eval(&self, _wo: Vec3d, _wi: Vec3d) -> Vec3d
+	{
+		self.albedo / (4.0 * PI)
+	}
+
+	fn sample(&self, wo: Vec3d, _uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		let wi = uniform_sphere(u);
+		/* scattering inside a volume does not cross a surface, so the path stays in its medium */
+		Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi), LOBE_DIFFUSE | LOBE_REFLECTION))
+	}
+
+	fn pdf(&self, _wo: Vec3d, _wi: Vec3d) -> f64
+	{
+		1.0 / (4.0 * PI)
+	}
+
+	fn flags(&self) -> u32
+	{
+		LOBE_DIFFUSE | LOBE_REFLECTION
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	#[test]
+	fn test_flags_match_samples()
+	{
+		let bxdf = IsotropicBxdf::new(Vec3d::newv(0.5));
+		let sample = bxdf.sample(Vec3d::new(0.0, 0.0, 1.0), 0.5, Vec2d::new(0.9, 0.3)).unwrap();
+		assert_eq!(sample.flags, bxdf.flags());
+		assert!(!sample.is_transmission());
+	}
+
+	#[test]
+	fn test_albedo_over_the_sphere()
+	{
+		/* no cosine term, so f / pdf is the albedo in every direction */
+		let bxdf = IsotropicBxdf::new(Vec3d::new(0.2, 0.5, 0.8));
+		for i in 0..16
+		{
+			let u = Vec2d::new((i as f64 + 0.5) / 16.0, 0.37);
+			let sample = bxdf.sample(Vec3d::new(0.0, 0.0, 1.0), 0.5, u).unwrap();
+			assert!(Vec3d::length(sample.f / sample.pdf - Vec3d::new(0.2, 0.5, 0.8)) < 1e-12);
+		}
+	}
+}
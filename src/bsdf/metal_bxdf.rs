@@ -0,0 +1,179 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use super::
+{
+	Bxdf,
+	BsdfSample,
+	LOBE_GLOSSY,
+	LOBE_REFLECTION,
+	LOBE_SPECULAR,
+	cos_theta,
+	microfacet::TrowbridgeReitz,
+	same_hemisphere,
+};
+
+/* a GGX lobe with the fuzz as its roughness tinted by the albedo at every angle, a fuzz of zero is a perfect mirror */
+pub struct MetalBxdf
+{
+	albedo: Vec3d,
+	distribution: TrowbridgeReitz,
+}
+
+impl MetalBxdf
+{
+	pub fn new(albedo: Vec3d, fuzz: f64) -> Self
+	{
+		Self{albedo, distribution: TrowbridgeReitz::from_roughness(fuzz, fuzz)}
+	}
+}
+
+impl Bxdf for MetalBxdf
+{
+	fn eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		if !same_hemisphere(wo, wi) || self.distribution.effectively_smooth()
+		{
+			return Vec3d::zero();
+		}
+
+		let cos_theta_o = f64::abs(cos_theta(wo));
+		let cos_theta_i = f64::abs(cos_theta(wi));
+		let wm = wi + wo;
+		if cos_theta_i == 0.0 || cos_theta_o == 0.0 || Vec3d::dot(wm, wm) == 0.0
+		{
+			return Vec3d::zero();
+		}
+
+		let wm = Vec3d::normalize(wm);
+		self.albedo * (self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * cos_theta_o))
+	}
+
+	fn sample(&self, wo: Vec3d, _uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		if self.distribution.effectively_smooth()
+		{
+			let wi = Vec3d::new(-wo.x(), -wo.y(), wo.z());
+			return Some(BsdfSample::new(wi, self.albedo, 1.0, LOBE_SPECULAR | LOBE_REFLECTION));
+		}
+
+		if cos_theta(wo) == 0.0
+		{
+			return None;
+		}
+
+		let wm = self.distribution.sample_wm(wo, u);
+		let wi = Vec3d::reflect(-wo, wm);
+		if !same_hemisphere(wo, wi)
+		{
+			return None;
+		}
+
+		Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi), LOBE_GLOSSY | LOBE_REFLECTION))
+	}
+
+	fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		if !same_hemisphere(wo, wi) || self.distribution.effectively_smooth()
+		{
+			return 0.0;
+		}
+
+		let wm = wo + wi;
+		if Vec3d::dot(wm, wm) == 0.0
+		{
+			return 0.0;
+		}
+
+		let mut wm = Vec3d::normalize(wm);
+		if wm.z() < 0.0
+		{
+			wm = -wm;
+		}
+
+		self.distribution.pdf(wo, wm) / (4.0 * f64::abs(Vec3d::dot(wo, wm)))
+	}
+
+	fn flags(&self) -> u32
+	{
+		if self.distribution.effectively_smooth()
+		{
+			LOBE_SPECULAR | LOBE_REFLECTION
+		}
+		else
+		{
+			LOBE_GLOSSY | LOBE_REFLECTION
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::bsdf::uniform_hemisphere;
+
+	use std::f64::consts::PI;
+
+	const N: usize = 200;
+
+	fn grid() -> impl Iterator<Item = Vec2d>
+	{
+		(0..N * N).map(|i| Vec2d::new(((i % N) as f64 + 0.5) / N as f64, ((i / N) as f64 + 0.5) / N as f64))
+	}
+
+	fn wo() -> Vec3d
+	{
+		Vec3d::normalize(Vec3d::new(0.4, 0.1, 0.8))
+	}
+
+	#[test]
+	fn test_zero_fuzz_is_specular()
+	{
+		let bxdf = MetalBxdf::new(Vec3d::newv(0.8), 0.0);
+		let sample = bxdf.sample(wo(), 0.5, Vec2d::new(0.3, 0.7)).unwrap();
+		assert_eq!(sample.flags, LOBE_SPECULAR | LOBE_REFLECTION);
+		assert_eq!(sample.wi, Vec3d::new(-wo().x(), -wo().y(), wo().z()));
+		assert_eq!(bxdf.pdf(wo(), sample.wi), 0.0);
+	}
+
+	#[test]
+	fn test_fuzz_is_glossy()
+	{
+		let bxdf = MetalBxdf::new(Vec3d::newv(0.8), 0.3);
+		assert_eq!(bxdf.flags(), LOBE_GLOSSY | LOBE_REFLECTION);
+		for u in grid().step_by(97)
+		{
+			if let Some(sample) = bxdf.sample(wo(), 0.5, u)
+			{
+				assert!(Vec3d::length(sample.f - bxdf.eval(wo(), sample.wi)) < 1e-9);
+				assert!((sample.pdf - bxdf.pdf(wo(), sample.wi)).abs() < 1e-9 * sample.pdf);
+			}
+		}
+	}
+
+	#[test]
+	fn test_pdf_integrates_to_one()
+	{
+		/* directions reflected below the surface are lost, the pdf integrates to the fraction of samples kept */
+		let bxdf = MetalBxdf::new(Vec3d::one(), 0.3);
+		let integral = grid().map(|u| bxdf.pdf(wo(), uniform_hemisphere(u)) * 2.0 * PI).sum::<f64>() / (N * N) as f64;
+		let kept = grid().filter(|u| bxdf.sample(wo(), 0.5, *u).is_some()).count() as f64 / (N * N) as f64;
+		assert!((integral - kept).abs() < 0.01, "{} {}", integral, kept);
+	}
+
+	#[test]
+	fn test_energy_conservation()
+	{
+		for (fuzz, min) in [(0.01, 0.97), (0.3, 0.5), (1.0, 0.2)]
+		{
+			let bxdf = MetalBxdf::new(Vec3d::one(), fuzz);
+			let albedo = grid().filter_map(|u| bxdf.sample(wo(), 0.5, u)).map(|sample| sample.f.x() / sample.pdf).sum::<f64>() / (N * N) as f64;
+			assert!(albedo > min && albedo <= 1.0, "{} {}", fuzz, albedo);
+		}
+	}
+}
@@ -0,0 +1,227 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use std::f64::consts::PI;
+
+/* Trowbridge-Reitz (GGX) microfacet distribution with anisotropic roughness */
+#[derive(Debug, Copy, Clone)]
+pub struct TrowbridgeReitz
+{
+	alpha_x: f64,
+	alpha_y: f64,
+}
+
+impl TrowbridgeReitz
+{
+	pub fn new(alpha_x: f64, alpha_y: f64) -> Self
+	{
+		let mut distribution = Self{alpha_x, alpha_y};
+		if !distribution.effectively_smooth()
+		{
+			/* keep a nearly smooth axis of an anisotropic distribution from dividing by zero */
+			distribution.alpha_x = f64::max(alpha_x, 1e-4);
+			distribution.alpha_y = f64::max(alpha_y, 1e-4);
+		}
+		distribution
+	}
+
+	/* perceptually linear roughness in [0, 1] */
+	pub fn from_roughness(roughness_u: f64, roughness_v: f64) -> Self
+	{
+		Self::new(Self::roughness_to_alpha(roughness_u), Self::roughness_to_alpha(roughness_v))
+	}
+
+	pub fn roughness_to_alpha(roughness: f64) -> f64
+	{
+		f64::sqrt(f64::max(roughness, 0.0))
+	}
+
+	pub fn effectively_smooth(&self) -> bool
+	{
+		f64::max(self.alpha_x, self.alpha_y) < 1e-3
+	}
+
+	fn cos2_theta(w: Vec3d) -> f64
+	{
+		w.z() * w.z()
+	}
+
+	fn tan2_theta(w: Vec3d) -> f64
+	{
+		f64::max(0.0, 1.0 - Self::cos2_theta(w)) / Self::cos2_theta(w)
+	}
+
+	fn cos2_sin2_phi(w: Vec3d) -> (f64, f64)
+	{
+		let sin2_theta = f64::max(0.0, 1.0 - Self::cos2_theta(w));
+		if sin2_theta == 0.0
+		{
+			return (1.0, 0.0);
+		}
+
+		let cos_phi = f64::clamp(w.x() / f64::sqrt(sin2_theta), -1.0, 1.0);
+		let sin_phi = f64::clamp(w.y() / f64::sqrt(sin2_theta), -1.0, 1.0);
+		(cos_phi * cos_phi, sin_phi * sin_phi)
+	}
+
+	pub fn d(&self, wm: Vec3d) -> f64
+	{
+		let tan2_theta = Self::tan2_theta(wm);
+		if !tan2_theta.is_finite()
+		{
+			return 0.0;
+		}
+
+		let cos4_theta = Self::cos2_theta(wm) * Self::cos2_theta(wm);
+		let (cos2_phi, sin2_phi) = Self::cos2_sin2_phi(wm);
+		let e = tan2_theta * (cos2_phi / (self.alpha_x * self.alpha_x) + sin2_phi / (self.alpha_y * self.alpha_y));
+		1.0 / (PI * self.alpha_x * self.alpha_y * cos4_theta * (1.0 + e) * (1.0 + e))
+	}
+
+	pub fn lambda(&self, w: Vec3d) -> f64
+	{
+		let tan2_theta = Self::tan2_theta(w);
+		if !tan2_theta.is_finite()
+		{
+			return 0.0;
+		}
+
+		let (cos2_phi, sin2_phi) = Self::cos2_sin2_phi(w);
+		let alpha2 = cos2_phi * self.alpha_x * self.alpha_x + sin2_phi * self.alpha_y * self.alpha_y;
+		(f64::sqrt(1.0 + alpha2 * tan2_theta) - 1.0) / 2.0
+	}
+
+	pub fn g1(&self, w: Vec3d) -> f64
+	{
+		1.0 / (1.0 + self.lambda(w))
+	}
+
+	pub fn g(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+	}
+
+	/* distribution of normals visible from w, normals facing away from w are hidden */
+	pub fn d_visible(&self, w: Vec3d, wm: Vec3d) -> f64
+	{
+		self.g1(w) / f64::abs(w.z()) * self.d(wm) * f64::max(0.0, Vec3d::dot(w, wm) * f64::signum(w.z()))
+	}
+
+	pub fn pdf(&self, w: Vec3d, wm: Vec3d) -> f64
+	{
+		self.d_visible(w, wm)
+	}
+
+	pub fn sample_wm(&self, w: Vec3d, u: Vec2d) -> Vec3d
+	{
+		/* transform w to the hemispherical configuration */
+		let mut wh = Vec3d::normalize(Vec3d::new(self.alpha_x * w.x(), self.alpha_y * w.y(), w.z()));
+		if wh.z() < 0.0
+		{
+			wh = -wh;
+		}
+
+		let t1 = if wh.z() < 0.99999 { Vec3d::normalize(Vec3d::cross(Vec3d::new(0.0, 0.0, 1.0), wh)) } else { Vec3d::new(1.0, 0.0, 0.0) };
+		let t2 = Vec3d::cross(wh, t1);
+
+		/* uniform disk sample warped to the projection of the visible hemisphere */
+		let r = f64::sqrt(u.x());
+		let phi = 2.0 * PI * u.y();
+		let px = r * f64::cos(phi);
+		let mut py = r * f64::sin(phi);
+		let h = f64::sqrt(1.0 - px * px);
+		let t = (1.0 + wh.z()) / 2.0;
+		py = (1.0 - t) * h + t * py;
+
+		let pz = f64::sqrt(f64::max(0.0, 1.0 - px * px - py * py));
+		let nh = t1 * px + t2 * py + wh * pz;
+		Vec3d::normalize(Vec3d::new(self.alpha_x * nh.x(), self.alpha_y * nh.y(), f64::max(1e-6, nh.z())))
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	const N: usize = 400;
+
+	/* midpoint quadrature over the hemisphere in spherical coordinates */
+	fn integrate(f: impl Fn(Vec3d) -> f64) -> f64
+	{
+		let mut sum = 0.0;
+		for i in 0..N
+		{
+			let theta = (i as f64 + 0.5) / N as f64 * PI / 2.0;
+			for j in 0..N
+			{
+				let phi = (j as f64 + 0.5) / N as f64 * 2.0 * PI;
+				sum += f(Vec3d::new(f64::sin(theta) * f64::cos(phi), f64::sin(theta) * f64::sin(phi), f64::cos(theta))) * f64::sin(theta);
+			}
+		}
+		sum * (PI / 2.0 / N as f64) * (2.0 * PI / N as f64)
+	}
+
+	fn distribution() -> TrowbridgeReitz
+	{
+		TrowbridgeReitz::new(0.3, 0.6)
+	}
+
+	fn wo() -> Vec3d
+	{
+		Vec3d::normalize(Vec3d::new(0.5, -0.2, 0.7))
+	}
+
+	#[test]
+	fn test_projected_area_is_one()
+	{
+		let integral = integrate(|wm| distribution().d(wm) * wm.z());
+		assert!((integral - 1.0).abs() < 0.01, "{}", integral);
+	}
+
+	#[test]
+	fn test_visible_normals_pdf_integrates_to_one()
+	{
+		let integral = integrate(|wm| distribution().pdf(wo(), wm));
+		assert!((integral - 1.0).abs() < 0.01, "{}", integral);
+	}
+
+	#[test]
+	fn test_sampled_normals_follow_the_pdf()
+	{
+		/* the mean of the sampled normals matches the mean under the pdf */
+		let n = 200;
+		let mut mean = Vec3d::zero();
+		for i in 0..n * n
+		{
+			mean += distribution().sample_wm(wo(), Vec2d::new(((i % n) as f64 + 0.5) / n as f64, ((i / n) as f64 + 0.5) / n as f64));
+		}
+		mean /= (n * n) as f64;
+		for axis in 0..3
+		{
+			let expected = integrate(|wm| distribution().pdf(wo(), wm) * wm.v[axis]);
+			assert!((mean.v[axis] - expected).abs() < 0.01, "{} {} {}", axis, mean.v[axis], expected);
+		}
+	}
+
+	#[test]
+	fn test_masking_is_at_most_one()
+	{
+		assert_eq!(distribution().g1(Vec3d::new(0.0, 0.0, 1.0)), 1.0);
+		let grazing = Vec3d::normalize(Vec3d::new(1.0, 0.0, 0.05));
+		assert!(distribution().g1(grazing) < distribution().g1(wo()));
+		assert!(distribution().g(wo(), grazing) <= distribution().g1(grazing));
+	}
+
+	#[test]
+	fn test_roughness()
+	{
+		assert_eq!(TrowbridgeReitz::roughness_to_alpha(0.25), 0.5);
+		assert!(TrowbridgeReitz::from_roughness(0.0, 0.0).effectively_smooth());
+		assert!(!TrowbridgeReitz::from_roughness(0.01, 0.0).effectively_smooth());
+	}
+}
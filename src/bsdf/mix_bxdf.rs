@@ -0,0 +1,210 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+use crate::onb::Onb;
+
+use super::
+{
+	Bsdf,
+	Bxdf,
+	BsdfSample,
+};
+
+/* linear blend of two bsdfs with their own shading frames, a missing bsdf is black */
+pub struct MixBxdf
+{
+	frame: Onb,
+	a: Option<Bsdf>,
+	b: Option<Bsdf>,
+	weight: f64,
+}
+
+impl MixBxdf
+{
+	/* weight is the fraction of b, frame must be the frame of the bsdf holding the mix */
+	pub fn new(frame: Onb, a: Option<Bsdf>, b: Option<Bsdf>, weight: f64) -> Self
+	{
+		Self{frame, a, b, weight}
+	}
+
+	fn components(&self) -> [(&Option<Bsdf>, f64); 2]
+	{
+		[(&self.a, 1.0 - self.weight), (&self.b, self.weight)]
+	}
+}
+
+impl Bxdf for MixBxdf
+{
+	fn eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		let wo = self.frame.local(wo);
+		let wi = self.frame.local(wi);
+		let mut f = Vec3d::zero();
+		for (bsdf, weight) in self.components()
+		{
+			if let Some(bsdf) = bsdf
+			{
+				if weight > 0.0
+				{
+					f += bsdf.eval(wo, wi) * weight;
+				}
+			}
+		}
+		f
+	}
+
+	fn sample(&self, wo: Vec3d, uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		let (bsdf, weight, uc) = if uc < 1.0 - self.weight
+		{
+			(&self.a, 1.0 - self.weight, uc / (1.0 - self.weight))
+		}
+		else
+		{
+			(&self.b, self.weight, (uc - (1.0 - self.weight)) / self.weight)
+		};
+
+		let mut sample = bsdf.as_ref()?.sample(self.frame.local(wo), f64::min(uc, 1.0 - f64::EPSILON), u)?;
+		sample.wi = self.frame.to_local(sample.wi);
+		if sample.is_specular()
+		{
+			sample.f *= weight;
+			sample.pdf *= weight;
+			return Some(sample);
+		}
+
+		Some(BsdfSample::new(sample.wi, self.eval(wo, sample.wi), self.pdf(wo, sample.wi), sample.flags))
+	}
+
+	fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		let wo = self.frame.local(wo);
+		let wi = self.frame.local(wi);
+		let mut pdf = 0.0;
+		for (bsdf, weight) in self.components()
+		{
+			if let Some(bsdf) = bsdf
+			{
+				if weight > 0.0
+				{
+					pdf += bsdf.pdf(wo, wi) * weight;
+				}
+			}
+		}
+		pdf
+	}
+
+	fn flags(&self) -> u32
+	{
+		let mut flags = 0;
+		for (bsdf, weight) in self.components()
+		{
+			if let Some(bsdf) = bsdf
+			{
+				if weight > 0.0
+				{
+					flags |= bsdf.flags();
+				}
+			}
+		}
+		flags
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::bsdf::
+	{
+		conductor_bxdf::ConductorBxdf,
+		diffuse_bxdf::DiffuseBxdf,
+		microfacet::TrowbridgeReitz,
+	};
+
+	const N: usize = 64;
+
+	fn normal() -> Vec3d
+	{
+		Vec3d::normalize(Vec3d::new(0.2, 0.3, 1.0))
+	}
+
+	fn tangent() -> Vec3d
+	{
+		Vec3d::new(1.0, 0.0, 0.0)
+	}
+
+	fn diffuse() -> Option<Bsdf>
+	{
+		Some(Bsdf::new(normal(), tangent(), Box::new(DiffuseBxdf::new(Vec3d::new(0.8, 0.2, 0.1)))))
+	}
+
+	fn conductor(roughness: f64) -> Option<Bsdf>
+	{
+		let bxdf = ConductorBxdf::new(TrowbridgeReitz::from_roughness(roughness, roughness), Vec3d::new(0.2, 0.9, 1.1), Vec3d::new(3.9, 2.5, 2.1));
+		Some(Bsdf::new(normal(), tangent(), Box::new(bxdf)))
+	}
+
+	fn mix(a: Option<Bsdf>, b: Option<Bsdf>, weight: f64) -> MixBxdf
+	{
+		MixBxdf::new(Onb::from_w(normal()), a, b, weight)
+	}
+
+	fn wo() -> Vec3d
+	{
+		Vec3d::normalize(Vec3d::new(0.4, 0.1, 0.8))
+	}
+
+	#[test]
+	fn test_eval_and_pdf_blend_the_components()
+	{
+		let frame = Onb::from_w(normal());
+		let wi = Vec3d::normalize(Vec3d::new(-0.3, 0.2, 0.9));
+		let bxdf = mix(diffuse(), conductor(0.4), 0.25);
+		let (a, b) = (diffuse().unwrap(), conductor(0.4).unwrap());
+		let f = a.eval(frame.local(wo()), frame.local(wi)) * 0.75 + b.eval(frame.local(wo()), frame.local(wi)) * 0.25;
+		let pdf = a.pdf(frame.local(wo()), frame.local(wi)) * 0.75 + b.pdf(frame.local(wo()), frame.local(wi)) * 0.25;
+		assert!(Vec3d::length(bxdf.eval(wo(), wi) - f) < 1e-12);
+		assert!((bxdf.pdf(wo(), wi) - pdf).abs() < 1e-12);
+		assert_eq!(bxdf.flags(), a.flags() | b.flags());
+	}
+
+	#[test]
+	fn test_sample_matches_eval_and_pdf()
+	{
+		let bxdf = mix(diffuse(), conductor(0.4), 0.6);
+		for i in 0..N * N
+		{
+			let u = Vec2d::new(((i % N) as f64 + 0.5) / N as f64, ((i / N) as f64 + 0.5) / N as f64);
+			if let Some(sample) = bxdf.sample(wo(), (i as f64 + 0.5) / (N * N) as f64, u)
+			{
+				assert!(Vec3d::length(sample.f - bxdf.eval(wo(), sample.wi)) < 1e-9);
+				assert!((sample.pdf - bxdf.pdf(wo(), sample.wi)).abs() < 1e-9 * sample.pdf);
+			}
+		}
+	}
+
+	#[test]
+	fn test_specular_samples_carry_the_weight()
+	{
+		let bxdf = mix(diffuse(), conductor(0.0), 0.3);
+		let sample = bxdf.sample(wo(), 0.9, Vec2d::new(0.5, 0.5)).unwrap();
+		let specular = conductor(0.0).unwrap().sample(Onb::from_w(normal()).local(wo()), 0.5, Vec2d::new(0.5, 0.5)).unwrap();
+		assert!(sample.is_specular());
+		assert!(Vec3d::length(sample.f - specular.f * 0.3) < 1e-12);
+		assert!((sample.pdf - 0.3).abs() < 1e-12);
+	}
+
+	#[test]
+	fn test_missing_component_is_black()
+	{
+		let bxdf = mix(diffuse(), None, 0.5);
+		assert!(bxdf.sample(wo(), 0.7, Vec2d::new(0.5, 0.5)).is_none());
+		let wi = Vec3d::normalize(Vec3d::new(-0.3, 0.2, 0.9));
+		let frame = Onb::from_w(normal());
+		assert!(Vec3d::length(bxdf.eval(wo(), wi) - diffuse().unwrap().eval(frame.local(wo()), frame.local(wi)) * 0.5) < 1e-12);
+	}
+}
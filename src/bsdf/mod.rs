@@ -0,0 +1,162 @@
+pub mod coated_bxdf;
+pub mod conductor_bxdf;
+pub mod dielectric_bxdf;
+pub mod diffuse_bxdf;
+pub mod fresnel;
+pub mod isotropic_bxdf;
+pub mod metal_bxdf;
+pub mod microfacet;
+pub mod mix_bxdf;
+pub mod phase_bxdf;
+pub mod principled_bxdf;
+pub mod sheen_bxdf;
+
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+use crate::onb::Onb;
+
+use std::f64::consts::PI;
+
+pub const LOBE_REFLECTION: u32   = 1 << 0;
+pub const LOBE_TRANSMISSION: u32 = 1 << 1;
+pub const LOBE_DIFFUSE: u32      = 1 << 2;
+pub const LOBE_GLOSSY: u32       = 1 << 3;
+pub const LOBE_SPECULAR: u32     = 1 << 4;
+
+pub struct BsdfSample
+{
+	pub wi: Vec3d,
+	pub f: Vec3d,
+	pub pdf: f64,
+	pub flags: u32,
+}
+
+impl BsdfSample
+{
+	pub fn new(wi: Vec3d, f: Vec3d, pdf: f64, flags: u32) -> Self
+	{
+		Self{wi, f, pdf, flags}
+	}
+
+	pub fn is_specular(&self) -> bool
+	{
+		self.flags & LOBE_SPECULAR != 0
+	}
+
+	pub fn is_transmission(&self) -> bool
+	{
+		self.flags & LOBE_TRANSMISSION != 0
+	}
+}
+
+/*
+ * directions are in the local shading frame, z is the normal on the side of the incoming ray
+ * and both wo and wi point away from the surface, f values include the cosine of wi
+ * specular lobes are not evaluated, their samples carry the lobe probability as pdf
+ */
+pub trait Bxdf: Send
+{
+	fn eval(&self, _wo: Vec3d, _wi: Vec3d) -> Vec3d
+	{
+		Vec3d::zero()
+	}
+
+	fn sample(&self, wo: Vec3d, uc: f64, u: Vec2d) -> Option<BsdfSample>;
+
+	fn pdf(&self, _wo: Vec3d, _wi: Vec3d) -> f64
+	{
+		0.0
+	}
+
+	fn flags(&self) -> u32;
+}
+
+pub struct Bsdf
+{
+	frame: Onb,
+	bxdf: Box::<dyn Bxdf>,
+}
+
+impl Bsdf
+{
+	/* the frame has z along the normal and x along the tangent, which orients anisotropic lobes */
+	pub fn new(normal: Vec3d, tangent: Vec3d, bxdf: Box::<dyn Bxdf>) -> Self
+	{
+		Self{frame: Onb::from_wu(normal, tangent), bxdf}
+	}
+
+	/* replaces the bxdf while keeping the shading frame, used to layer materials */
+	pub fn wrap(self, func: impl FnOnce(Box::<dyn Bxdf>) -> Box::<dyn Bxdf>) -> Self
+	{
+		Self{frame: self.frame, bxdf: func(self.bxdf)}
+	}
+
+	pub fn flags(&self) -> u32
+	{
+		self.bxdf.flags()
+	}
+
+	/* true if at least one lobe can be evaluated, so light sampling is worthwhile */
+	pub fn is_non_specular(&self) -> bool
+	{
+		self.flags() & (LOBE_DIFFUSE | LOBE_GLOSSY) != 0
+	}
+
+	pub fn eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		self.bxdf.eval(self.frame.to_local(wo), self.frame.to_local(wi))
+	}
+
+	pub fn sample(&self, wo: Vec3d, uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		let mut sample = self.bxdf.sample(self.frame.to_local(wo), uc, u)?;
+		if sample.pdf <= 0.0 || sample.f == Vec3d::zero()
+		{
+			return None;
+		}
+
+		sample.wi = self.frame.local(sample.wi);
+		Some(sample)
+	}
+
+	pub fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		self.bxdf.pdf(self.frame.to_local(wo), self.frame.to_local(wi))
+	}
+}
+
+pub fn cos_theta(w: Vec3d) -> f64
+{
+	w.z()
+}
+
+pub fn same_hemisphere(a: Vec3d, b: Vec3d) -> bool
+{
+	a.z() * b.z() > 0.0
+}
+
+pub fn cosine_hemisphere(u: Vec2d) -> Vec3d
+{
+	let phi = 2.0 * PI * u.x();
+	let r = f64::sqrt(u.y());
+	Vec3d::new(r * f64::cos(phi), r * f64::sin(phi), f64::sqrt(f64::max(0.0, 1.0 - u.y())))
+}
+
+pub fn uniform_hemisphere(u: Vec2d) -> Vec3d
+{
+	let z = u.x();
+	let r = f64::sqrt(f64::max(0.0, 1.0 - z * z));
+	let phi = 2.0 * PI * u.y();
+	Vec3d::new(r * f64::cos(phi), r * f64::sin(phi), z)
+}
+
+pub fn uniform_sphere(u: Vec2d) -> Vec3d
+{
+	let z = 1.0 - 2.0 * u.x();
+	let r = f64::sqrt(f64::max(0.0, 1.0 - z * z));
+	let phi = 2.0 * PI * u.y();
+	Vec3d::new(r * f64::cos(phi), r * f64::sin(phi), z)
+}
@@ -0,0 +1,90 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+use crate::phase_functions::PhaseFunction;
+
+use std::sync::Arc;
+
+use super::
+{
+	Bxdf,
+	BsdfSample,
+	LOBE_DIFFUSE,
+	LOBE_REFLECTION,
+};
+
+/* phase function of a participating medium scaled by its albedo, there is no cosine term */
+pub struct PhaseBxdf
+{
+	albedo: Vec3d,
+	phase: Arc::<dyn PhaseFunction>,
+}
+
+impl PhaseBxdf
+{
+	pub fn new(albedo: Vec3d, phase: Arc::<dyn PhaseFunction>) -> Self
+	{
+		Self{albedo, phase}
+	}
+}
+
+impl Bxdf for PhaseBxdf
+{
+	fn eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		self.albedo * self.phase.p(wo, wi)
+	}
+
+	fn sample(&self, wo: Vec3d, _uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		let sample = self.phase.sample(wo, u);
+		/* scattering inside a volume does not cross a surface, so the path stays in its medium */
+		Some(BsdfSample::new(sample.wi, self.albedo * sample.p, sample.pdf, LOBE_DIFFUSE | LOBE_REFLECTION))
+	}
+
+	fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		self.phase.pdf(wo, wi)
+	}
+
+	fn flags(&self) -> u32
+	{
+		LOBE_DIFFUSE | LOBE_REFLECTION
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::phase_functions::henyey_greenstein::HenyeyGreenstein;
+
+	#[test]
+	fn test_flags_match_samples()
+	{
+		let bxdf = PhaseBxdf::new(Vec3d::newv(0.5), Arc::new(HenyeyGreenstein::new(0.6)));
+		for i in 0..16
+		{
+			let sample = bxdf.sample(Vec3d::new(0.0, 0.0, 1.0), 0.5, Vec2d::new((i as f64 + 0.5) / 16.0, 0.71)).unwrap();
+			assert_eq!(sample.flags, bxdf.flags());
+			assert!(!sample.is_transmission());
+		}
+	}
+
+	#[test]
+	fn test_sample_matches_eval()
+	{
+		let bxdf = PhaseBxdf::new(Vec3d::new(0.2, 0.5, 0.8), Arc::new(HenyeyGreenstein::new(-0.4)));
+		let wo = Vec3d::normalize(Vec3d::new(0.3, -0.2, 1.0));
+		for i in 0..16
+		{
+			let sample = bxdf.sample(wo, 0.5, Vec2d::new((i as f64 + 0.5) / 16.0, 0.23)).unwrap();
+			assert!(Vec3d::length(sample.f - bxdf.eval(wo, sample.wi)) < 1e-9);
+			assert!((sample.pdf - bxdf.pdf(wo, sample.wi)).abs() < 1e-9);
+			assert!(Vec3d::length(sample.f / sample.pdf - Vec3d::new(0.2, 0.5, 0.8)) < 1e-9);
+		}
+	}
+}
@@ -0,0 +1,391 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use std::f64::consts::PI;
+
+use super::
+{
+	Bxdf,
+	BsdfSample,
+	LOBE_DIFFUSE,
+	LOBE_GLOSSY,
+	LOBE_REFLECTION,
+	LOBE_SPECULAR,
+	LOBE_TRANSMISSION,
+	cos_theta,
+	cosine_hemisphere,
+	dielectric_bxdf::DielectricBxdf,
+	fresnel::
+	{
+		fr_schlick,
+		schlick_weight,
+	},
+	microfacet::TrowbridgeReitz,
+	same_hemisphere,
+};
+
+const LOBE_COUNT: usize = 4;
+
+/* principled parameters resolved at the shading point, all in [0, 1] except eta */
+#[derive(Debug, Copy, Clone)]
+pub struct PrincipledParams
+{
+	pub base_color: Vec3d,
+	pub metallic: f64,
+	pub roughness: f64,
+	pub specular: f64,
+	pub specular_tint: f64,
+	pub sheen: f64,
+	pub sheen_tint: f64,
+	pub clearcoat: f64,
+	pub clearcoat_gloss: f64,
+	pub transmission: f64,
+	pub subsurface: f64,
+	pub anisotropic: f64,
+	pub eta: f64,
+}
+
+/*
+ * Burley's principled BSDF: diffuse with retro-reflection, subsurface and sheen,
+ * an anisotropic specular lobe, a GTR1 clearcoat and a rough dielectric transmission lobe
+ */
+pub struct PrincipledBxdf
+{
+	base_color: Vec3d,
+	roughness: f64,
+	subsurface: f64,
+	sheen: Vec3d,
+	diffuse_weight: f64,
+	specular_f0: Vec3d,
+	specular_weight: f64,
+	distribution: TrowbridgeReitz,
+	clearcoat_weight: f64,
+	clearcoat_alpha: f64,
+	transmission: DielectricBxdf,
+	transmission_weight: f64,
+	/* sampling probabilities of the diffuse, specular, clearcoat and transmission lobes */
+	lobe_pdf: [f64; LOBE_COUNT],
+}
+
+impl PrincipledBxdf
+{
+	pub fn new(params: &PrincipledParams) -> Self
+	{
+		let base_color = params.base_color;
+		let luminance = base_color.luminance();
+		let tint = if luminance > 0.0 { base_color / luminance } else { Vec3d::one() };
+		let sheen = (Vec3d::one() * (1.0 - params.sheen_tint) + tint * params.sheen_tint) * params.sheen;
+		let specular_color = (Vec3d::one() * (1.0 - params.specular_tint) + tint * params.specular_tint) * (0.08 * params.specular);
+		let specular_f0 = specular_color * (1.0 - params.metallic) + base_color * params.metallic;
+
+		let diffuse_weight = (1.0 - params.metallic) * (1.0 - params.transmission);
+		let transmission_weight = (1.0 - params.metallic) * params.transmission;
+		let specular_weight = 1.0 - transmission_weight;
+		let clearcoat_weight = 0.25 * params.clearcoat;
+
+		let alpha = params.roughness * params.roughness;
+		let aspect = f64::sqrt(1.0 - 0.9 * params.anisotropic);
+		let distribution = TrowbridgeReitz::new(alpha / aspect, alpha * aspect);
+		let transmission = DielectricBxdf::new(params.eta, TrowbridgeReitz::new(alpha, alpha), base_color.for_each(&f64::sqrt));
+
+		let mut lobe_pdf = [diffuse_weight * (luminance + sheen.luminance()),
+		                    specular_weight * (0.5 + 0.5 * specular_f0.luminance()),
+		                    clearcoat_weight,
+		                    transmission_weight];
+		let total: f64 = lobe_pdf.iter().sum();
+		if total > 0.0
+		{
+			lobe_pdf.iter_mut().for_each(|p| *p /= total);
+		}
+
+		Self{base_color,
+		     roughness: params.roughness,
+		     subsurface: params.subsurface,
+		     sheen,
+		     diffuse_weight,
+		     specular_f0,
+		     specular_weight,
+		     distribution,
+		     clearcoat_weight,
+		     clearcoat_alpha: 0.1 * (1.0 - params.clearcoat_gloss) + 0.001 * params.clearcoat_gloss,
+		     transmission,
+		     transmission_weight,
+		     lobe_pdf}
+	}
+
+	fn half_vector(wo: Vec3d, wi: Vec3d) -> Option<Vec3d>
+	{
+		let wm = wo + wi;
+		if Vec3d::dot(wm, wm) == 0.0
+		{
+			return None;
+		}
+
+		let wm = Vec3d::normalize(wm);
+		Some(if wm.z() < 0.0 { -wm } else { wm })
+	}
+
+	fn diffuse_eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		if !same_hemisphere(wo, wi)
+		{
+			return Vec3d::zero();
+		}
+
+		let cos_theta_o = f64::abs(cos_theta(wo));
+		let cos_theta_i = f64::abs(cos_theta(wi));
+		let wm = match Self::half_vector(wo, wi)
+		{
+			None => return Vec3d::zero(),
+			Some(wm) => wm,
+		};
+		let cos_theta_d = Vec3d::dot(wi, wm);
+		let fl = schlick_weight(cos_theta_i);
+		let fv = schlick_weight(cos_theta_o);
+
+		/* retro-reflection grows with roughness at grazing angles */
+		let fd90 = 0.5 + 2.0 * self.roughness * cos_theta_d * cos_theta_d;
+		let fd = (1.0 + (fd90 - 1.0) * fl) * (1.0 + (fd90 - 1.0) * fv);
+
+		/* Hanrahan-Krueger inspired flattening that approximates subsurface scattering */
+		let fss90 = self.roughness * cos_theta_d * cos_theta_d;
+		let fss = (1.0 + (fss90 - 1.0) * fl) * (1.0 + (fss90 - 1.0) * fv);
+		let ss = 1.25 * (fss * (1.0 / (cos_theta_i + cos_theta_o) - 0.5) + 0.5);
+
+		let diffuse = self.base_color * ((fd * (1.0 - self.subsurface) + ss * self.subsurface) / PI);
+		(diffuse + self.sheen * schlick_weight(cos_theta_d)) * (self.diffuse_weight * cos_theta_i)
+	}
+
+	fn diffuse_pdf(wo: Vec3d, wi: Vec3d) -> f64
+	{
+		if !same_hemisphere(wo, wi)
+		{
+			return 0.0;
+		}
+
+		f64::abs(cos_theta(wi)) / PI
+	}
+
+	fn specular_eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		if !same_hemisphere(wo, wi) || self.distribution.effectively_smooth()
+		{
+			return Vec3d::zero();
+		}
+
+		let cos_theta_o = f64::abs(cos_theta(wo));
+		let wm = match Self::half_vector(wo, wi)
+		{
+			None => return Vec3d::zero(),
+			Some(wm) => wm,
+		};
+
+		let f = fr_schlick(self.specular_f0, f64::abs(Vec3d::dot(wo, wm)));
+		f * (self.specular_weight * self.distribution.d(wm) * self.distribution.g(wo, wi) / (4.0 * cos_theta_o))
+	}
+
+	fn specular_pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		if !same_hemisphere(wo, wi) || self.distribution.effectively_smooth()
+		{
+			return 0.0;
+		}
+
+		match Self::half_vector(wo, wi)
+		{
+			None => 0.0,
+			Some(wm) => self.distribution.pdf(wo, wm) / (4.0 * f64::abs(Vec3d::dot(wo, wm))),
+		}
+	}
+
+	/* generalized Trowbridge-Reitz with gamma 1, the long tailed distribution of the clearcoat */
+	fn gtr1(&self, cos_theta_m: f64) -> f64
+	{
+		let a2 = self.clearcoat_alpha * self.clearcoat_alpha;
+		(a2 - 1.0) / (PI * f64::ln(a2) * (1.0 + (a2 - 1.0) * cos_theta_m * cos_theta_m))
+	}
+
+	fn clearcoat_eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		if !same_hemisphere(wo, wi)
+		{
+			return Vec3d::zero();
+		}
+
+		let cos_theta_o = f64::abs(cos_theta(wo));
+		let wm = match Self::half_vector(wo, wi)
+		{
+			None => return Vec3d::zero(),
+			Some(wm) => wm,
+		};
+
+		let f = 0.04 + 0.96 * schlick_weight(f64::abs(Vec3d::dot(wo, wm)));
+		let g = TrowbridgeReitz::new(0.25, 0.25).g(wo, wi);
+		Vec3d::newv(self.clearcoat_weight * self.gtr1(wm.z()) * g * f / (4.0 * cos_theta_o))
+	}
+
+	fn clearcoat_pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		if !same_hemisphere(wo, wi)
+		{
+			return 0.0;
+		}
+
+		match Self::half_vector(wo, wi)
+		{
+			None => 0.0,
+			Some(wm) => self.gtr1(wm.z()) * wm.z() / (4.0 * f64::abs(Vec3d::dot(wo, wm))),
+		}
+	}
+
+	fn clearcoat_sample(&self, wo: Vec3d, u: Vec2d) -> Vec3d
+	{
+		let a2 = self.clearcoat_alpha * self.clearcoat_alpha;
+		let cos_theta_m = f64::sqrt(f64::max(0.0, (1.0 - f64::powf(a2, 1.0 - u.x())) / (1.0 - a2)));
+		let sin_theta_m = f64::sqrt(f64::max(0.0, 1.0 - cos_theta_m * cos_theta_m));
+		let phi = 2.0 * PI * u.y();
+		let mut wm = Vec3d::new(sin_theta_m * f64::cos(phi), sin_theta_m * f64::sin(phi), cos_theta_m);
+		if cos_theta(wo) < 0.0
+		{
+			wm = -wm;
+		}
+
+		Vec3d::reflect(-wo, wm)
+	}
+}
+
+impl Bxdf for PrincipledBxdf
+{
+	fn eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		let mut f = self.specular_eval(wo, wi);
+		if self.diffuse_weight > 0.0
+		{
+			f += self.diffuse_eval(wo, wi);
+		}
+		if self.clearcoat_weight > 0.0
+		{
+			f += self.clearcoat_eval(wo, wi);
+		}
+		if self.transmission_weight > 0.0
+		{
+			f += self.transmission.eval(wo, wi) * self.transmission_weight;
+		}
+		f
+	}
+
+	fn sample(&self, wo: Vec3d, uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		/* pick a lobe and rescale uc so the lobe can reuse it */
+		let mut lobe = 0;
+		let mut uc = uc;
+		while lobe < LOBE_COUNT - 1 && (self.lobe_pdf[lobe] == 0.0 || uc >= self.lobe_pdf[lobe])
+		{
+			uc -= self.lobe_pdf[lobe];
+			lobe += 1;
+		}
+		if self.lobe_pdf[lobe] == 0.0
+		{
+			return None;
+		}
+		uc = f64::min(uc / self.lobe_pdf[lobe], 1.0 - f64::EPSILON);
+
+		let wi = match lobe
+		{
+			0 =>
+			{
+				let wi = cosine_hemisphere(u);
+				if cos_theta(wo) < 0.0 { -wi } else { wi }
+			},
+			1 =>
+			{
+				if self.distribution.effectively_smooth()
+				{
+					let wi = Vec3d::new(-wo.x(), -wo.y(), wo.z());
+					let f = fr_schlick(self.specular_f0, f64::abs(cos_theta(wo))) * self.specular_weight;
+					return Some(BsdfSample::new(wi, f, self.lobe_pdf[lobe], LOBE_SPECULAR | LOBE_REFLECTION));
+				}
+
+				let wi = Vec3d::reflect(-wo, self.distribution.sample_wm(wo, u));
+				if !same_hemisphere(wo, wi)
+				{
+					return None;
+				}
+				wi
+			},
+			2 =>
+			{
+				let wi = self.clearcoat_sample(wo, u);
+				if !same_hemisphere(wo, wi)
+				{
+					return None;
+				}
+				wi
+			},
+			_ =>
+			{
+				let sample = self.transmission.sample(wo, uc, u)?;
+				if sample.is_specular()
+				{
+					return Some(BsdfSample::new(sample.wi, sample.f * self.transmission_weight, sample.pdf * self.lobe_pdf[lobe], sample.flags));
+				}
+				sample.wi
+			},
+		};
+
+		let flags = match lobe
+		{
+			0                            => LOBE_DIFFUSE | LOBE_REFLECTION,
+			_ if same_hemisphere(wo, wi) => LOBE_GLOSSY | LOBE_REFLECTION,
+			_                            => LOBE_GLOSSY | LOBE_TRANSMISSION,
+		};
+		Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi), flags))
+	}
+
+	fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		let mut pdf = 0.0;
+		if self.lobe_pdf[0] > 0.0
+		{
+			pdf += self.lobe_pdf[0] * Self::diffuse_pdf(wo, wi);
+		}
+		if self.lobe_pdf[1] > 0.0
+		{
+			pdf += self.lobe_pdf[1] * self.specular_pdf(wo, wi);
+		}
+		if self.lobe_pdf[2] > 0.0
+		{
+			pdf += self.lobe_pdf[2] * self.clearcoat_pdf(wo, wi);
+		}
+		if self.lobe_pdf[3] > 0.0
+		{
+			pdf += self.lobe_pdf[3] * self.transmission.pdf(wo, wi);
+		}
+		pdf
+	}
+
+	fn flags(&self) -> u32
+	{
+		let mut flags = 0;
+		if self.lobe_pdf[0] > 0.0
+		{
+			flags |= LOBE_DIFFUSE | LOBE_REFLECTION;
+		}
+		if self.lobe_pdf[1] > 0.0
+		{
+			flags |= LOBE_REFLECTION | if self.distribution.effectively_smooth() { LOBE_SPECULAR } else { LOBE_GLOSSY };
+		}
+		if self.lobe_pdf[2] > 0.0
+		{
+			flags |= LOBE_GLOSSY | LOBE_REFLECTION;
+		}
+		if self.lobe_pdf[3] > 0.0
+		{
+			flags |= self.transmission.flags();
+		}
+		flags
+	}
+}
@@ -0,0 +1,248 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+use super::
+{
+	Bxdf,
+	BsdfSample,
+	LOBE_DIFFUSE,
+	LOBE_GLOSSY,
+	LOBE_REFLECTION,
+	cos_theta,
+	cosine_hemisphere,
+	same_hemisphere,
+	uniform_hemisphere,
+};
+
+const ALPHA_MIN: f64 = 0.01;
+const ALBEDO_SIZE: usize = 32;
+const ALBEDO_SAMPLES: usize = 64;
+
+/*
+ * cloth, the Charlie sheen distribution of Estevez & Kulla with the visibility term of Neubelt & Pettineo
+ * over a diffuse base, the base only receives the energy that is not reflected by the sheen
+ */
+pub struct SheenBxdf
+{
+	base: Vec3d,
+	sheen: Vec3d,
+	alpha: f64,
+}
+
+impl SheenBxdf
+{
+	pub fn new(base: Vec3d, sheen: Vec3d, roughness: f64) -> Self
+	{
+		Self{base, sheen, alpha: f64::clamp(roughness * roughness, ALPHA_MIN, 1.0)}
+	}
+
+	fn d(alpha: f64, wm: Vec3d) -> f64
+	{
+		let inv_alpha = 1.0 / alpha;
+		let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - wm.z() * wm.z()));
+		(2.0 + inv_alpha) * f64::powf(sin_theta, inv_alpha) / (2.0 * PI)
+	}
+
+	/* uncolored sheen lobe including the cosine */
+	fn sheen_eval(alpha: f64, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		let cos_theta_o = f64::abs(cos_theta(wo));
+		let cos_theta_i = f64::abs(cos_theta(wi));
+		let wm = wo + wi;
+		if cos_theta_o == 0.0 || cos_theta_i == 0.0 || Vec3d::dot(wm, wm) == 0.0
+		{
+			return 0.0;
+		}
+
+		let visibility = 1.0 / (4.0 * (cos_theta_i + cos_theta_o - cos_theta_i * cos_theta_o));
+		Self::d(alpha, Vec3d::normalize(wm)) * visibility * cos_theta_i
+	}
+
+	/*
+	 * directional albedo of the uncolored sheen, tabulated over cos theta and alpha on first use
+	 * the visibility term is approximate and goes above one at grazing angles of smooth cloth
+	 */
+	fn albedo(&self, cos_theta: f64) -> f64
+	{
+		static TABLE: OnceLock<Vec::<f64>> = OnceLock::new();
+		let table = TABLE.get_or_init(||
+		{
+			let step = 1.0 / (ALBEDO_SIZE - 1) as f64;
+			let mut table = Vec::with_capacity(ALBEDO_SIZE * ALBEDO_SIZE);
+			for j in 0..ALBEDO_SIZE
+			{
+				let alpha = ALPHA_MIN + (1.0 - ALPHA_MIN) * j as f64 * step;
+				for i in 0..ALBEDO_SIZE
+				{
+					let cos_theta_o = f64::max(i as f64 * step, 1e-3);
+					let wo = Vec3d::new(f64::sqrt(1.0 - cos_theta_o * cos_theta_o), 0.0, cos_theta_o);
+					let mut sum = 0.0;
+					for u in 0..ALBEDO_SAMPLES
+					{
+						for v in 0..ALBEDO_SAMPLES
+						{
+							let wi = uniform_hemisphere(Vec2d::new((u as f64 + 0.5) / ALBEDO_SAMPLES as f64, (v as f64 + 0.5) / ALBEDO_SAMPLES as f64));
+							sum += Self::sheen_eval(alpha, wo, wi);
+						}
+					}
+					table.push(sum * 2.0 * PI / (ALBEDO_SAMPLES * ALBEDO_SAMPLES) as f64);
+				}
+			}
+			table
+		});
+
+		let x = f64::clamp(f64::abs(cos_theta), 0.0, 1.0) * (ALBEDO_SIZE - 1) as f64;
+		let y = (self.alpha - ALPHA_MIN) / (1.0 - ALPHA_MIN) * (ALBEDO_SIZE - 1) as f64;
+		let (i, j) = (usize::min(x as usize, ALBEDO_SIZE - 2), usize::min(y as usize, ALBEDO_SIZE - 2));
+		let (tx, ty) = (x - i as f64, y - j as f64);
+		let at = |i: usize, j: usize| table[j * ALBEDO_SIZE + i];
+		(at(i, j) * (1.0 - tx) + at(i + 1, j) * tx) * (1.0 - ty) + (at(i, j + 1) * (1.0 - tx) + at(i + 1, j + 1) * tx) * ty
+	}
+
+	/* the larger albedo of both directions, symmetric to keep the bxdf reciprocal */
+	fn albedo_pair(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		f64::max(self.albedo(cos_theta(wo)), self.albedo(cos_theta(wi)))
+	}
+
+	fn max_sheen(&self) -> f64
+	{
+		f64::max(self.sheen.x(), f64::max(self.sheen.y(), self.sheen.z()))
+	}
+
+	/* probability of sampling the sheen instead of the base */
+	fn sheen_probability(&self, wo: Vec3d) -> f64
+	{
+		let sheen = self.max_sheen() * f64::min(self.albedo(cos_theta(wo)), 1.0);
+		let base = self.base.luminance() * (1.0 - sheen);
+		if sheen + base <= 0.0
+		{
+			return 0.5;
+		}
+
+		sheen / (sheen + base)
+	}
+}
+
+impl Bxdf for SheenBxdf
+{
+	fn eval(&self, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		if !same_hemisphere(wo, wi)
+		{
+			return Vec3d::zero();
+		}
+
+		/* the sheen is scaled down where it would reflect more than it receives */
+		let albedo = self.albedo_pair(wo, wi);
+		let scale = 1.0 - self.max_sheen() * f64::min(albedo, 1.0);
+		let diffuse = self.base * (f64::max(scale, 0.0) * f64::abs(cos_theta(wi)) / PI);
+		diffuse + self.sheen * (Self::sheen_eval(self.alpha, wo, wi) / f64::max(albedo, 1.0))
+	}
+
+	fn sample(&self, wo: Vec3d, uc: f64, u: Vec2d) -> Option<BsdfSample>
+	{
+		let p_sheen = self.sheen_probability(wo);
+		let (mut wi, flags) = if uc < p_sheen
+		{
+			(uniform_hemisphere(u), LOBE_GLOSSY | LOBE_REFLECTION)
+		}
+		else
+		{
+			(cosine_hemisphere(u), LOBE_DIFFUSE | LOBE_REFLECTION)
+		};
+		if cos_theta(wo) < 0.0
+		{
+			wi = -wi;
+		}
+
+		Some(BsdfSample::new(wi, self.eval(wo, wi), self.pdf(wo, wi), flags))
+	}
+
+	fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		if !same_hemisphere(wo, wi)
+		{
+			return 0.0;
+		}
+
+		let p_sheen = self.sheen_probability(wo);
+		p_sheen / (2.0 * PI) + (1.0 - p_sheen) * f64::abs(cos_theta(wi)) / PI
+	}
+
+	fn flags(&self) -> u32
+	{
+		LOBE_DIFFUSE | LOBE_GLOSSY | LOBE_REFLECTION
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	const N: usize = 128;
+
+	fn hemisphere() -> impl Iterator<Item = Vec3d>
+	{
+		(0..N * N).map(|i| uniform_hemisphere(Vec2d::new(((i % N) as f64 + 0.5) / N as f64, ((i / N) as f64 + 0.5) / N as f64)))
+	}
+
+	fn direction(cos_theta: f64) -> Vec3d
+	{
+		Vec3d::new(f64::sqrt(1.0 - cos_theta * cos_theta), 0.0, cos_theta)
+	}
+
+	#[test]
+	fn test_directional_albedo()
+	{
+		for roughness in [0.1, 0.3, 0.6, 1.0]
+		{
+			let bxdf = SheenBxdf::new(Vec3d::zero(), Vec3d::one(), roughness);
+			for cos_theta_o in [0.05, 0.3, 0.7, 1.0]
+			{
+				let wo = direction(cos_theta_o);
+				let integral = hemisphere().map(|wi| SheenBxdf::sheen_eval(bxdf.alpha, wo, wi)).sum::<f64>() * 2.0 * PI / (N * N) as f64;
+				assert!((bxdf.albedo(cos_theta_o) - integral).abs() < 0.05 * f64::max(integral, 1.0), "{} {}", bxdf.albedo(cos_theta_o), integral);
+
+				/* the lobe as evaluated stays below one */
+				let albedo = hemisphere().map(|wi| bxdf.eval(wo, wi).x()).sum::<f64>() * 2.0 * PI / (N * N) as f64;
+				assert!(albedo <= 1.0, "{} {} {}", roughness, cos_theta_o, albedo);
+			}
+		}
+	}
+
+	#[test]
+	fn test_energy_conservation()
+	{
+		let bxdf = SheenBxdf::new(Vec3d::one(), Vec3d::one(), 0.2);
+		for cos_theta_o in [0.05, 0.1, 0.5, 1.0]
+		{
+			let albedo = hemisphere().map(|wi| bxdf.eval(direction(cos_theta_o), wi).x()).sum::<f64>() * 2.0 * PI / (N * N) as f64;
+			assert!(albedo <= 1.01, "{} {}", cos_theta_o, albedo);
+		}
+	}
+
+	#[test]
+	fn test_sample_matches_pdf()
+	{
+		let bxdf = SheenBxdf::new(Vec3d::new(0.5, 0.2, 0.1), Vec3d::one(), 0.4);
+		let wo = direction(0.6);
+		let integral = hemisphere().map(|wi| bxdf.pdf(wo, wi)).sum::<f64>() * 2.0 * PI / (N * N) as f64;
+		assert!((integral - 1.0).abs() < 1e-2, "{}", integral);
+
+		for uc in [0.1, 0.9]
+		{
+			let sample = bxdf.sample(wo, uc, Vec2d::new(0.3, 0.6)).unwrap();
+			assert!((sample.pdf - bxdf.pdf(wo, sample.wi)).abs() < 1e-12);
+			assert!(Vec3d::length(sample.f - bxdf.eval(wo, sample.wi)) < 1e-12);
+		}
+	}
+}
@@ -75,6 +75,12 @@ impl BvhNode
 		Self{left, right, aabb}
 	}
 
+	/* a Group node, every object inside it goes in one hierarchy */
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		Self::new(scene.parse_xml_inner_objects(node), 0.0, 1.0)
+	}
+
 	fn box_compare(a: &Arc::<dyn Hittable>, b: &Arc::<dyn Hittable>, axis: usize) -> Ordering
 	{
 		match (a.bounding_box(0.0, 0.0), b.bounding_box(0.0, 0.0))
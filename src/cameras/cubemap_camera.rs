@@ -0,0 +1,98 @@
+use crate::math::vec::
+{
+	Vec2u,
+	Vec3d,
+};
+use crate::ray::Ray;
+
+use super::
+{
+	Camera,
+	CameraFrame,
+};
+
+/* forward, right and up direction of each face in camera space */
+const FACES: [[[f64; 3]; 3]; 6] =
+[
+	[[ 1.0,  0.0,  0.0], [ 0.0,  0.0,  1.0], [0.0, 1.0,  0.0]], /* right */
+	[[-1.0,  0.0,  0.0], [ 0.0,  0.0, -1.0], [0.0, 1.0,  0.0]], /* left */
+	[[ 0.0,  1.0,  0.0], [ 1.0,  0.0,  0.0], [0.0, 0.0,  1.0]], /* up */
+	[[ 0.0, -1.0,  0.0], [ 1.0,  0.0,  0.0], [0.0, 0.0, -1.0]], /* down */
+	[[ 0.0,  0.0, -1.0], [ 1.0,  0.0,  0.0], [0.0, 1.0,  0.0]], /* front */
+	[[ 0.0,  0.0,  1.0], [-1.0,  0.0,  0.0], [0.0, 1.0,  0.0]], /* back */
+];
+
+/*
+ * the six 90 degree faces of a cube around the camera in a 3x2 grid, right, left and up on the top row
+ * then down, front and back, the up and down faces have the front face below and above them
+ */
+pub struct CubemapCamera
+{
+	frame: CameraFrame,
+}
+
+impl CubemapCamera
+{
+	pub fn new(lookfrom: Vec3d, lookat: Vec3d, vup: Vec3d, size: Vec2u) -> Self
+	{
+		Self{frame: CameraFrame::new(lookfrom, lookat, vup, size)}
+	}
+
+	pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self
+	{
+		self.frame = self.frame.with_shutter(time0, time1);
+		self
+	}
+}
+
+impl Camera for CubemapCamera
+{
+	fn get_ray(&self, u: f64, v: f64) -> Option<Ray>
+	{
+		let column = f64::clamp(f64::floor(u * 3.0), 0.0, 2.0);
+		let row = f64::clamp(f64::floor(v * 2.0), 0.0, 1.0);
+		let face = FACES[(1.0 - row) as usize * 3 + column as usize];
+
+		let a = (u * 3.0 - column) * 2.0 - 1.0;
+		let b = (v * 2.0 - row) * 2.0 - 1.0;
+		let axis = |i: usize| Vec3d::new(face[i][0], face[i][1], face[i][2]);
+		Some(self.frame.ray(Vec3d::zero(), axis(0) + axis(1) * a + axis(2) * b))
+	}
+
+	fn size(&self) -> Vec2u
+	{
+		self.frame.size()
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	#[test]
+	fn test_face_centers_cover_every_axis()
+	{
+		let camera = CubemapCamera::new(Vec3d::zero(), Vec3d::new(0.0, 0.0, -1.0), Vec3d::new(0.0, 1.0, 0.0), Vec2u::new(300, 200));
+		let mut sum = Vec3d::zero();
+		for (column, row) in [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]
+		{
+			let dir = camera.get_ray((column as f64 + 0.5) / 3.0, (row as f64 + 0.5) / 2.0).unwrap().dir();
+			assert_eq!(dir.v.iter().filter(|x| x.abs() == 1.0).count(), 1);
+			sum += dir.for_each(&f64::abs);
+		}
+		assert_eq!(sum, Vec3d::newv(2.0));
+	}
+
+	#[test]
+	fn test_faces_are_right_handed()
+	{
+		/* right cross up is backwards, like the camera */
+		for face in FACES
+		{
+			let axis = |i: usize| Vec3d::new(face[i][0], face[i][1], face[i][2]);
+			assert_eq!(Vec3d::cross(axis(1), axis(2)), -axis(0));
+		}
+	}
+}
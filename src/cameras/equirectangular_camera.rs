@@ -0,0 +1,83 @@
+use crate::math::vec::
+{
+	Vec2u,
+	Vec3d,
+};
+use crate::ray::Ray;
+
+use std::f64::consts::PI;
+
+use super::
+{
+	Camera,
+	CameraFrame,
+};
+
+/* 360 degree panorama, longitude along the width with lookat in the center and latitude along the height */
+pub struct EquirectangularCamera
+{
+	frame: CameraFrame,
+}
+
+impl EquirectangularCamera
+{
+	pub fn new(lookfrom: Vec3d, lookat: Vec3d, vup: Vec3d, size: Vec2u) -> Self
+	{
+		Self{frame: CameraFrame::new(lookfrom, lookat, vup, size)}
+	}
+
+	pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self
+	{
+		self.frame = self.frame.with_shutter(time0, time1);
+		self
+	}
+
+	/* camera space direction of a point of the panorama, both coordinates in [0, 1] */
+	pub fn direction(u: f64, v: f64) -> Vec3d
+	{
+		let phi = (u - 0.5) * 2.0 * PI;
+		let theta = (v - 0.5) * PI;
+		Vec3d::new(f64::cos(theta) * f64::sin(phi), f64::sin(theta), -f64::cos(theta) * f64::cos(phi))
+	}
+}
+
+impl Camera for EquirectangularCamera
+{
+	fn get_ray(&self, u: f64, v: f64) -> Option<Ray>
+	{
+		Some(self.frame.ray(Vec3d::zero(), Self::direction(u, v)))
+	}
+
+	fn size(&self) -> Vec2u
+	{
+		self.frame.size()
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	#[test]
+	fn test_directions()
+	{
+		let near = |a: Vec3d, b: Vec3d| Vec3d::length(a - b) < 1e-12;
+		assert!(near(EquirectangularCamera::direction(0.5, 0.5), Vec3d::new(0.0, 0.0, -1.0)));
+		assert!(near(EquirectangularCamera::direction(0.75, 0.5), Vec3d::new(1.0, 0.0, 0.0)));
+		assert!(near(EquirectangularCamera::direction(0.0, 0.5), Vec3d::new(0.0, 0.0, 1.0)));
+		assert!(near(EquirectangularCamera::direction(0.3, 1.0), Vec3d::new(0.0, 1.0, 0.0)));
+		assert!(near(EquirectangularCamera::direction(0.3, 0.0), Vec3d::new(0.0, -1.0, 0.0)));
+	}
+
+	#[test]
+	fn test_directions_are_normalized()
+	{
+		for i in 0..100
+		{
+			let dir = EquirectangularCamera::direction((i % 10) as f64 / 9.0, (i / 10) as f64 / 9.0);
+			assert!((Vec3d::length(dir) - 1.0).abs() < 1e-12);
+		}
+	}
+}
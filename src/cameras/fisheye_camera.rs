@@ -0,0 +1,124 @@
+use crate::math::vec::
+{
+	Vec2u,
+	Vec3d,
+};
+use crate::ray::Ray;
+
+use super::
+{
+	Camera,
+	CameraFrame,
+};
+
+/* how the angle from the optical axis maps to the distance from the center of the image circle */
+#[derive(Clone, Copy)]
+pub enum FisheyeMapping
+{
+	/* distance proportional to the angle */
+	Equidistant,
+	/* distance proportional to sin(angle / 2), preserves solid angles */
+	Equisolid,
+}
+
+/* fisheye lens whose image circle fits the short side of the film, fov is the full field of view in degrees up to 360 */
+pub struct FisheyeCamera
+{
+	frame: CameraFrame,
+	theta_max: f64,
+	mapping: FisheyeMapping,
+}
+
+impl FisheyeCamera
+{
+	pub fn new(lookfrom: Vec3d, lookat: Vec3d, vup: Vec3d, fov: f64, size: Vec2u, mapping: FisheyeMapping) -> Self
+	{
+		let frame = CameraFrame::new(lookfrom, lookat, vup, size);
+		let theta_max = f64::to_radians(f64::clamp(fov, 0.0, 360.0)) / 2.0;
+		Self{frame, theta_max, mapping}
+	}
+
+	pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self
+	{
+		self.frame = self.frame.with_shutter(time0, time1);
+		self
+	}
+
+	/* angle from the optical axis at the distance r from the center, the image circle has a radius of 1 */
+	fn theta(&self, r: f64) -> f64
+	{
+		match self.mapping
+		{
+			FisheyeMapping::Equidistant => r * self.theta_max,
+			FisheyeMapping::Equisolid   => 2.0 * f64::asin(r * f64::sin(self.theta_max / 2.0)),
+		}
+	}
+}
+
+impl Camera for FisheyeCamera
+{
+	fn get_ray(&self, u: f64, v: f64) -> Option<Ray>
+	{
+		let size = self.frame.size();
+		let diameter = u32::min(size.x(), size.y()) as f64;
+		let x = (u - 0.5) * 2.0 * size.x() as f64 / diameter;
+		let y = (v - 0.5) * 2.0 * size.y() as f64 / diameter;
+		let r = f64::sqrt(x * x + y * y);
+		if r > 1.0
+		{
+			return None;
+		}
+
+		let theta = self.theta(r);
+		let phi = f64::atan2(y, x);
+		let dir = Vec3d::new(f64::sin(theta) * f64::cos(phi), f64::sin(theta) * f64::sin(phi), -f64::cos(theta));
+		Some(self.frame.ray(Vec3d::zero(), dir))
+	}
+
+	fn size(&self) -> Vec2u
+	{
+		self.frame.size()
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	use std::f64::consts::PI;
+
+	fn camera(mapping: FisheyeMapping) -> FisheyeCamera
+	{
+		FisheyeCamera::new(Vec3d::zero(), Vec3d::new(0.0, 0.0, -1.0), Vec3d::new(0.0, 1.0, 0.0), 180.0, Vec2u::new(200, 100), mapping)
+	}
+
+	/* angle from the optical axis of the ray through (u, v) */
+	fn theta(camera: &FisheyeCamera, u: f64, v: f64) -> f64
+	{
+		let dir = Vec3d::normalize(camera.get_ray(u, v).unwrap().dir());
+		f64::acos(-dir.z())
+	}
+
+	#[test]
+	fn test_image_circle()
+	{
+		/* the circle fits the height of a wide film */
+		let camera = camera(FisheyeMapping::Equidistant);
+		assert!(theta(&camera, 0.5, 0.5) < 1e-12);
+		assert!((theta(&camera, 0.5, 1.0) - PI / 2.0).abs() < 1e-12);
+		assert!((theta(&camera, 0.75, 0.5) - PI / 2.0).abs() < 1e-12);
+		assert!(camera.get_ray(0.9, 0.5).is_none());
+	}
+
+	#[test]
+	fn test_mappings()
+	{
+		/* halfway to the edge of the circle */
+		let equidistant = theta(&camera(FisheyeMapping::Equidistant), 0.625, 0.5);
+		let equisolid = theta(&camera(FisheyeMapping::Equisolid), 0.625, 0.5);
+		assert!((equidistant - PI / 4.0).abs() < 1e-12);
+		assert!((equisolid - 2.0 * f64::asin(0.5 * f64::sin(PI / 4.0))).abs() < 1e-12);
+	}
+}
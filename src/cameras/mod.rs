@@ -0,0 +1,125 @@
+pub mod cubemap_camera;
+pub mod equirectangular_camera;
+pub mod fisheye_camera;
+pub mod ods_camera;
+pub mod orthographic_camera;
+pub mod perspective_camera;
+
+use crate::math::vec::
+{
+	Vec2u,
+	Vec3d,
+};
+use crate::ray::Ray;
+
+use rand::Rng;
+
+pub trait Camera: Send + Sync
+{
+	/* ray through the film position (u, v) measured from the bottom left corner, None where the film is not exposed */
+	fn get_ray(&self, u: f64, v: f64) -> Option<Ray>;
+	fn size(&self) -> Vec2u;
+
+	fn width(&self) -> u32
+	{
+		self.size().x()
+	}
+
+	fn height(&self) -> u32
+	{
+		self.size().y()
+	}
+}
+
+/* position, orientation, film size and shutter interval shared by every projection */
+pub struct CameraFrame
+{
+	origin: Vec3d,
+	u: Vec3d,
+	v: Vec3d,
+	w: Vec3d,
+	size: Vec2u,
+	time0: f64,
+	time1: f64,
+}
+
+impl CameraFrame
+{
+	pub fn new(lookfrom: Vec3d, lookat: Vec3d, vup: Vec3d, size: Vec2u) -> Self
+	{
+		let w = Vec3d::normalize(lookfrom - lookat);
+		let u = Vec3d::normalize(Vec3d::cross(vup, w));
+		let v = Vec3d::cross(w, u);
+
+		Self{origin: lookfrom, u, v, w, size, time0: 0.0, time1: 0.0}
+	}
+
+	pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self
+	{
+		self.time0 = time0;
+		self.time1 = time1;
+		self
+	}
+
+	pub fn size(&self) -> Vec2u
+	{
+		self.size
+	}
+
+	pub fn aspect_ratio(&self) -> f64
+	{
+		self.size.x() as f64 / self.size.y() as f64
+	}
+
+	fn local(&self, a: Vec3d) -> Vec3d
+	{
+		self.u * a.x() + self.v * a.y() + self.w * a.z()
+	}
+
+	/* origin and direction are in camera space, x to the right, y up and z pointing backwards */
+	pub fn ray(&self, origin: Vec3d, dir: Vec3d) -> Ray
+	{
+		if f64::abs(self.time0 - self.time1) > f64::EPSILON
+		{
+			let mut rng = rand::thread_rng();
+			return Ray::with_time(self.origin + self.local(origin), self.local(dir), rng.gen_range(self.time0..self.time1));
+		}
+
+		Ray::new(self.origin + self.local(origin), self.local(dir))
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	fn frame() -> CameraFrame
+	{
+		CameraFrame::new(Vec3d::new(1.0, 2.0, 3.0), Vec3d::new(1.0, 2.0, 0.0), Vec3d::new(0.0, 1.0, 0.0), Vec2u::new(200, 100))
+	}
+
+	#[test]
+	fn test_frame_maps_camera_space_to_world()
+	{
+		/* looking down -z from lookfrom, so camera space is world space shifted to lookfrom */
+		let r = frame().ray(Vec3d::new(0.5, 0.0, 0.0), Vec3d::new(0.0, 0.0, -1.0));
+		assert!(Vec3d::length(r.orig() - Vec3d::new(1.5, 2.0, 3.0)) < 1e-12);
+		assert!(Vec3d::length(r.dir() - Vec3d::new(0.0, 0.0, -1.0)) < 1e-12);
+		assert_eq!(frame().aspect_ratio(), 2.0);
+	}
+
+	#[test]
+	fn test_rays_are_timed_within_the_shutter()
+	{
+		assert_eq!(frame().ray(Vec3d::zero(), Vec3d::new(0.0, 0.0, -1.0)).time(), 0.0);
+
+		let frame = frame().with_shutter(0.25, 0.75);
+		for _ in 0..100
+		{
+			let time = frame.ray(Vec3d::zero(), Vec3d::new(0.0, 0.0, -1.0)).time();
+			assert!((0.25..0.75).contains(&time));
+		}
+	}
+}
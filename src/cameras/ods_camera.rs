@@ -0,0 +1,99 @@
+use crate::math::vec::
+{
+	Vec2u,
+	Vec3d,
+};
+use crate::ray::Ray;
+
+use std::f64::consts::PI;
+
+use super::
+{
+	Camera,
+	CameraFrame,
+	equirectangular_camera::EquirectangularCamera,
+};
+
+/*
+ * omni-directional stereo, the left and right eye panoramas are stacked with the left eye on top
+ * every ray starts on a circle of diameter ipd, tangent to it, like an eye looking in that direction
+ */
+pub struct OdsCamera
+{
+	frame: CameraFrame,
+	ipd: f64,
+}
+
+impl OdsCamera
+{
+	/* size is the size of the whole film, each eye gets half its height */
+	pub fn new(lookfrom: Vec3d, lookat: Vec3d, vup: Vec3d, ipd: f64, size: Vec2u) -> Self
+	{
+		Self{frame: CameraFrame::new(lookfrom, lookat, vup, size), ipd}
+	}
+
+	pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self
+	{
+		self.frame = self.frame.with_shutter(time0, time1);
+		self
+	}
+}
+
+impl Camera for OdsCamera
+{
+	fn get_ray(&self, u: f64, v: f64) -> Option<Ray>
+	{
+		let (eye, v) = if v >= 0.5 { (-1.0, v * 2.0 - 1.0) } else { (1.0, v * 2.0) };
+		let dir = EquirectangularCamera::direction(u, v);
+
+		/* horizontal direction to the right of dir */
+		let phi = (u - 0.5) * 2.0 * PI;
+		let right = Vec3d::new(f64::cos(phi), 0.0, f64::sin(phi));
+		Some(self.frame.ray(right * (eye * self.ipd / 2.0), dir))
+	}
+
+	fn size(&self) -> Vec2u
+	{
+		self.frame.size()
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	fn camera() -> OdsCamera
+	{
+		OdsCamera::new(Vec3d::zero(), Vec3d::new(0.0, 0.0, -1.0), Vec3d::new(0.0, 1.0, 0.0), 0.064, Vec2u::new(400, 400))
+	}
+
+	#[test]
+	fn test_left_eye_is_on_top()
+	{
+		/* looking forward, the left eye sits to the left */
+		let left = camera().get_ray(0.5, 0.75).unwrap();
+		let right = camera().get_ray(0.5, 0.25).unwrap();
+		assert!(Vec3d::length(left.orig() - Vec3d::new(-0.032, 0.0, 0.0)) < 1e-12);
+		assert!(Vec3d::length(right.orig() - Vec3d::new(0.032, 0.0, 0.0)) < 1e-12);
+		assert!(Vec3d::length(left.dir() - right.dir()) < 1e-12);
+	}
+
+	#[test]
+	fn test_rays_are_tangent_to_the_viewing_circle()
+	{
+		for i in 0..100
+		{
+			let (u, v) = ((i % 10) as f64 / 9.0, (i / 10) as f64 / 9.0);
+			let r = camera().get_ray(u, v).unwrap();
+			let horizontal = Vec3d::new(r.dir().x(), 0.0, r.dir().z());
+			assert!((Vec3d::length(r.orig()) - 0.032).abs() < 1e-12);
+			assert!(r.orig().y() == 0.0);
+			assert!(Vec3d::dot(r.orig(), horizontal).abs() < 1e-12);
+			/* each eye panorama is a full equirectangular image */
+			let eye_v = if v >= 0.5 { v * 2.0 - 1.0 } else { v * 2.0 };
+			assert!(Vec3d::length(r.dir() - EquirectangularCamera::direction(u, eye_v)) < 1e-12);
+		}
+	}
+}
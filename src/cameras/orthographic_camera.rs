@@ -0,0 +1,69 @@
+use crate::math::vec::
+{
+	Vec2u,
+	Vec3d,
+};
+use crate::ray::Ray;
+
+use super::
+{
+	Camera,
+	CameraFrame,
+};
+
+/* parallel projection, e.g. for architectural elevations, view_height is the height of the film in world units */
+pub struct OrthographicCamera
+{
+	frame: CameraFrame,
+	view_width: f64,
+	view_height: f64,
+}
+
+impl OrthographicCamera
+{
+	pub fn new(lookfrom: Vec3d, lookat: Vec3d, vup: Vec3d, view_height: f64, size: Vec2u) -> Self
+	{
+		let frame = CameraFrame::new(lookfrom, lookat, vup, size);
+		let view_width = frame.aspect_ratio() * view_height;
+		Self{frame, view_width, view_height}
+	}
+
+	pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self
+	{
+		self.frame = self.frame.with_shutter(time0, time1);
+		self
+	}
+}
+
+impl Camera for OrthographicCamera
+{
+	fn get_ray(&self, u: f64, v: f64) -> Option<Ray>
+	{
+		let origin = Vec3d::new((u - 0.5) * self.view_width, (v - 0.5) * self.view_height, 0.0);
+		Some(self.frame.ray(origin, Vec3d::new(0.0, 0.0, -1.0)))
+	}
+
+	fn size(&self) -> Vec2u
+	{
+		self.frame.size()
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	#[test]
+	fn test_rays_are_parallel()
+	{
+		let camera = OrthographicCamera::new(Vec3d::zero(), Vec3d::new(0.0, 0.0, -1.0), Vec3d::new(0.0, 1.0, 0.0), 4.0, Vec2u::new(200, 100));
+		for (u, v) in [(0.0, 0.0), (0.5, 0.5), (1.0, 0.25)]
+		{
+			let r = camera.get_ray(u, v).unwrap();
+			assert_eq!(r.dir(), Vec3d::new(0.0, 0.0, -1.0));
+			assert!(Vec3d::length(r.orig() - Vec3d::new((u - 0.5) * 8.0, (v - 0.5) * 4.0, 0.0)) < 1e-12);
+		}
+	}
+}
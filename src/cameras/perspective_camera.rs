@@ -0,0 +1,173 @@
+use crate::apertures::
+{
+	Aperture,
+	circular_aperture::CircularAperture,
+};
+use crate::math::vec::
+{
+	Vec2d,
+	Vec2u,
+	Vec3d,
+};
+use crate::ray::Ray;
+
+use rand::Rng;
+
+use std::sync::Arc;
+
+use super::
+{
+	Camera,
+	CameraFrame,
+};
+
+/* thin lens perspective projection, the lens is focused at focus_dist */
+pub struct PerspectiveCamera
+{
+	frame: CameraFrame,
+	viewport_width: f64,
+	viewport_height: f64,
+	lens_radius: f64,
+	focus_dist: f64,
+	aperture: Arc::<dyn Aperture>,
+	cat_eye: f64,
+	squeeze: f64,
+}
+
+impl PerspectiveCamera
+{
+	pub fn new(lookfrom: Vec3d, lookat: Vec3d, vup: Vec3d, vfov: f64, size: Vec2u, aperture: f64, focus_dist: f64) -> Self
+	{
+		PerspectiveCamera::with_time(lookfrom, lookat, vup, vfov, size, aperture, focus_dist, 0.0, 0.0)
+	}
+
+	pub fn with_time(lookfrom: Vec3d, lookat: Vec3d, vup: Vec3d, vfov: f64, size: Vec2u, aperture: f64, focus_dist: f64, time0: f64, time1: f64) -> Self
+	{
+		let frame = CameraFrame::new(lookfrom, lookat, vup, size).with_shutter(time0, time1);
+		let theta = f64::to_radians(vfov);
+		let h = f64::tan(theta / 2.0);
+		let viewport_height = 2.0 * h;
+		let viewport_width = frame.aspect_ratio() * viewport_height;
+		let lens_radius = aperture / 2.0;
+
+		PerspectiveCamera{frame, viewport_width, viewport_height, lens_radius, focus_dist, aperture: Arc::new(CircularAperture::new()), cat_eye: 0.0, squeeze: 1.0}
+	}
+
+	pub fn with_shutter(mut self, time0: f64, time1: f64) -> Self
+	{
+		self.frame = self.frame.with_shutter(time0, time1);
+		self
+	}
+
+	/* shape of the opening, the aperture diameter is the diameter of the disk around it */
+	pub fn with_aperture(mut self, aperture: Arc::<dyn Aperture>) -> Self
+	{
+		self.aperture = aperture;
+		self
+	}
+
+	/*
+	 * the lens barrel clips the opening away from the center of the film, into cat's eye shaped highlights
+	 * the clipping disk moves by cat_eye aperture radii in the corners, 0 disables it
+	 */
+	pub fn with_cat_eye(mut self, cat_eye: f64) -> Self
+	{
+		self.cat_eye = cat_eye;
+		self
+	}
+
+	/* anamorphic lenses squeeze the opening horizontally, making highlights taller than wide */
+	pub fn with_anamorphic_squeeze(mut self, squeeze: f64) -> Self
+	{
+		assert!(squeeze > 0.0, "anamorphic squeeze must be positive");
+		self.squeeze = squeeze;
+		self
+	}
+}
+
+impl Camera for PerspectiveCamera
+{
+	fn get_ray(&self, u: f64, v: f64) -> Option<Ray>
+	{
+		let mut rng = rand::thread_rng();
+		let lens = self.aperture.sample(Vec2d::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)));
+		let film = Vec2d::new((u - 0.5) * self.viewport_width, (v - 0.5) * self.viewport_height);
+		if self.cat_eye > 0.0
+		{
+			/* the film position relative to the corners, the vignetted light is lost */
+			let corner = film * (2.0 / f64::sqrt(self.viewport_width * self.viewport_width + self.viewport_height * self.viewport_height));
+			let clipped = lens + corner * self.cat_eye;
+			if Vec2d::dot(clipped, clipped) > 1.0
+			{
+				return None;
+			}
+		}
+
+		let offset = Vec3d::new(lens.x() / self.squeeze, lens.y(), 0.0) * self.lens_radius;
+		let target = Vec3d::new(film.x(), film.y(), -1.0) * self.focus_dist;
+		Some(self.frame.ray(offset, target - offset))
+	}
+
+	fn size(&self) -> Vec2u
+	{
+		self.frame.size()
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::apertures::polygonal_aperture::PolygonalAperture;
+
+	/* looking down -z from the origin with a lens of radius 1 focused at 2 */
+	fn camera() -> PerspectiveCamera
+	{
+		PerspectiveCamera::new(Vec3d::zero(), Vec3d::new(0.0, 0.0, -1.0), Vec3d::new(0.0, 1.0, 0.0), 90.0, Vec2u::new(100, 100), 2.0, 2.0)
+	}
+
+	#[test]
+	fn test_rays_meet_on_the_focus_plane()
+	{
+		let camera = camera().with_aperture(Arc::new(PolygonalAperture::new(6, 0.0)));
+		for _ in 0..100
+		{
+			let r = camera.get_ray(0.75, 0.5).unwrap();
+			let p = r.orig() + r.dir() * ((-2.0 - r.orig().z()) / r.dir().z());
+			assert!(Vec3d::length(p - Vec3d::new(1.0, 0.0, -2.0)) < 1e-9);
+		}
+	}
+
+	#[test]
+	fn test_anamorphic_squeeze_narrows_the_lens()
+	{
+		let camera = camera().with_anamorphic_squeeze(2.0);
+		let mut height: f64 = 0.0;
+		for _ in 0..1000
+		{
+			let orig = camera.get_ray(0.5, 0.5).unwrap().orig();
+			assert!(f64::abs(orig.x()) <= 0.5 && f64::abs(orig.y()) <= 1.0);
+			height = f64::max(height, f64::abs(orig.y()));
+		}
+		assert!(height > 0.5);
+	}
+
+	#[test]
+	#[should_panic]
+	fn test_squeeze_must_be_positive()
+	{
+		camera().with_anamorphic_squeeze(0.0);
+	}
+
+	#[test]
+	fn test_cat_eye_vignettes_the_corners()
+	{
+		let camera = camera().with_cat_eye(1.0);
+		let center = (0..1000).filter(|_| camera.get_ray(0.5, 0.5).is_some()).count();
+		let corner = (0..1000).filter(|_| camera.get_ray(1.0, 1.0).is_some()).count();
+		assert_eq!(center, 1000);
+		/* a unit disk shifted by its radius overlaps it by about 39% */
+		assert!((corner as f64 / 1000.0 - 0.391).abs() < 0.06);
+	}
+}
@@ -0,0 +1,152 @@
+use crate::hittable::HitRecord;
+use crate::math::vec::
+{
+	Vec3d,
+	Vec4d,
+};
+use crate::ray::Ray;
+use crate::spectrum::
+{
+	RgbSpectrum,
+	SampledWavelengths,
+	Spectrum,
+};
+
+use std::ops::
+{
+	Add,
+	AddAssign,
+	Div,
+	Mul,
+	MulAssign,
+};
+
+/* radiance and throughput carried along a path, rgb triples or the sampled wavelengths */
+pub trait PathColor: Copy + Send + Sync + Add<Self, Output = Self> + Mul<Self, Output = Self> + Mul<f64, Output = Self> + Div<f64, Output = Self> + AddAssign + MulAssign
+{
+	fn zero() -> Self;
+	fn one() -> Self;
+	fn from_rgb(rgb: Vec3d, lambda: &SampledWavelengths) -> Self;
+	fn emitted(r: &Ray, rec: &HitRecord, lambda: &SampledWavelengths) -> Self;
+	/* called on the path throughput once only the hero wavelength is left */
+	fn terminate_secondary(&mut self, lambda: &SampledWavelengths);
+	fn to_rgb(self, lambda: &SampledWavelengths) -> Vec3d;
+	fn channels() -> usize;
+	fn spectral() -> bool;
+	fn channel(self, i: usize) -> f64;
+	fn map(self, func: &dyn Fn(f64) -> f64) -> Self;
+
+	fn average(self) -> f64
+	{
+		(0..Self::channels()).map(|i| self.channel(i)).sum::<f64>() / Self::channels() as f64
+	}
+}
+
+impl PathColor for Vec3d
+{
+	fn zero() -> Self
+	{
+		Vec3d::zero()
+	}
+
+	fn one() -> Self
+	{
+		Vec3d::one()
+	}
+
+	fn from_rgb(rgb: Vec3d, _lambda: &SampledWavelengths) -> Self
+	{
+		rgb
+	}
+
+	fn emitted(r: &Ray, rec: &HitRecord, _lambda: &SampledWavelengths) -> Self
+	{
+		rec.material.emitted(r, rec, rec.uv, rec.p)
+	}
+
+	fn terminate_secondary(&mut self, lambda: &SampledWavelengths)
+	{
+		*self *= lambda.hero_rgb_weight();
+	}
+
+	fn to_rgb(self, _lambda: &SampledWavelengths) -> Vec3d
+	{
+		self
+	}
+
+	fn channels() -> usize
+	{
+		3
+	}
+
+	fn spectral() -> bool
+	{
+		false
+	}
+
+	fn channel(self, i: usize) -> f64
+	{
+		self.v[i]
+	}
+
+	fn map(self, func: &dyn Fn(f64) -> f64) -> Self
+	{
+		self.for_each(func)
+	}
+}
+
+impl PathColor for Vec4d
+{
+	fn zero() -> Self
+	{
+		Vec4d::newv(0.0)
+	}
+
+	fn one() -> Self
+	{
+		Vec4d::newv(1.0)
+	}
+
+	fn from_rgb(rgb: Vec3d, lambda: &SampledWavelengths) -> Self
+	{
+		RgbSpectrum::new(rgb).sample(lambda)
+	}
+
+	fn emitted(r: &Ray, rec: &HitRecord, lambda: &SampledWavelengths) -> Self
+	{
+		match rec.material.emitted_spectrum(r, rec, rec.uv, rec.p, lambda)
+		{
+			Some(l) => l,
+			None => Self::from_rgb(rec.material.emitted(r, rec, rec.uv, rec.p), lambda),
+		}
+	}
+
+	fn terminate_secondary(&mut self, _lambda: &SampledWavelengths)
+	{
+	}
+
+	fn to_rgb(self, lambda: &SampledWavelengths) -> Vec3d
+	{
+		lambda.to_rgb(self)
+	}
+
+	fn channels() -> usize
+	{
+		4
+	}
+
+	fn spectral() -> bool
+	{
+		true
+	}
+
+	fn channel(self, i: usize) -> f64
+	{
+		self.v[i]
+	}
+
+	fn map(self, func: &dyn Fn(f64) -> f64) -> Self
+	{
+		self.for_each(func)
+	}
+}
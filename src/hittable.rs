@@ -7,20 +7,29 @@ use crate::math::
 		Vec3d,
 	}
 };
+use crate::light_bvh::LightBounds;
 use crate::materials::Material;
+use crate::onb::Onb;
 use crate::ray::Ray;
 use crate::scene::Scene;
 
+use rand::Rng;
+
 use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct HitRecord
 {
 	pub p: Vec3d,
 	pub normal: Vec3d,
 	pub t: f64,
 	pub uv: Vec2d,
+	pub dpdu: Vec3d,
+	pub dpdv: Vec3d,
 	pub front_face: bool,
 	pub material: Arc::<dyn Material>,
+	pub light_links: u64,
+	pub light_group: u64,
 }
 
 impl HitRecord
@@ -37,7 +46,72 @@ impl HitRecord
 		{
 			normal = -n;
 		}
-		Self{p, normal, t, uv, front_face, material}
+		Self{p, normal, t, uv, dpdu: Vec3d::zero(), dpdv: Vec3d::zero(), front_face, material, light_links: !0, light_group: 1}
+	}
+
+	/* partial derivatives of the position along the texture coordinates */
+	pub fn with_tangents(mut self, dpdu: Vec3d, dpdv: Vec3d) -> Self
+	{
+		self.dpdu = dpdu;
+		self.dpdv = dpdv;
+		self
+	}
+
+	/* normal on the outside of the surface, whichever side was hit */
+	pub fn outward_normal(&self) -> Vec3d
+	{
+		if self.front_face { self.normal } else { -self.normal }
+	}
+
+	/* dpdu and dpdv, or an arbitrary frame around the normal for surfaces without texture derivatives */
+	pub fn tangents(&self) -> (Vec3d, Vec3d)
+	{
+		if Vec3d::length(Vec3d::cross(self.dpdu, self.dpdv)) > 0.0
+		{
+			return (self.dpdu, self.dpdv);
+		}
+
+		let onb = Onb::from_w(self.outward_normal());
+		(onb.u(), onb.v())
+	}
+
+	/* dpdu made orthogonal to the shading normal, the u axis of the shading frame */
+	pub fn shading_tangent(&self) -> Vec3d
+	{
+		let (dpdu, _) = self.tangents();
+		dpdu - self.normal * Vec3d::dot(self.normal, dpdu)
+	}
+
+	/* alpha test, fractional opacity is resolved with a hash of the ray so repeated queries agree */
+	pub fn is_cut_out(&self, r: &Ray) -> bool
+	{
+		let opacity = self.material.opacity(self.uv, self.p);
+		if opacity >= 1.0
+		{
+			return false;
+		}
+		if opacity <= 0.0
+		{
+			return true;
+		}
+
+		let mut hash = 0x9e3779b97f4a7c15u64;
+		for x in r.orig().v.iter().chain(r.dir().v.iter()).chain(std::iter::once(&self.t))
+		{
+			hash = (hash ^ x.to_bits()).wrapping_mul(0xbf58476d1ce4e5b9);
+			hash ^= hash >> 31;
+		}
+		((hash >> 11) as f64 / (1u64 << 53) as f64) >= opacity
+	}
+
+	/* replaces the shading normal with an outward facing one, ignored if it faces away from the ray */
+	pub fn set_shading_normal(&mut self, r: &Ray, normal: Vec3d)
+	{
+		let normal = if self.front_face { normal } else { -normal };
+		if Vec3d::dot(normal, r.dir()) < 0.0
+		{
+			self.normal = normal;
+		}
 	}
 
 	pub fn set_face_normal(&mut self, r: &Ray, normal: Vec3d)
@@ -68,15 +142,20 @@ pub trait Hittable : Sync + Send
 		None
 	}
 
-	fn pdf_value(&self, _o: Vec3d, _v: Vec3d) -> f64
+	fn pdf_value(&self, _o: Vec3d, _v: Vec3d, _time: f64) -> f64
 	{
 		0.0
 	}
 
-	fn random(&self, _o: Vec3d) -> Vec3d
+	fn random(&self, _o: Vec3d, _time: f64) -> Vec3d
 	{
 		Vec3d::new(1.0, 0.0, 0.0)
 	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		None
+	}
 }
 
 pub struct HittableList
@@ -161,13 +240,24 @@ impl Hittable for HittableList
 		rec
 	}
 
-	fn pdf_value(&self, o: Vec3d, v: Vec3d) -> f64
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
 	{
-		self.objects[0].pdf_value(o, v)
+		if self.objects.is_empty()
+		{
+			return 0.0;
+		}
+
+		self.objects.iter().map(|obj| obj.pdf_value(o, v, time)).sum::<f64>() / self.objects.len() as f64
 	}
 
-	fn random(&self, o: Vec3d) -> Vec3d
+	fn random(&self, o: Vec3d, time: f64) -> Vec3d
 	{
-		self.objects[0].random(o)
+		if self.objects.is_empty()
+		{
+			return Vec3d::new(1.0, 0.0, 0.0);
+		}
+
+		let mut rng = rand::thread_rng();
+		self.objects[rng.gen_range(0..self.objects.len())].random(o, time)
 	}
 }
@@ -0,0 +1,248 @@
+use crate::math::vec::Vec3d;
+use crate::onb::Onb;
+
+use std::str::FromStr;
+
+/* IESNA LM-63 photometric data, type C photometry */
+pub struct IesProfile
+{
+	vertical: Vec::<f64>,
+	horizontal: Vec::<f64>,
+	candela: Vec::<Vec::<f64>>,
+	max_candela: f64,
+}
+
+impl IesProfile
+{
+	pub fn new(filename: &str) -> Self
+	{
+		let content = std::fs::read_to_string(filename).expect("can't read file");
+		match Self::parse(&content)
+		{
+			Some(profile) => profile,
+			None => panic!("failed to parse {}", filename),
+		}
+	}
+
+	pub fn parse(content: &str) -> Option<Self>
+	{
+		let mut lines = content.lines();
+		let tilt = loop
+		{
+			let line = lines.next()?.trim();
+			if let Some(tilt) = line.strip_prefix("TILT=")
+			{
+				break tilt.trim().to_string();
+			}
+		};
+
+		let mut values = lines.flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ',')).filter(|s| !s.is_empty()).map(f64::from_str);
+		let mut next = move || -> Option<f64> { values.next()?.ok() };
+
+		if tilt == "INCLUDE"
+		{
+			let _geometry = next()?;
+			let count = next()? as usize;
+			for _i in 0..count * 2
+			{
+				next()?;
+			}
+		}
+
+		let _lamps = next()?;
+		let _lumens = next()?;
+		let multiplier = next()?;
+		let nv = next()? as usize;
+		let nh = next()? as usize;
+		let photometric_type = next()? as i32;
+		for _i in 0..4
+		{
+			next()?;
+		}
+		let ballast = next()?;
+		let _future = next()?;
+		let _watts = next()?;
+
+		if photometric_type != 1 || nv == 0 || nh == 0
+		{
+			return None;
+		}
+
+		let mut vertical = Vec::<f64>::with_capacity(nv);
+		for _i in 0..nv
+		{
+			vertical.push(next()?);
+		}
+
+		let mut horizontal = Vec::<f64>::with_capacity(nh);
+		for _i in 0..nh
+		{
+			horizontal.push(next()?);
+		}
+
+		let mut candela = Vec::<Vec::<f64>>::with_capacity(nh);
+		let mut max_candela = 0.0;
+		for _h in 0..nh
+		{
+			let mut row = Vec::<f64>::with_capacity(nv);
+			for _v in 0..nv
+			{
+				let c = next()? * multiplier * ballast;
+				max_candela = f64::max(max_candela, c);
+				row.push(c);
+			}
+			candela.push(row);
+		}
+
+		Some(Self{vertical, horizontal, candela, max_candela})
+	}
+
+	fn lookup(angles: &[f64], a: f64) -> (usize, usize, f64)
+	{
+		if a <= angles[0] || angles.len() == 1
+		{
+			return (0, 0, 0.0);
+		}
+
+		let last = angles.len() - 1;
+		if a >= angles[last]
+		{
+			return (last, last, 0.0);
+		}
+
+		let i = angles.partition_point(|x| *x <= a) - 1;
+		let t = (a - angles[i]) / (angles[i + 1] - angles[i]);
+		(i, i + 1, t)
+	}
+
+	/* candela at vertical angle theta from nadir and horizontal angle phi, in degrees */
+	pub fn candela(&self, theta: f64, phi: f64) -> f64
+	{
+		let mut phi = phi.rem_euclid(360.0);
+		let last = self.horizontal[self.horizontal.len() - 1];
+		if last <= 0.0
+		{
+			phi = 0.0;
+		}
+		else if last <= 90.0
+		{
+			phi = if phi > 270.0 { 360.0 - phi } else if phi > 180.0 { phi - 180.0 } else if phi > 90.0 { 180.0 - phi } else { phi };
+		}
+		else if last <= 180.0 && phi > 180.0
+		{
+			phi = 360.0 - phi;
+		}
+
+		let (v0, v1, tv) = Self::lookup(&self.vertical, theta);
+		let (h0, h1, th) = Self::lookup(&self.horizontal, phi);
+		let c0 = self.candela[h0][v0] * (1.0 - tv) + self.candela[h0][v1] * tv;
+		let c1 = self.candela[h1][v0] * (1.0 - tv) + self.candela[h1][v1] * tv;
+		c0 * (1.0 - th) + c1 * th
+	}
+
+	/* intensity relative to the brightest direction */
+	pub fn value(&self, theta: f64, phi: f64) -> f64
+	{
+		if self.max_candela <= 0.0
+		{
+			return 0.0;
+		}
+
+		self.candela(theta, phi) / self.max_candela
+	}
+
+	/* relative intensity towards dir, for a fixture whose nadir is the w axis of frame */
+	pub fn value_towards(&self, frame: &Onb, dir: Vec3d) -> f64
+	{
+		let dir = Vec3d::normalize(dir);
+		let theta = f64::acos(f64::clamp(Vec3d::dot(dir, frame.w()), -1.0, 1.0));
+		let phi = f64::atan2(Vec3d::dot(dir, frame.v()), Vec3d::dot(dir, frame.u()));
+		self.value(f64::to_degrees(theta), f64::to_degrees(phi))
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	const EPSILON: f64 = 1e-9;
+
+	/* two vertical and three horizontal angles, quadrant symmetric, with a tilt line and a multiplier of 2 */
+	const FIXTURE: &str = "IESNA:LM-63-2002
+[TEST] small fixture
+[MANUFAC] none
+TILT=NONE
+1 1000 2 3 3 1 2 0.5 0.5 0
+1.0 1.0 100
+0 45 90
+0 45 90
+100 80 0
+50 40 0
+10 8 0
+";
+
+	fn profile() -> IesProfile
+	{
+		IesProfile::parse(FIXTURE).unwrap()
+	}
+
+	#[test]
+	fn test_angle_tables()
+	{
+		let profile = profile();
+		assert_eq!(profile.vertical, vec![0.0, 45.0, 90.0]);
+		assert_eq!(profile.horizontal, vec![0.0, 45.0, 90.0]);
+		assert_eq!(profile.candela.len(), 3);
+		assert_eq!(profile.candela[1], vec![100.0, 80.0, 0.0]);
+		assert_eq!(profile.max_candela, 200.0);
+	}
+
+	#[test]
+	fn test_table_values()
+	{
+		let profile = profile();
+		assert!((profile.candela(0.0, 0.0) - 200.0).abs() < EPSILON);
+		assert!((profile.candela(45.0, 45.0) - 80.0).abs() < EPSILON);
+		assert!((profile.candela(90.0, 0.0)).abs() < EPSILON);
+	}
+
+	#[test]
+	fn test_bilinear_interpolation()
+	{
+		let profile = profile();
+		/* halfway between 0 and 45 degrees both ways, the average of the four corners */
+		let expected = (200.0 + 160.0 + 100.0 + 80.0) / 4.0;
+		assert!((profile.candela(22.5, 22.5) - expected).abs() < EPSILON);
+		assert!((profile.candela(67.5, 90.0) - 8.0).abs() < EPSILON);
+	}
+
+	#[test]
+	fn test_quadrant_symmetry()
+	{
+		let profile = profile();
+		for phi in [10.0, 30.0, 80.0]
+		{
+			let c = profile.candela(30.0, phi);
+			assert!((profile.candela(30.0, 180.0 - phi) - c).abs() < EPSILON);
+			assert!((profile.candela(30.0, 180.0 + phi) - c).abs() < EPSILON);
+			assert!((profile.candela(30.0, 360.0 - phi) - c).abs() < EPSILON);
+		}
+	}
+
+	#[test]
+	fn test_tilt_include()
+	{
+		let content = "TILT=INCLUDE\n1\n2\n0 90\n1 1\n1 -1 1 1 1 1 2 0 0 0\n1 1 0\n0\n0\n42\n";
+		let profile = IesProfile::parse(content).unwrap();
+		assert!((profile.value(0.0, 0.0) - 1.0).abs() < EPSILON);
+		assert!((profile.candela(30.0, 0.0) - 42.0).abs() < EPSILON);
+	}
+
+	#[test]
+	fn test_rejects_type_b()
+	{
+		assert!(IesProfile::parse("TILT=NONE\n1 -1 1 1 1 2 2 0 0 0\n1 1 0\n0\n0\n42\n").is_none());
+	}
+}
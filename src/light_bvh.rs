@@ -0,0 +1,512 @@
+use crate::hittable::
+{
+	HitRecord,
+	Hittable,
+};
+use crate::materials::Material;
+use crate::math::
+{
+	aabb::Aabb,
+	vec::
+	{
+		Vec2d,
+		Vec3d,
+	}
+};
+use crate::ray::Ray;
+
+use rand::Rng;
+
+use std::f64::consts::PI;
+
+use std::sync::Arc;
+
+#[derive(Debug, Copy, Clone)]
+pub struct LightBounds
+{
+	pub aabb: Aabb,
+	pub phi: f64,
+	pub w: Vec3d,
+	pub cos_theta_o: f64,
+	pub cos_theta_e: f64,
+	pub two_sided: bool,
+}
+
+impl LightBounds
+{
+	pub fn new(aabb: Aabb, phi: f64, w: Vec3d, cos_theta_o: f64, cos_theta_e: f64, two_sided: bool) -> Self
+	{
+		Self{aabb, phi, w: Vec3d::normalize(w), cos_theta_o, cos_theta_e, two_sided}
+	}
+
+	pub fn union(a: &Self, b: &Self) -> Self
+	{
+		if a.phi == 0.0
+		{
+			return *b;
+		}
+
+		if b.phi == 0.0
+		{
+			return *a;
+		}
+
+		let (w, cos_theta_o) = Self::cone_union(a.w, a.cos_theta_o, b.w, b.cos_theta_o);
+		Self
+		{
+			aabb: Aabb::surrounding_box(&a.aabb, &b.aabb),
+			phi: a.phi + b.phi,
+			w,
+			cos_theta_o,
+			cos_theta_e: f64::min(a.cos_theta_e, b.cos_theta_e),
+			two_sided: a.two_sided || b.two_sided,
+		}
+	}
+
+	fn cone_union(wa: Vec3d, cos_a: f64, wb: Vec3d, cos_b: f64) -> (Vec3d, f64)
+	{
+		let theta_a = f64::acos(f64::clamp(cos_a, -1.0, 1.0));
+		let theta_b = f64::acos(f64::clamp(cos_b, -1.0, 1.0));
+		let theta_d = f64::acos(f64::clamp(Vec3d::dot(wa, wb), -1.0, 1.0));
+		if f64::min(theta_d + theta_b, PI) <= theta_a
+		{
+			return (wa, cos_a);
+		}
+
+		if f64::min(theta_d + theta_a, PI) <= theta_b
+		{
+			return (wb, cos_b);
+		}
+
+		let theta_o = (theta_a + theta_d + theta_b) / 2.0;
+		if theta_o >= PI
+		{
+			return (wa, -1.0);
+		}
+
+		let wr = Vec3d::cross(wa, wb);
+		if Vec3d::dot(wr, wr) == 0.0
+		{
+			return (wa, -1.0);
+		}
+
+		/* rotate wa towards wb by theta_o - theta_a around wr */
+		let k = Vec3d::normalize(wr);
+		let theta_r = theta_o - theta_a;
+		let w = wa * f64::cos(theta_r) + Vec3d::cross(k, wa) * f64::sin(theta_r) + k * Vec3d::dot(k, wa) * (1.0 - f64::cos(theta_r));
+		(Vec3d::normalize(w), f64::cos(theta_o))
+	}
+
+	fn centroid(&self) -> Vec3d
+	{
+		(self.aabb.min() + self.aabb.max()) * 0.5
+	}
+
+	pub fn importance(&self, p: Vec3d) -> f64
+	{
+		if self.phi <= 0.0
+		{
+			return 0.0;
+		}
+
+		let pc = self.centroid();
+		let half_diagonal = Vec3d::length(self.aabb.max() - self.aabb.min()) / 2.0;
+		let d2 = f64::max(Vec3d::dot(p - pc, p - pc), half_diagonal * half_diagonal);
+
+		let wi = p - pc;
+		let mut cos_theta_w = if Vec3d::dot(wi, wi) > 0.0 { Vec3d::dot(self.w, Vec3d::normalize(wi)) } else { 1.0 };
+		if self.two_sided
+		{
+			cos_theta_w = f64::abs(cos_theta_w);
+		}
+		let sin_theta_w = f64::sqrt(f64::max(0.0, 1.0 - cos_theta_w * cos_theta_w));
+
+		/* angle subtended by the bounding sphere of the light bounds as seen from p */
+		let distance_squared = Vec3d::dot(p - pc, p - pc);
+		let cos_theta_b = if distance_squared < half_diagonal * half_diagonal
+		{
+			-1.0
+		}
+		else
+		{
+			f64::sqrt(f64::max(0.0, 1.0 - half_diagonal * half_diagonal / distance_squared))
+		};
+		let sin_theta_b = f64::sqrt(f64::max(0.0, 1.0 - cos_theta_b * cos_theta_b));
+
+		let sin_theta_o = f64::sqrt(f64::max(0.0, 1.0 - self.cos_theta_o * self.cos_theta_o));
+		let (cos_theta_x, sin_theta_x) = if cos_theta_w > self.cos_theta_o
+		{
+			(1.0, 0.0)
+		}
+		else
+		{
+			(cos_theta_w * self.cos_theta_o + sin_theta_w * sin_theta_o,
+			 sin_theta_w * self.cos_theta_o - cos_theta_w * sin_theta_o)
+		};
+
+		let cos_theta_p = if cos_theta_x > cos_theta_b
+		{
+			1.0
+		}
+		else
+		{
+			cos_theta_x * cos_theta_b + sin_theta_x * sin_theta_b
+		};
+
+		if cos_theta_p <= self.cos_theta_e
+		{
+			return 0.0;
+		}
+
+		self.phi * cos_theta_p / d2
+	}
+
+	/* average front and back face emitted luminance at the given surface samples */
+	pub fn estimate_radiance(material: &Arc::<dyn Material>, samples: &[(Vec3d, Vec3d, Vec2d)]) -> (f64, f64)
+	{
+		let mut front = 0.0;
+		let mut back = 0.0;
+		for (p, n, uv) in samples
+		{
+			let r = Ray::new(*p + *n, -*n);
+			let rec = HitRecord::new(&r, *p, 1.0, *uv, *n, material.clone());
+			front += material.emitted(&r, &rec, *uv, *p).luminance();
+
+			let r = Ray::new(*p - *n, *n);
+			let rec = HitRecord::new(&r, *p, 1.0, *uv, *n, material.clone());
+			back += material.emitted(&r, &rec, *uv, *p).luminance();
+		}
+
+		(front / samples.len() as f64, back / samples.len() as f64)
+	}
+
+	pub fn from_surface(aabb: Aabb, area: f64, normal: Vec3d, material: &Arc::<dyn Material>, samples: &[(Vec3d, Vec3d, Vec2d)]) -> Option<Self>
+	{
+		let (front, back) = Self::estimate_radiance(material, samples);
+		if front <= 0.0 && back <= 0.0
+		{
+			return None;
+		}
+
+		let phi = (front + back) * area * PI;
+		if back <= 0.0
+		{
+			return Some(Self::new(aabb, phi, normal, 1.0, 0.0, false));
+		}
+
+		if front <= 0.0
+		{
+			return Some(Self::new(aabb, phi, -normal, 1.0, 0.0, false));
+		}
+
+		Some(Self::new(aabb, phi, normal, 1.0, 0.0, true))
+	}
+}
+
+enum LightBvhNode
+{
+	Leaf(Arc::<dyn Hittable>),
+	Interior(Box::<LightBvhTree>, Box::<LightBvhTree>),
+}
+
+struct LightBvhTree
+{
+	bounds: LightBounds,
+	node: LightBvhNode,
+}
+
+impl LightBvhTree
+{
+	fn new(mut lights: Vec::<(Arc::<dyn Hittable>, LightBounds)>) -> Self
+	{
+		if lights.len() == 1
+		{
+			let (light, bounds) = lights.remove(0);
+			return Self{bounds, node: LightBvhNode::Leaf(light)};
+		}
+
+		let mut cmin = Vec3d::newv(f64::INFINITY);
+		let mut cmax = Vec3d::newv(-f64::INFINITY);
+		for (_, bounds) in &lights
+		{
+			cmin = Vec3d::min(cmin, bounds.centroid());
+			cmax = Vec3d::max(cmax, bounds.centroid());
+		}
+
+		let extent = cmax - cmin;
+		let axis = if extent.x() > extent.y() && extent.x() > extent.z()
+		{
+			0
+		}
+		else if extent.y() > extent.z()
+		{
+			1
+		}
+		else
+		{
+			2
+		};
+
+		lights.sort_unstable_by(|a, b| a.1.centroid().v[axis].partial_cmp(&b.1.centroid().v[axis]).unwrap_or(std::cmp::Ordering::Equal));
+
+		let mid = lights.len() / 2;
+		let left_lights = lights.drain(0..mid).collect();
+		let left = Self::new(left_lights);
+		let right = Self::new(lights);
+		let bounds = LightBounds::union(&left.bounds, &right.bounds);
+		Self{bounds, node: LightBvhNode::Interior(Box::new(left), Box::new(right))}
+	}
+
+	fn children_probability(left: &Self, right: &Self, o: Vec3d) -> Option<(f64, f64)>
+	{
+		let li = left.bounds.importance(o);
+		let ri = right.bounds.importance(o);
+		if li + ri <= 0.0
+		{
+			return None;
+		}
+
+		Some((li / (li + ri), ri / (li + ri)))
+	}
+
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
+	{
+		match &self.node
+		{
+			LightBvhNode::Leaf(light) => light.pdf_value(o, v, time),
+			LightBvhNode::Interior(left, right) =>
+			{
+				if !self.bounds.aabb.hit(&Ray::with_time(o, v, time), 0.001, f64::INFINITY)
+				{
+					return 0.0;
+				}
+
+				match Self::children_probability(left, right, o)
+				{
+					None => 0.0,
+					Some((pl, pr)) =>
+					{
+						let mut pdf = 0.0;
+						if pl > 0.0
+						{
+							pdf += pl * left.pdf_value(o, v, time);
+						}
+						if pr > 0.0
+						{
+							pdf += pr * right.pdf_value(o, v, time);
+						}
+						pdf
+					}
+				}
+			}
+		}
+	}
+
+	fn random(&self, o: Vec3d, time: f64) -> Option<Vec3d>
+	{
+		match &self.node
+		{
+			LightBvhNode::Leaf(light) => Some(light.random(o, time)),
+			LightBvhNode::Interior(left, right) =>
+			{
+				let (pl, _pr) = Self::children_probability(left, right, o)?;
+				let mut rng = rand::thread_rng();
+				if rng.gen_range(0.0..1.0) < pl
+				{
+					left.random(o, time)
+				}
+				else
+				{
+					right.random(o, time)
+				}
+			}
+		}
+	}
+}
+
+pub struct LightBvh
+{
+	root: Option<LightBvhTree>,
+}
+
+impl LightBvh
+{
+	pub fn new(lights: Vec::<Arc::<dyn Hittable>>) -> Self
+	{
+		let lights: Vec::<(Arc::<dyn Hittable>, LightBounds)> = lights.into_iter().filter_map(|light|
+		{
+			let bounds = light.light_bounds()?;
+			if bounds.phi > 0.0
+			{
+				Some((light, bounds))
+			}
+			else
+			{
+				None
+			}
+		}).collect();
+
+		if lights.is_empty()
+		{
+			return Self{root: None};
+		}
+
+		Self{root: Some(LightBvhTree::new(lights))}
+	}
+
+	pub fn is_empty(&self) -> bool
+	{
+		self.root.is_none()
+	}
+}
+
+impl Hittable for LightBvh
+{
+	fn hit(&self, _r: &Ray, _tmin: f64, _tmax: f64) -> Option<HitRecord>
+	{
+		None
+	}
+
+	fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb>
+	{
+		self.root.as_ref().map(|root| root.bounds.aabb)
+	}
+
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
+	{
+		match &self.root
+		{
+			None => 0.0,
+			Some(root) => root.pdf_value(o, v, time),
+		}
+	}
+
+	fn random(&self, o: Vec3d, time: f64) -> Vec3d
+	{
+		match self.root.as_ref().and_then(|root| root.random(o, time))
+		{
+			None => Vec3d::new(1.0, 0.0, 0.0),
+			Some(dir) => dir,
+		}
+	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		self.root.as_ref().map(|root| root.bounds)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::materials::diffuse_light::DiffuseLight;
+	use crate::objects::sphere::Sphere;
+	use crate::textures::solid_color::SolidColor;
+
+	fn angle(a: Vec3d, b: Vec3d) -> f64
+	{
+		f64::acos(f64::clamp(Vec3d::dot(a, b), -1.0, 1.0))
+	}
+
+	fn point_bounds(p: Vec3d, phi: f64, w: Vec3d, two_sided: bool) -> LightBounds
+	{
+		LightBounds::new(Aabb::new(p, p), phi, w, 1.0, 0.0, two_sided)
+	}
+
+	fn sphere_light(center: Vec3d, emit: f64) -> Arc::<dyn Hittable>
+	{
+		Arc::new(Sphere::new(center, 1.0, Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(Vec3d::newv(emit)))))))
+	}
+
+	#[test]
+	fn test_cone_union_bounds_both_cones()
+	{
+		let wa = Vec3d::new(1.0, 0.0, 0.0);
+		let wb = Vec3d::new(0.0, 1.0, 0.0);
+		let (w, cos_theta) = LightBounds::cone_union(wa, f64::cos(f64::to_radians(30.0)), wb, f64::cos(f64::to_radians(10.0)));
+		let theta = f64::acos(cos_theta);
+		assert!(angle(w, wa) + f64::to_radians(30.0) <= theta + 1e-9);
+		assert!(angle(w, wb) + f64::to_radians(10.0) <= theta + 1e-9);
+		assert!((theta - f64::to_radians(65.0)).abs() < 1e-9);
+
+		/* a cone inside the other leaves it unchanged */
+		let (w, cos_theta) = LightBounds::cone_union(wa, 0.0, Vec3d::normalize(Vec3d::new(1.0, 0.1, 0.0)), 0.9);
+		assert_eq!(w, wa);
+		assert_eq!(cos_theta, 0.0);
+	}
+
+	#[test]
+	fn test_union_skips_dark_bounds()
+	{
+		let lit = point_bounds(Vec3d::zero(), 2.0, Vec3d::new(0.0, 1.0, 0.0), false);
+		let dark = point_bounds(Vec3d::newv(5.0), 0.0, Vec3d::new(1.0, 0.0, 0.0), true);
+		let union = LightBounds::union(&dark, &lit);
+		assert_eq!(union.phi, 2.0);
+		assert_eq!(union.aabb.max(), Vec3d::zero());
+		assert!(!union.two_sided);
+	}
+
+	#[test]
+	fn test_importance_follows_the_emission()
+	{
+		let up = Vec3d::new(0.0, 1.0, 0.0);
+		let bounds = point_bounds(Vec3d::zero(), 1.0, up, false);
+		assert!((bounds.importance(up * 2.0) / bounds.importance(up * 4.0) - 4.0).abs() < 1e-9);
+		assert_eq!(bounds.importance(-up * 2.0), 0.0);
+		assert!(point_bounds(Vec3d::zero(), 1.0, up, true).importance(-up * 2.0) > 0.0);
+	}
+
+	#[test]
+	fn test_pdf_integrates_to_one()
+	{
+		let bvh = LightBvh::new(vec![
+			sphere_light(Vec3d::new(5.0, 0.0, 0.0), 1.0),
+			sphere_light(Vec3d::new(-4.0, 1.0, 0.0), 4.0),
+			sphere_light(Vec3d::new(0.0, 6.0, 2.0), 2.0),
+			sphere_light(Vec3d::new(1.0, -3.0, -5.0), 8.0),
+		]);
+		let n = 400;
+		let mut integral = 0.0;
+		for i in 0..n
+		{
+			for j in 0..n * 2
+			{
+				let theta = (i as f64 + 0.5) / n as f64 * PI;
+				let phi = (j as f64 + 0.5) / (n * 2) as f64 * 2.0 * PI;
+				let v = Vec3d::new(f64::sin(theta) * f64::cos(phi), f64::cos(theta), f64::sin(theta) * f64::sin(phi));
+				integral += bvh.pdf_value(Vec3d::zero(), v, 0.0) * f64::sin(theta);
+			}
+		}
+		integral *= (PI / n as f64) * (2.0 * PI / (n * 2) as f64);
+		assert!((integral - 1.0).abs() < 0.02);
+	}
+
+	#[test]
+	fn test_brighter_lights_are_sampled_more()
+	{
+		let dim = Vec3d::new(5.0, 0.0, 0.0);
+		let bright = Vec3d::new(-5.0, 0.0, 0.0);
+		let bvh = LightBvh::new(vec![sphere_light(dim, 1.0), sphere_light(bright, 3.0)]);
+		let n = 10000;
+		let mut towards_bright = 0;
+		for _ in 0..n
+		{
+			let v = bvh.random(Vec3d::zero(), 0.0);
+			assert!(bvh.pdf_value(Vec3d::zero(), v, 0.0) > 0.0);
+			if v.x() < 0.0
+			{
+				towards_bright += 1;
+			}
+		}
+		assert!((towards_bright as f64 / n as f64 - 0.75).abs() < 0.02);
+	}
+
+	#[test]
+	fn test_dark_lights_are_dropped()
+	{
+		assert!(LightBvh::new(vec![sphere_light(Vec3d::zero(), 0.0)]).is_empty());
+		assert!(!LightBvh::new(vec![sphere_light(Vec3d::zero(), 1.0)]).is_empty());
+	}
+}
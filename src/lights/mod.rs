@@ -0,0 +1,19 @@
+pub mod point_light;
+pub mod spot_light;
+
+use crate::math::vec::Vec3d;
+
+/* radiance arriving at a point from a light, along wi towards the light */
+pub struct LightSample
+{
+	pub wi: Vec3d,
+	pub distance: f64,
+	pub li: Vec3d,
+}
+
+/* light at a single position, no ray can hit it so it is only reached by sampling it */
+pub trait Light: Send + Sync
+{
+	/* None when the light does not illuminate p */
+	fn sample_li(&self, p: Vec3d) -> Option<LightSample>;
+}
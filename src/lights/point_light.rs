@@ -0,0 +1,106 @@
+use crate::ies::IesProfile;
+use crate::math::vec::Vec3d;
+use crate::onb::Onb;
+use crate::scene::Scene;
+
+use std::sync::Arc;
+
+use super::
+{
+	Light,
+	LightSample,
+};
+
+pub struct PointLight
+{
+	position: Vec3d,
+	intensity: Vec3d,
+	profile: Option::<(Arc::<IesProfile>, Onb)>,
+}
+
+impl PointLight
+{
+	/* intensity is the radiant intensity, the power per unit solid angle */
+	pub fn new(position: Vec3d, intensity: Vec3d) -> Self
+	{
+		Self{position, intensity, profile: None}
+	}
+
+	/* intensity is then the one of the brightest direction of the profile */
+	pub fn with_profile(mut self, profile: Arc::<IesProfile>, axis: Vec3d) -> Self
+	{
+		self.profile = Some((profile, Onb::from_w(axis)));
+		self
+	}
+
+	pub fn from(_scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let position = Scene::parse_xml_vec3d(&child("Position").expect("no Position node found for PointLight"));
+		let intensity = Scene::parse_xml_vec3d(&child("Intensity").expect("no Intensity node found for PointLight"));
+		let mut light = Self::new(position, intensity);
+		if let Some(profile) = child("Profile")
+		{
+			let (profile, axis) = Scene::parse_xml_profile(&profile);
+			light = light.with_profile(profile, axis);
+		}
+		light
+	}
+}
+
+impl Light for PointLight
+{
+	fn sample_li(&self, p: Vec3d) -> Option<LightSample>
+	{
+		let to_light = self.position - p;
+		let distance = Vec3d::length(to_light);
+		if distance <= 0.0
+		{
+			return None;
+		}
+
+		let wi = to_light / distance;
+		let scale = match &self.profile
+		{
+			None => 1.0,
+			Some((profile, frame)) => profile.value_towards(frame, -wi),
+		};
+
+		if scale <= 0.0
+		{
+			return None;
+		}
+
+		Some(LightSample{wi, distance, li: self.intensity * (scale / (distance * distance))})
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	#[test]
+	fn test_inverse_square()
+	{
+		let light = PointLight::new(Vec3d::new(0.0, 4.0, 0.0), Vec3d::newv(8.0));
+		let sample = light.sample_li(Vec3d::zero()).unwrap();
+		assert_eq!(sample.distance, 4.0);
+		assert_eq!(sample.wi, Vec3d::new(0.0, 1.0, 0.0));
+		assert_eq!(sample.li, Vec3d::newv(0.5));
+	}
+
+	#[test]
+	fn test_profile_orientation()
+	{
+		/* all the light goes straight down the axis, nothing above 45 degrees from nadir */
+		let profile = Arc::new(IesProfile::parse("IESNA:LM-63-2002\nTILT=NONE\n1 -1 1 3 1 1 2 0 0 0\n1 1 0\n0 45 90\n0\n100 100 0\n").unwrap());
+		let light = PointLight::new(Vec3d::zero(), Vec3d::one()).with_profile(profile, Vec3d::new(0.0, -1.0, 0.0));
+		let below = light.sample_li(Vec3d::new(0.0, -1.0, 0.0)).unwrap();
+		assert!(Vec3d::length(below.li - Vec3d::one()) < 1e-9);
+		assert!(light.sample_li(Vec3d::new(0.0, 1.0, 0.0)).is_none());
+		assert!(light.sample_li(Vec3d::new(1.0, 0.0, 0.0)).is_none());
+	}
+}
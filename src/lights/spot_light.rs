@@ -0,0 +1,160 @@
+use crate::ies::IesProfile;
+use crate::math::vec::Vec3d;
+use crate::onb::Onb;
+use crate::scene::Scene;
+
+use std::sync::Arc;
+
+use super::
+{
+	Light,
+	LightSample,
+};
+
+pub struct SpotLight
+{
+	position: Vec3d,
+	direction: Vec3d,
+	intensity: Vec3d,
+	cos_falloff_start: f64,
+	cos_falloff_end: f64,
+	profile: Option::<(Arc::<IesProfile>, Onb)>,
+}
+
+impl SpotLight
+{
+	/*
+	 * the cone is lit up to angle degrees from its axis, the last falloff degrees of it
+	 * fade smoothly to black
+	 */
+	pub fn new(position: Vec3d, target: Vec3d, intensity: Vec3d, angle: f64, falloff: f64) -> Self
+	{
+		let direction = Vec3d::normalize(target - position);
+		let cos_falloff_end = f64::cos(f64::to_radians(angle));
+		let cos_falloff_start = f64::cos(f64::to_radians(f64::max(angle - falloff, 0.0)));
+		Self{position, direction, intensity, cos_falloff_start, cos_falloff_end, profile: None}
+	}
+
+	/* the profile shapes the light inside the cone, intensity is then the one of its brightest direction */
+	pub fn with_profile(mut self, profile: Arc::<IesProfile>, axis: Vec3d) -> Self
+	{
+		self.profile = Some((profile, Onb::from_w(axis)));
+		self
+	}
+
+	pub fn from(_scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let position = Scene::parse_xml_vec3d(&child("Position").expect("no Position node found for SpotLight"));
+		let target = Scene::parse_xml_vec3d(&child("Target").expect("no Target node found for SpotLight"));
+		let intensity = Scene::parse_xml_vec3d(&child("Intensity").expect("no Intensity node found for SpotLight"));
+		let cone = child("Cone").expect("no Cone node found for SpotLight");
+		let falloff = cone.attribute("falloff").map_or(0.0, |_| Scene::parse_xml_attr_f64(&cone, "falloff".to_string()));
+		let mut light = Self::new(position, target, intensity, Scene::parse_xml_attr_f64(&cone, "angle".to_string()), falloff);
+		if let Some(profile) = child("Profile")
+		{
+			let (profile, axis) = Scene::parse_xml_profile(&profile);
+			light = light.with_profile(profile, axis);
+		}
+		light
+	}
+
+	fn falloff(&self, cos_theta: f64) -> f64
+	{
+		if cos_theta >= self.cos_falloff_start
+		{
+			return 1.0;
+		}
+
+		let t = f64::clamp((cos_theta - self.cos_falloff_end) / (self.cos_falloff_start - self.cos_falloff_end), 0.0, 1.0);
+		t * t * (3.0 - 2.0 * t)
+	}
+}
+
+impl Light for SpotLight
+{
+	fn sample_li(&self, p: Vec3d) -> Option<LightSample>
+	{
+		let to_light = self.position - p;
+		let distance = Vec3d::length(to_light);
+		if distance <= 0.0
+		{
+			return None;
+		}
+
+		let wi = to_light / distance;
+		let mut scale = self.falloff(Vec3d::dot(-wi, self.direction));
+		if let Some((profile, frame)) = &self.profile
+		{
+			scale *= profile.value_towards(frame, -wi);
+		}
+
+		if scale <= 0.0
+		{
+			return None;
+		}
+
+		Some(LightSample{wi, distance, li: self.intensity * (scale / (distance * distance))})
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	fn light() -> SpotLight
+	{
+		SpotLight::new(Vec3d::zero(), Vec3d::new(0.0, -1.0, 0.0), Vec3d::one(), 30.0, 10.0)
+	}
+
+	fn at_angle(degrees: f64) -> Vec3d
+	{
+		let radians = f64::to_radians(degrees);
+		Vec3d::new(f64::sin(radians), -f64::cos(radians), 0.0)
+	}
+
+	#[test]
+	fn test_inside_cone()
+	{
+		let sample = light().sample_li(at_angle(15.0) * 2.0).unwrap();
+		assert!(Vec3d::length(sample.li - Vec3d::newv(0.25)) < 1e-9);
+	}
+
+	#[test]
+	fn test_outside_cone()
+	{
+		assert!(light().sample_li(at_angle(31.0)).is_none());
+		assert!(light().sample_li(Vec3d::new(0.0, 1.0, 0.0)).is_none());
+	}
+
+	#[test]
+	fn test_falloff_is_monotonic()
+	{
+		let light = light();
+		let mut last = 1.0;
+		for i in 0..=100
+		{
+			let falloff = light.falloff(f64::cos(f64::to_radians(20.0 + i as f64 * 0.1)));
+			assert!(falloff <= last && falloff >= 0.0);
+			last = falloff;
+		}
+		assert!((light.falloff(f64::cos(f64::to_radians(20.0))) - 1.0).abs() < 1e-9);
+		assert!(light.falloff(f64::cos(f64::to_radians(30.0))).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_profile_inside_cone()
+	{
+		/* half the intensity at 10 degrees from nadir, the cone falloff still applies on top */
+		let profile = Arc::new(IesProfile::parse("IESNA:LM-63-2002\nTILT=NONE\n1 -1 1 3 1 1 2 0 0 0\n1 1 0\n0 20 90\n0\n100 0 0\n").unwrap());
+		let light = light().with_profile(profile, Vec3d::new(0.0, -1.0, 0.0));
+		let below = light.sample_li(at_angle(0.0)).unwrap();
+		assert!(Vec3d::length(below.li - Vec3d::one()) < 1e-9);
+		let inside = light.sample_li(at_angle(10.0)).unwrap();
+		assert!(Vec3d::length(inside.li - Vec3d::newv(0.5)) < 1e-9);
+		assert!(light.sample_li(at_angle(25.0)).is_none());
+	}
+}
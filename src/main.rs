@@ -1,24 +1,50 @@
+mod apertures;
+mod backgrounds;
+mod bsdf;
 mod bvh;
-mod camera;
+mod cameras;
+mod color;
 mod hittable;
+mod ies;
+mod light_bvh;
+mod lights;
 mod math;
 mod materials;
+mod media;
 mod objects;
 mod onb;
 mod perlin;
+mod phase_functions;
 mod pdf;
 mod ray;
 mod scene;
+mod spectrum;
 mod textures;
 
-use crate::hittable::Hittable;
-use crate::math::vec::Vec3d;
-use crate::ray::Ray;
+use crate::bsdf::LOBE_DIFFUSE;
+use crate::color::PathColor;
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+	Vec4d,
+};
+use crate::media::
+{
+	MediumStack,
+	sample_interaction,
+};
+use crate::ray::
+{
+	Ray,
+	RayKind,
+};
 use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
 use crate::pdf::
 {
 	Pdf,
-	cosine_pdf::CosinePdf,
+	background_pdf::BackgroundPdf,
 	hittable_pdf::HittablePdf,
 	mixture_pdf::MixturePdf,
 };
@@ -66,30 +92,301 @@ impl Pixel
 	}
 }
 
-fn ray_color(r: &Ray, background: Vec3d, objects: Arc::<dyn Hittable>, lights: Arc::<dyn Hittable>, depth: i32) -> Vec3d
+/* bound on the events inside media and on interface crossings, which do not count towards the depth */
+const MAX_MEDIUM_SCATTERS: i32 = 1024;
+
+fn power_heuristic(a: f64, b: f64) -> f64
+{
+	a * a / (a * a + b * b)
+}
+
+fn light_pdf(scene: &Scene, p: Vec3d, time: f64) -> Option<MixturePdf>
+{
+	let mut pdfs: Vec::<Arc::<dyn Pdf>> = Vec::new();
+	if let Some(lights) = scene.lights()
+	{
+		pdfs.push(Arc::new(HittablePdf::new(lights, p, time)));
+	}
+	if scene.background().is_sampled()
+	{
+		pdfs.push(Arc::new(BackgroundPdf::new(scene.background())));
+	}
+
+	if pdfs.is_empty()
+	{
+		return None;
+	}
+
+	Some(MixturePdf::new(pdfs))
+}
+
+/*
+ * radiance arriving along a shadow ray, only what light sampling can generate is counted
+ * the ray crosses medium interfaces and is attenuated by the media it goes through
+ */
+fn shadow_color<C: PathColor>(r: &Ray, scene: &Scene, light_links: u64, lambda: &SampledWavelengths, media: &MediumStack) -> C
+{
+	let mut rng = rand::thread_rng();
+	let mut ray = *r;
+	let mut media = media.clone();
+	let mut transmittance = C::one();
+
+	for _ in 0..MAX_MEDIUM_SCATTERS
+	{
+		let hit = scene.objects().hit(&ray, 0.001, f64::INFINITY);
+		if let Some(current) = media.current()
+		{
+			let tmax = media.tmax(&ray, hit.as_ref().map(|rec| rec.t));
+			transmittance *= media::transmittance::<C>(current.as_ref(), &ray, tmax, lambda, &mut rng);
+		}
+
+		let rec = match hit
+		{
+			Some(rec) => rec,
+			None =>
+			{
+				if scene.background().is_sampled()
+				{
+					return transmittance * C::from_rgb(scene.background().value(ray.dir()), lambda);
+				}
+
+				return C::zero();
+			}
+		};
+
+		if rec.material.is_interface()
+		{
+			media.cross(&rec);
+			ray = Ray::with_kind(rec.p, ray.dir(), ray.time(), ray.kind());
+			continue;
+		}
+
+		if light_links & rec.light_group == 0
+		{
+			return C::zero();
+		}
+
+		return transmittance * C::emitted(&ray, &rec, lambda);
+	}
+
+	C::zero()
+}
+
+/* transmittance of a shadow ray towards a point light at distance tmax, zero when something blocks it */
+fn shadow_transmittance<C: PathColor>(r: &Ray, tmax: f64, scene: &Scene, lambda: &SampledWavelengths, media: &MediumStack) -> C
 {
-	if depth <= 0
+	let mut rng = rand::thread_rng();
+	let mut ray = *r;
+	let mut tmax = tmax;
+	let mut media = media.clone();
+	let mut transmittance = C::one();
+
+	for _ in 0..MAX_MEDIUM_SCATTERS
 	{
-		return Vec3d::newv(0.0);
+		let hit = scene.objects().hit(&ray, 0.001, tmax);
+		if let Some(current) = media.current()
+		{
+			let t = hit.as_ref().map_or(tmax, |rec| rec.t);
+			transmittance *= media::transmittance::<C>(current.as_ref(), &ray, t, lambda, &mut rng);
+		}
+
+		let rec = match hit
+		{
+			Some(rec) => rec,
+			None => return transmittance,
+		};
+
+		if !rec.material.is_interface()
+		{
+			return C::zero();
+		}
+
+		media.cross(&rec);
+		tmax -= rec.t;
+		ray = Ray::with_kind(rec.p, ray.dir(), ray.time(), ray.kind());
 	}
 
-	if let Some(rec) = objects.hit(r, 0.001, f64::INFINITY)
+	C::zero()
+}
+
+/* light reaching p from the point lights, f weights the direction towards each light */
+fn point_light_color<C: PathColor>(scene: &Scene, p: Vec3d, time: f64, lambda: &SampledWavelengths, media: &MediumStack, f: &dyn Fn(Vec3d) -> C) -> C
+{
+	let mut color = C::zero();
+	for light in scene.point_lights()
+	{
+		if let Some(sample) = light.sample_li(p)
+		{
+			let shadow = Ray::with_kind(p, sample.wi, time, RayKind::Shadow);
+			color += f(sample.wi) * C::from_rgb(sample.li, lambda) * shadow_transmittance::<C>(&shadow, sample.distance, scene, lambda, media);
+		}
+	}
+	color
+}
+
+fn ray_color<C: PathColor>(r: &Ray, scene: &Scene, depth: i32) -> Vec3d
+{
+	let mut ray = *r;
+	let mut color = C::zero();
+	let mut throughput = C::one();
+	let mut light_links = !0;
+	/* pdf of the last diffuse bounce and where it happened, for weighting emission hit by it */
+	let mut last_diffuse: Option<(f64, Vec3d)> = None;
+	let mut rng = rand::thread_rng();
+	let mut lambda = SampledWavelengths::sample_uniform(rng.gen_range(0.0..1.0), C::spectral());
+	/* media the path is travelling through, starting in the global medium around the camera */
+	let mut media = MediumStack::new(scene.medium());
+	let mut bounces = 0;
+	let mut scatters = 0;
+
+	while bounces < depth
 	{
-		let emitted = rec.material.emitted(&r, &rec, rec.uv, rec.p);
-		if let Some(tuple) = rec.material.scatter(r, &rec)
+		let hit = scene.objects().hit(&ray, 0.001, f64::INFINITY);
+		if let Some(current) = media.current()
 		{
-			let light_pdf: Arc::<dyn Pdf> = Arc::new(HittablePdf::new(lights.clone(), rec.p));
-			let cosine_pdf: Arc::<dyn Pdf> = Arc::new(CosinePdf::new(rec.normal));
-			let mixed_pdf = MixturePdf::new(light_pdf.clone(), cosine_pdf.clone());
-			let scattered = Ray::with_time(rec.p, mixed_pdf.generate(), r.time());
-			let pdf = mixed_pdf.value(scattered.dir());
-			return emitted + tuple.0 * rec.material.scattering_pdf(r, &rec, &scattered) * ray_color(&scattered, background, objects, lights, depth - 1) / pdf;
+			let tmax = media.tmax(&ray, hit.as_ref().map(|rec| rec.t));
+			let interaction = sample_interaction::<C>(current.as_ref(), &ray, tmax, &lambda, &mut rng);
+			color += throughput * interaction.emitted;
+			throughput *= interaction.weight;
+			if let Some(p) = interaction.scatter
+			{
+				scatters += 1;
+				if scatters > MAX_MEDIUM_SCATTERS
+				{
+					break;
+				}
+
+				let wo = -Vec3d::normalize(ray.dir());
+				let phase = current.phase();
+				if let Some(lights) = light_pdf(scene, p, ray.time())
+				{
+					let shadow = Ray::with_kind(p, Vec3d::normalize(lights.generate()), ray.time(), RayKind::Shadow);
+					let light_pdf = lights.value(shadow.dir());
+					let f = phase.p(wo, shadow.dir());
+					if light_pdf > 0.0 && f > 0.0
+					{
+						let li = shadow_color::<C>(&shadow, scene, light_links, &lambda, &media);
+						color += throughput * li * (f * power_heuristic(light_pdf, phase.pdf(wo, shadow.dir())) / light_pdf);
+					}
+				}
+
+				color += throughput * point_light_color::<C>(scene, p, ray.time(), &lambda, &media, &|wi| C::one() * phase.p(wo, wi));
+
+				let sample = phase.sample(wo, Vec2d::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)));
+				throughput = throughput * (sample.p / sample.pdf);
+				last_diffuse = Some((sample.pdf, p));
+				ray = Ray::with_kind(p, sample.wi, ray.time(), RayKind::Diffuse);
+				continue;
+			}
+		}
+
+		let rec = match hit
+		{
+			Some(rec) => rec,
+			None =>
+			{
+				let mut weight = 1.0;
+				if let (Some((pdf, p)), true) = (last_diffuse, scene.background().is_sampled())
+				{
+					if let Some(lights) = light_pdf(scene, p, ray.time())
+					{
+						weight = power_heuristic(pdf, lights.value(ray.dir()));
+					}
+				}
+				color += throughput * C::from_rgb(scene.background().value(ray.dir()), &lambda) * weight;
+				break;
+			}
+		};
+
+		/* crossing into or out of a medium does not change the direction nor count as a bounce */
+		if rec.material.is_interface()
+		{
+			scatters += 1;
+			if scatters > MAX_MEDIUM_SCATTERS
+			{
+				break;
+			}
+
+			media.cross(&rec);
+			ray = Ray::with_kind(rec.p, ray.dir(), ray.time(), ray.kind());
+			continue;
+		}
+		bounces += 1;
+
+		if light_links & rec.light_group != 0
+		{
+			let emitted = C::emitted(&ray, &rec, &lambda);
+			let mut weight = 1.0;
+			if let Some((pdf, p)) = last_diffuse
+			{
+				if let Some(lights) = light_pdf(scene, p, ray.time())
+				{
+					weight = power_heuristic(pdf, lights.value(ray.dir()));
+				}
+			}
+			color += throughput * emitted * weight;
+		}
+
+		let terminated = lambda.secondary_terminated();
+		let bsdf = match rec.material.bsdf(&ray, &rec, &mut lambda)
+		{
+			Some(bsdf) => bsdf,
+			None => break,
+		};
+
+		/* from here on the path only carries the hero wavelength */
+		if !terminated && lambda.secondary_terminated()
+		{
+			throughput.terminate_secondary(&lambda);
+		}
+
+		let wo = -Vec3d::normalize(ray.dir());
+		if bsdf.is_non_specular()
+		{
+			if let Some(lights) = light_pdf(scene, rec.p, ray.time())
+			{
+				let shadow = Ray::with_kind(rec.p, Vec3d::normalize(lights.generate()), ray.time(), RayKind::Shadow);
+				let light_pdf = lights.value(shadow.dir());
+				let f = bsdf.eval(wo, shadow.dir());
+				if light_pdf > 0.0 && f != Vec3d::zero()
+				{
+					let li = shadow_color::<C>(&shadow, scene, rec.light_links, &lambda, &media);
+					color += throughput * C::from_rgb(f, &lambda) * li * (power_heuristic(light_pdf, bsdf.pdf(wo, shadow.dir())) / light_pdf);
+				}
+			}
+
+			color += throughput * point_light_color::<C>(scene, rec.p, ray.time(), &lambda, &media, &|wi| C::from_rgb(bsdf.eval(wo, wi), &lambda));
 		}
 
-		return emitted
+		let sample = match bsdf.sample(wo, rng.gen_range(0.0..1.0), Vec2d::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)))
+		{
+			Some(sample) => sample,
+			None => break,
+		};
+
+		throughput *= C::from_rgb(sample.f / sample.pdf, &lambda);
+		let kind = if sample.is_transmission()
+		{
+			RayKind::Refraction
+		}
+		else if sample.is_specular() || sample.flags & LOBE_DIFFUSE == 0
+		{
+			RayKind::Reflection
+		}
+		else
+		{
+			RayKind::Diffuse
+		};
+		if sample.is_transmission()
+		{
+			media.cross(&rec);
+		}
+		last_diffuse = if sample.is_specular() { None } else { Some((sample.pdf, rec.p)) };
+		ray = Ray::with_kind(rec.p, sample.wi, ray.time(), kind);
+		light_links = rec.light_links;
 	}
 
-	background
+	color.to_rgb(&lambda)
 }
 
 fn main()
@@ -97,18 +394,19 @@ fn main()
 	let samples = 10000;
 	let max_depth: i32 = 50;
 
-	//let background = Vec3d::new(0.5, 0.5, 0.75);
-	//let background = Vec3d::new(0.02, 0.02, 0.05);
-	let background = Vec3d::zero();
-
 	let pb = ProgressBar::new(1);
 	pb.set_style(ProgressStyle::default_bar().template("[{elapsed_precise}, ETA: {eta}] {wide_bar:} {msg}"));
 
-	//let scene = Scene::random_scene();
-	//let scene = Scene::simple_light_scene();
-	let scene = Scene::cornell_box();
-	//let scene = Scene::chapter2();
-	//let scene = Scene::from_file("scene/cornell.xml");
+	/* a built-in scene by name or an XML scene file, the Cornell box by default */
+	let scene = match std::env::args().nth(1).as_deref()
+	{
+		None | Some("cornell") => Scene::cornell_box(),
+		Some("random")         => Scene::random_scene(),
+		Some("simple")         => Scene::simple_light_scene(),
+		Some("chapter2")       => Scene::chapter2(),
+		Some("cloth")          => Scene::cloth_scene(),
+		Some(filename)         => Scene::from_file(filename),
+	};
 
 	let width = scene.camera().width();
 	let height = scene.camera().height();
@@ -156,8 +454,19 @@ fn main()
 			{
 				let u = (x as f64 + rng.gen_range(0.0..1.0)) / (scene.camera().width() - 1) as f64;
 				let v = (y as f64 + rng.gen_range(0.0..1.0)) / (scene.camera().height() - 1) as f64;
-				let ray = scene.camera().get_ray(u, v);
-				color += ray_color(&ray, background, scene.objects(), scene.lights(), max_depth);
+				let ray = match scene.camera().get_ray(u, v)
+				{
+					Some(ray) => ray,
+					None => continue,
+				};
+				color += if scene.spectral()
+				{
+					ray_color::<Vec4d>(&ray, &scene, max_depth)
+				}
+				else
+				{
+					ray_color::<Vec3d>(&ray, &scene, max_depth)
+				};
 			}
 			color /= samples as f64;
 			color = color.for_each(&f64::sqrt);
@@ -167,8 +476,7 @@ fn main()
 		{
 			let u = (x as f64 + rng.gen_range(0.0..1.0)) / (scene.camera().width() - 1) as f64;
 			let v = (y as f64 + rng.gen_range(0.0..1.0)) / (scene.camera().height() - 1) as f64;
-			let ray = scene.camera().get_ray(u, v);
-			let bvh_depth = scene.objects().bvh_depth(&ray, 0.001, f64::INFINITY);
+			let bvh_depth = scene.camera().get_ray(u, v).and_then(|ray| scene.objects().bvh_depth(&ray, 0.001, f64::INFINITY));
 			color = match bvh_depth
 			{
 				None => Vec3d::zero(),
@@ -201,3 +509,62 @@ fn main()
 	println!("");
 	imgbuf.save("output.png").unwrap();
 }
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::cameras::perspective_camera::PerspectiveCamera;
+	use crate::hittable::Hittable;
+	use crate::materials::
+	{
+		diffuse_light::DiffuseLight,
+		lambertian::Lambertian,
+	};
+	use crate::math::vec::Vec2u;
+	use crate::objects::
+	{
+		rect::XZRect,
+		sphere::Sphere,
+		visibility::
+		{
+			VISIBLE_ALL,
+			VISIBLE_SHADOW,
+			Visibility,
+		},
+	};
+	use crate::textures::solid_color::SolidColor;
+
+	/* a floor lit by a light that shadow rays reach and by one that they do not */
+	fn scene(nee: bool) -> Scene
+	{
+		let camera = Arc::new(PerspectiveCamera::new(Vec3d::new(0.0, 3.0, -3.0), Vec3d::zero(), Vec3d::new(0.0, 1.0, 0.0), 40.0, Vec2u::new(16, 16), 0.0, 1.0));
+		let mut scene = Scene::new(camera);
+
+		let floor: Arc::<dyn Hittable> = Arc::new(XZRect::new(-10.0, 10.0, -10.0, 10.0, 0.0, Arc::new(Lambertian::new(Arc::new(SolidColor::new(Vec3d::newv(0.5)))))));
+		let emitter = || Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(Vec3d::newv(4.0)))));
+		let visible: Arc::<dyn Hittable> = Arc::new(Sphere::new(Vec3d::new(-2.0, 1.5, 0.0), 1.0, emitter()));
+		let hidden: Arc::<dyn Hittable> = Arc::new(Visibility::new(Arc::new(Sphere::new(Vec3d::new(2.0, 1.5, 0.0), 1.0, emitter()))).with_flags(VISIBLE_ALL & !VISIBLE_SHADOW));
+
+		scene.set_objects(vec![floor, visible.clone(), hidden.clone()]);
+		scene.set_lights(if nee { vec![visible, hidden] } else { Vec::new() });
+		scene
+	}
+
+	fn average(scene: &Scene) -> f64
+	{
+		const N: usize = 40000;
+		let r = Ray::with_kind(Vec3d::new(0.0, 3.0, -3.0), Vec3d::new(0.0, -1.0, 1.0), 0.0, RayKind::Camera);
+		(0..N).map(|_| Vec3d::dot(ray_color::<Vec3d>(&r, scene, 2), Vec3d::newv(1.0 / 3.0))).sum::<f64>() / N as f64
+	}
+
+	#[test]
+	fn test_shadow_invisible_emitter_matches_without_light_sampling()
+	{
+		/* only bsdf sampling reaches the hidden emitter, it must then keep its full weight */
+		let with_nee = average(&scene(true));
+		let without_nee = average(&scene(false));
+		assert!((with_nee - without_nee).abs() < 0.05 * without_nee, "{} {}", with_nee, without_nee);
+	}
+}
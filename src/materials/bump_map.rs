@@ -0,0 +1,159 @@
+use crate::bsdf::Bsdf;
+use crate::hittable::HitRecord;
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+	Vec4d,
+};
+use crate::media::Medium;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
+use crate::textures::Texture;
+
+use std::sync::Arc;
+
+use super::Material;
+
+/* offset in texture space of the finite differences */
+const BUMP_DELTA: f64 = 0.0005;
+
+/* displaces the shading normal as if the surface was moved along it by the height texture */
+pub struct BumpMap
+{
+	base: Arc::<dyn Material>,
+	height: Arc::<dyn Texture>,
+	scale: f64,
+}
+
+impl BumpMap
+{
+	pub fn new(base: Arc::<dyn Material>, height: Arc::<dyn Texture>) -> Self
+	{
+		Self{base, height, scale: 1.0}
+	}
+
+	pub fn with_scale(mut self, scale: f64) -> Self
+	{
+		self.scale = scale;
+		self
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let base = scene.material(Scene::parse_xml_attr_str(&child("Base").expect("no Base node found for BumpMap"), "material".to_string()));
+		let height = scene.texture(Scene::parse_xml_attr_str(&child("Height").expect("no Height node found for BumpMap"), "texture".to_string()));
+		let mut bump_map = Self::new(base, height);
+		if let Some(scale) = child("Scale")
+		{
+			bump_map = bump_map.with_scale(Scene::parse_xml_f64(&scale));
+		}
+		bump_map
+	}
+
+	pub fn perturb(r: &Ray, rec: &mut HitRecord, height: &Arc::<dyn Texture>, scale: f64)
+	{
+		let n = rec.outward_normal();
+		let (dpdu, dpdv) = rec.tangents();
+
+		let h = height.value(rec.uv, rec.p).x();
+		let hu = height.value(rec.uv + Vec2d::new(BUMP_DELTA, 0.0), rec.p + dpdu * BUMP_DELTA).x();
+		let hv = height.value(rec.uv + Vec2d::new(0.0, BUMP_DELTA), rec.p + dpdv * BUMP_DELTA).x();
+
+		let dpdu = dpdu + n * (scale * (hu - h) / BUMP_DELTA);
+		let dpdv = dpdv + n * (scale * (hv - h) / BUMP_DELTA);
+		let mut bumped = Vec3d::normalize(Vec3d::cross(dpdu, dpdv));
+		if Vec3d::dot(bumped, n) < 0.0
+		{
+			bumped = -bumped;
+		}
+		rec.set_shading_normal(r, bumped);
+	}
+}
+
+impl Material for BumpMap
+{
+	fn resolve(&self, scene: &Scene)
+	{
+		self.base.resolve(scene);
+	}
+
+	fn bsdf(&self, r: &Ray, rec: &HitRecord, lambda: &mut SampledWavelengths) -> Option<Bsdf>
+	{
+		let mut rec = rec.clone();
+		Self::perturb(r, &mut rec, &self.height, self.scale);
+		self.base.bsdf(r, &rec, lambda)
+	}
+
+	fn emitted(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
+	{
+		self.base.emitted(r, rec, uv, p)
+	}
+
+	fn emitted_spectrum(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d, lambda: &SampledWavelengths) -> Option<Vec4d>
+	{
+		self.base.emitted_spectrum(r, rec, uv, p, lambda)
+	}
+
+	fn opacity(&self, uv: Vec2d, p: Vec3d) -> f64
+	{
+		self.base.opacity(uv, p)
+	}
+
+	fn medium(&self, rec: &HitRecord) -> Option<Arc::<dyn Medium>>
+	{
+		self.base.medium(rec)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::materials::lambertian::Lambertian;
+	use crate::textures::solid_color::SolidColor;
+
+	/* height rising along u */
+	struct Ramp(f64);
+
+	impl Texture for Ramp
+	{
+		fn value(&self, uv: Vec2d, _p: Vec3d) -> Vec3d
+		{
+			Vec3d::newv(uv.x() * self.0)
+		}
+	}
+
+	fn bumped(from_above: bool, height: Arc::<dyn Texture>, scale: f64) -> Vec3d
+	{
+		let dir = if from_above { Vec3d::new(0.0, 0.0, -1.0) } else { Vec3d::new(0.0, 0.0, 1.0) };
+		let r = Ray::new(-dir, dir);
+		let material = Arc::new(Lambertian::new(Arc::new(SolidColor::new(Vec3d::one()))));
+		let mut rec = HitRecord::new(&r, Vec3d::zero(), 1.0, Vec2d::new(0.5, 0.5), Vec3d::new(0.0, 0.0, 1.0), material).with_tangents(Vec3d::new(2.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0));
+		BumpMap::perturb(&r, &mut rec, &height, scale);
+		rec.normal
+	}
+
+	#[test]
+	fn test_constant_height_keeps_the_normal()
+	{
+		let flat: Arc::<dyn Texture> = Arc::new(SolidColor::new(Vec3d::newv(0.7)));
+		assert!(Vec3d::length(bumped(true, flat, 1.0) - Vec3d::new(0.0, 0.0, 1.0)) < 1e-12);
+	}
+
+	#[test]
+	fn test_slope_tilts_the_normal()
+	{
+		/* a slope of 3 * 0.5 per unit of u over dpdu of length 2 tilts the normal by atan(0.75) */
+		let normal = bumped(true, Arc::new(Ramp(3.0)), 0.5);
+		assert!(Vec3d::length(normal - Vec3d::new(-0.6, 0.0, 0.8)) < 1e-6, "{:?}", normal);
+
+		/* the back face is bumped the same way, facing the ray */
+		let normal = bumped(false, Arc::new(Ramp(3.0)), 0.5);
+		assert!(Vec3d::length(normal - Vec3d::new(0.6, 0.0, -0.8)) < 1e-6, "{:?}", normal);
+	}
+}
@@ -0,0 +1,90 @@
+use crate::bsdf::
+{
+	Bsdf,
+	sheen_bxdf::SheenBxdf,
+};
+use crate::hittable::HitRecord;
+use crate::math::vec::Vec3d;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
+use crate::textures::
+{
+	Texture,
+	solid_color::SolidColor,
+};
+
+use std::sync::Arc;
+
+use super::Material;
+
+/* fabrics such as velvet, satin or wool, a diffuse base under a retro-reflective sheen at grazing angles */
+pub struct Cloth
+{
+	albedo: Arc::<dyn Texture>,
+	sheen: Arc::<dyn Texture>,
+	roughness: Arc::<dyn Texture>,
+}
+
+impl Cloth
+{
+	pub fn new(albedo: Arc::<dyn Texture>) -> Self
+	{
+		Self{albedo, sheen: Arc::new(SolidColor::new(Vec3d::one())), roughness: Arc::new(SolidColor::new(Vec3d::newv(0.5)))}
+	}
+
+	pub fn with_sheen(mut self, sheen: Arc::<dyn Texture>) -> Self
+	{
+		self.sheen = sheen;
+		self
+	}
+
+	/* read from the first channel, low values concentrate the sheen at grazing angles */
+	pub fn with_roughness(mut self, roughness: Arc::<dyn Texture>) -> Self
+	{
+		self.roughness = roughness;
+		self
+	}
+
+	/* each parameter is a child node with either a texture name, a value or x y z attributes */
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let param = |name: &str| -> Option<Arc::<dyn Texture>>
+		{
+			let child = node.children().find(|node| node.is_element() && node.tag_name().name() == name)?;
+			if let Some(texture) = child.attribute("texture")
+			{
+				Some(scene.texture(texture.to_string()))
+			}
+			else if child.attribute("value").is_some()
+			{
+				Some(Arc::new(SolidColor::new(Vec3d::newv(Scene::parse_xml_f64(&child)))))
+			}
+			else
+			{
+				Some(Arc::new(SolidColor::new(Scene::parse_xml_vec3d(&child))))
+			}
+		};
+
+		let mut cloth = Self::new(param("Albedo").expect("no Albedo node found for Cloth"));
+		if let Some(sheen) = param("Sheen")
+		{
+			cloth = cloth.with_sheen(sheen);
+		}
+		if let Some(roughness) = param("Roughness")
+		{
+			cloth = cloth.with_roughness(roughness);
+		}
+		cloth
+	}
+}
+
+impl Material for Cloth
+{
+	fn bsdf(&self, _r: &Ray, rec: &HitRecord, _lambda: &mut SampledWavelengths) -> Option<Bsdf>
+	{
+		let roughness = f64::clamp(self.roughness.value(rec.uv, rec.p).x(), 0.0, 1.0);
+		let bxdf = SheenBxdf::new(self.albedo.value(rec.uv, rec.p), self.sheen.value(rec.uv, rec.p), roughness);
+		Some(Bsdf::new(rec.normal, rec.shading_tangent(), Box::new(bxdf)))
+	}
+}
@@ -0,0 +1,123 @@
+use crate::bsdf::
+{
+	Bsdf,
+	coated_bxdf::CoatedBxdf,
+	microfacet::TrowbridgeReitz,
+};
+use crate::hittable::HitRecord;
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+	Vec4d,
+};
+use crate::media::Medium;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
+
+use std::sync::Arc;
+
+use super::Material;
+
+/* smooth or rough clear coat over any material, e.g. car paint, varnish or plastic */
+pub struct Coated
+{
+	base: Arc::<dyn Material>,
+	ior: f64,
+	roughness: f64,
+	color: Vec3d,
+	thickness: f64,
+}
+
+impl Coated
+{
+	pub fn new(base: Arc::<dyn Material>) -> Self
+	{
+		Self{base, ior: 1.5, roughness: 0.0, color: Vec3d::one(), thickness: 0.0}
+	}
+
+	pub fn with_ior(mut self, ior: f64) -> Self
+	{
+		self.ior = ior;
+		self
+	}
+
+	pub fn with_roughness(mut self, roughness: f64) -> Self
+	{
+		self.roughness = roughness;
+		self
+	}
+
+	/* color is the transmittance of one unit of thickness at normal incidence */
+	pub fn with_absorption(mut self, color: Vec3d, thickness: f64) -> Self
+	{
+		self.color = color;
+		self.thickness = thickness;
+		self
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let base = child("Base").expect("no Base node found for Coated");
+		let mut coated = Self::new(scene.material(Scene::parse_xml_attr_str(&base, "material".to_string())));
+		if let Some(ior) = child("Ior")
+		{
+			coated = coated.with_ior(Scene::parse_xml_f64(&ior));
+		}
+		if let Some(roughness) = child("Roughness")
+		{
+			coated = coated.with_roughness(Scene::parse_xml_f64(&roughness));
+		}
+		if let Some(color) = child("Color")
+		{
+			let thickness = child("Thickness").map_or(1.0, |thickness| Scene::parse_xml_f64(&thickness));
+			coated = coated.with_absorption(Scene::parse_xml_vec3d(&color), thickness);
+		}
+		coated
+	}
+}
+
+impl Material for Coated
+{
+	fn resolve(&self, scene: &Scene)
+	{
+		self.base.resolve(scene);
+	}
+
+	fn bsdf(&self, r: &Ray, rec: &HitRecord, lambda: &mut SampledWavelengths) -> Option<Bsdf>
+	{
+		let bsdf = self.base.bsdf(r, rec, lambda)?;
+
+		/* the coat is on the outside of the surface */
+		if !rec.front_face
+		{
+			return Some(bsdf);
+		}
+
+		let distribution = TrowbridgeReitz::from_roughness(self.roughness, self.roughness);
+		Some(bsdf.wrap(|base| Box::new(CoatedBxdf::new(base, self.ior, distribution, self.color, self.thickness))))
+	}
+
+	fn emitted(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
+	{
+		self.base.emitted(r, rec, uv, p)
+	}
+
+	fn emitted_spectrum(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d, lambda: &SampledWavelengths) -> Option<Vec4d>
+	{
+		self.base.emitted_spectrum(r, rec, uv, p, lambda)
+	}
+
+	fn opacity(&self, uv: Vec2d, p: Vec3d) -> f64
+	{
+		self.base.opacity(uv, p)
+	}
+
+	fn medium(&self, rec: &HitRecord) -> Option<Arc::<dyn Medium>>
+	{
+		self.base.medium(rec)
+	}
+}
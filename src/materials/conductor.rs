@@ -0,0 +1,156 @@
+use crate::bsdf::
+{
+	Bsdf,
+	conductor_bxdf::ConductorBxdf,
+	fresnel::
+	{
+		FilmFresnel,
+		ThinFilm,
+	},
+	microfacet::TrowbridgeReitz,
+};
+use crate::hittable::HitRecord;
+use crate::math::vec::Vec3d;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
+use crate::textures::Texture;
+
+use std::sync::Arc;
+
+use super::Material;
+
+/* rough conductor, eta and k are the complex index of refraction at red, green and blue wavelengths */
+pub struct Conductor
+{
+	eta: Vec3d,
+	k: Vec3d,
+	distribution: TrowbridgeReitz,
+	film_thickness: Option<Arc::<dyn Texture>>,
+	film_ior: f64,
+}
+
+impl Conductor
+{
+	pub fn new(eta: Vec3d, k: Vec3d, roughness: f64) -> Self
+	{
+		Self::with_anisotropy(eta, k, roughness, roughness)
+	}
+
+	pub fn with_anisotropy(eta: Vec3d, k: Vec3d, roughness_u: f64, roughness_v: f64) -> Self
+	{
+		Self{eta, k, distribution: TrowbridgeReitz::from_roughness(roughness_u, roughness_v), film_thickness: None, film_ior: 1.0}
+	}
+
+	/* thin film on top of the metal, its thickness in nanometers is read from the first channel of the texture */
+	pub fn with_thin_film(mut self, thickness: Arc::<dyn Texture>, ior: f64) -> Self
+	{
+		self.film_thickness = Some(thickness);
+		self.film_ior = ior;
+		self
+	}
+
+	/* a named metal or the Eta and K colors, a single roughness value or u and v */
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let (roughness_u, roughness_v) = match child("Roughness")
+		{
+			None => (0.0, 0.0),
+			Some(roughness) if roughness.attribute("value").is_some() => (Scene::parse_xml_f64(&roughness), Scene::parse_xml_f64(&roughness)),
+			Some(roughness) => (Scene::parse_xml_attr_f64(&roughness, "u".to_string()), Scene::parse_xml_attr_f64(&roughness, "v".to_string())),
+		};
+		let (eta, k) = match child("Preset")
+		{
+			None => (Scene::parse_xml_vec3d(&child("Eta").expect("no Eta node found for Conductor")), Scene::parse_xml_vec3d(&child("K").expect("no K node found for Conductor"))),
+			Some(preset) =>
+			{
+				let preset = match Scene::parse_xml_attr_str(&preset, "name".to_string()).as_str()
+				{
+					"aluminium" => Self::aluminium(0.0),
+					"copper"    => Self::copper(0.0),
+					"gold"      => Self::gold(0.0),
+					"silver"    => Self::silver(0.0),
+					name        => panic!("unknown conductor preset: {}", name),
+				};
+				(preset.eta, preset.k)
+			},
+		};
+
+		let mut conductor = Self::with_anisotropy(eta, k, roughness_u, roughness_v);
+		if let Some(film) = child("ThinFilm")
+		{
+			let (thickness, ior) = scene.parse_xml_thin_film(&film);
+			conductor = conductor.with_thin_film(thickness, ior);
+		}
+		conductor
+	}
+
+	pub fn gold(roughness: f64) -> Self
+	{
+		Self::new(Vec3d::new(0.143, 0.374, 1.442), Vec3d::new(3.983, 2.385, 1.603), roughness)
+	}
+
+	pub fn copper(roughness: f64) -> Self
+	{
+		Self::new(Vec3d::new(0.200, 0.924, 1.102), Vec3d::new(3.912, 2.452, 2.142), roughness)
+	}
+
+	pub fn aluminium(roughness: f64) -> Self
+	{
+		Self::new(Vec3d::new(1.657, 0.880, 0.521), Vec3d::new(9.224, 6.270, 4.837), roughness)
+	}
+
+	pub fn silver(roughness: f64) -> Self
+	{
+		Self::new(Vec3d::new(0.155, 0.117, 0.138), Vec3d::new(4.828, 3.122, 2.147), roughness)
+	}
+}
+
+impl Material for Conductor
+{
+	fn bsdf(&self, _r: &Ray, rec: &HitRecord, lambda: &mut SampledWavelengths) -> Option<Bsdf>
+	{
+		let mut bxdf = ConductorBxdf::new(self.distribution, self.eta, self.k);
+		if let Some(thickness) = &self.film_thickness
+		{
+			bxdf = bxdf.with_film(FilmFresnel::new(ThinFilm::new(self.film_ior, thickness.value(rec.uv, rec.p).x(), 1.0), lambda));
+		}
+		Some(Bsdf::new(rec.normal, rec.shading_tangent(), Box::new(bxdf)))
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::math::vec::Vec2d;
+	use crate::onb::Onb;
+
+	fn eval(normal: Vec3d, dpdu: Vec3d, wo: Vec3d, wi: Vec3d) -> Vec3d
+	{
+		let material: Arc::<dyn Material> = Arc::new(Conductor::with_anisotropy(Vec3d::new(0.2, 0.9, 1.1), Vec3d::new(3.9, 2.5, 2.1), 0.1, 0.5));
+		let r = Ray::new(wo * 2.0, -wo);
+		let rec = HitRecord::new(&r, Vec3d::zero(), 2.0, Vec2d::zero(), normal, material.clone()).with_tangents(dpdu, Vec3d::cross(normal, dpdu));
+		let bsdf = material.bsdf(&r, &rec, &mut SampledWavelengths::sample_uniform(0.5, false)).unwrap();
+		bsdf.eval(wo, wi)
+	}
+
+	#[test]
+	fn test_anisotropy_follows_the_tangent()
+	{
+		/* the normals lie on both sides of where an arbitrary frame around the normal switches axes */
+		let wo = Vec3d::normalize(Vec3d::new(0.5, 0.1, 0.8));
+		let wi = Vec3d::normalize(Vec3d::new(-0.2, 0.5, 0.8));
+		let dpdu = Vec3d::new(0.0, 0.0, 1.0);
+		let a = Onb::from_wu(Vec3d::new(f64::cos(0.4), f64::sin(0.4), 0.0), dpdu);
+		let b = Onb::from_wu(Vec3d::new(f64::cos(0.5), f64::sin(0.5), 0.0), dpdu);
+		let f = eval(a.w(), dpdu, a.local(wo), a.local(wi));
+		assert!(Vec3d::length(f - eval(b.w(), dpdu, b.local(wo), b.local(wi))) < 1e-9 * Vec3d::length(f));
+
+		/* turning the tangent turns the highlight */
+		assert!(Vec3d::length(f - eval(a.w(), a.v(), a.local(wo), a.local(wi))) > 0.1 * Vec3d::length(f));
+	}
+}
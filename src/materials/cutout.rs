@@ -0,0 +1,169 @@
+use crate::bsdf::Bsdf;
+use crate::hittable::HitRecord;
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+	Vec4d,
+};
+use crate::media::Medium;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
+use crate::textures::Texture;
+
+use std::sync::Arc;
+
+use super::Material;
+
+/* opacity from the first channel of a texture, leaves, fences and decals */
+pub struct Cutout
+{
+	base: Arc::<dyn Material>,
+	opacity: Arc::<dyn Texture>,
+	threshold: Option<f64>,
+}
+
+impl Cutout
+{
+	pub fn new(base: Arc::<dyn Material>, opacity: Arc::<dyn Texture>) -> Self
+	{
+		Self{base, opacity, threshold: None}
+	}
+
+	/* binary cutout instead of stochastic transparency */
+	pub fn with_threshold(mut self, threshold: f64) -> Self
+	{
+		self.threshold = Some(threshold);
+		self
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let base = scene.material(Scene::parse_xml_attr_str(&child("Base").expect("no Base node found for Cutout"), "material".to_string()));
+		let opacity = scene.texture(Scene::parse_xml_attr_str(&child("Opacity").expect("no Opacity node found for Cutout"), "texture".to_string()));
+		let mut cutout = Self::new(base, opacity);
+		if let Some(threshold) = child("Threshold")
+		{
+			cutout = cutout.with_threshold(Scene::parse_xml_f64(&threshold));
+		}
+		cutout
+	}
+}
+
+impl Material for Cutout
+{
+	fn resolve(&self, scene: &Scene)
+	{
+		self.base.resolve(scene);
+	}
+
+	fn bsdf(&self, r: &Ray, rec: &HitRecord, lambda: &mut SampledWavelengths) -> Option<Bsdf>
+	{
+		self.base.bsdf(r, rec, lambda)
+	}
+
+	fn emitted(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
+	{
+		self.base.emitted(r, rec, uv, p)
+	}
+
+	fn emitted_spectrum(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d, lambda: &SampledWavelengths) -> Option<Vec4d>
+	{
+		self.base.emitted_spectrum(r, rec, uv, p, lambda)
+	}
+
+	fn opacity(&self, uv: Vec2d, p: Vec3d) -> f64
+	{
+		let opacity = self.opacity.value(uv, p).x() * self.base.opacity(uv, p);
+		match self.threshold
+		{
+			None => opacity,
+			Some(threshold) => if opacity >= threshold { 1.0 } else { 0.0 },
+		}
+	}
+
+	fn medium(&self, rec: &HitRecord) -> Option<Arc::<dyn Medium>>
+	{
+		self.base.medium(rec)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::hittable::Hittable;
+	use crate::materials::lambertian::Lambertian;
+	use crate::objects::sphere::Sphere;
+	use crate::textures::solid_color::SolidColor;
+
+	fn color(v: f64) -> Arc::<dyn Texture>
+	{
+		Arc::new(SolidColor::new(Vec3d::newv(v)))
+	}
+
+	fn base() -> Arc::<dyn Material>
+	{
+		Arc::new(Lambertian::new(color(0.5)))
+	}
+
+	/* opaque where z is negative */
+	struct HalfSpace;
+
+	impl Texture for HalfSpace
+	{
+		fn value(&self, _uv: Vec2d, p: Vec3d) -> Vec3d
+		{
+			Vec3d::newv(if p.z() < 0.0 { 1.0 } else { 0.0 })
+		}
+	}
+
+	#[test]
+	fn test_threshold_and_nesting()
+	{
+		assert_eq!(Cutout::new(base(), color(0.4)).opacity(Vec2d::zero(), Vec3d::zero()), 0.4);
+		assert_eq!(Cutout::new(base(), color(0.4)).with_threshold(0.5).opacity(Vec2d::zero(), Vec3d::zero()), 0.0);
+		assert_eq!(Cutout::new(base(), color(0.6)).with_threshold(0.5).opacity(Vec2d::zero(), Vec3d::zero()), 1.0);
+
+		let nested = Cutout::new(Arc::new(Cutout::new(base(), color(0.5))), color(0.5));
+		assert_eq!(nested.opacity(Vec2d::zero(), Vec3d::zero()), 0.25);
+	}
+
+	#[test]
+	fn test_rays_go_through_cut_out_parts()
+	{
+		let sphere = Sphere::new(Vec3d::zero(), 1.0, Arc::new(Cutout::new(base(), Arc::new(HalfSpace))));
+		let r = Ray::new(Vec3d::new(0.0, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0));
+		let rec = sphere.hit(&r, 0.001, f64::INFINITY).unwrap();
+		assert!((rec.t - 6.0).abs() < 1e-9);
+		assert!(!rec.front_face);
+
+		let invisible = Sphere::new(Vec3d::zero(), 1.0, Arc::new(Cutout::new(base(), color(0.0))));
+		assert!(invisible.hit(&r, 0.001, f64::INFINITY).is_none());
+	}
+
+	#[test]
+	fn test_stochastic_opacity()
+	{
+		let sphere = Sphere::new(Vec3d::zero(), 1.0, Arc::new(Cutout::new(base(), color(0.3))));
+		let n = 10000;
+		let mut front = 0;
+		for i in 0..n
+		{
+			let x = (i as f64 + 0.5) / n as f64 - 0.5;
+			let r = Ray::new(Vec3d::new(x, 0.0, 5.0), Vec3d::new(0.0, 0.0, -1.0));
+			let first = sphere.hit(&r, 0.001, f64::INFINITY).map(|rec| rec.front_face);
+			/* the same ray always makes the same choice */
+			assert_eq!(first, sphere.hit(&r, 0.001, f64::INFINITY).map(|rec| rec.front_face));
+			if first == Some(true)
+			{
+				front += 1;
+			}
+		}
+		assert!((front as f64 / n as f64 - 0.3).abs() < 0.02);
+	}
+}
@@ -1,76 +1,245 @@
+use crate::bsdf::
+{
+	Bsdf,
+	dielectric_bxdf::DielectricBxdf,
+	fresnel::
+	{
+		FilmFresnel,
+		ThinFilm,
+	},
+	microfacet::TrowbridgeReitz,
+};
 use crate::hittable::HitRecord;
 use crate::math::vec::Vec3d;
 use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
+use crate::textures::Texture;
 
-use rand::Rng;
+use std::sync::Arc;
 
 use super::Material;
 
+/* index of refraction, wavelengths in micrometers for the dispersion formulas */
+#[derive(Debug, Copy, Clone)]
+pub enum Ior
+{
+	Constant(f64),
+	Cauchy(f64, f64),
+	Sellmeier([f64; 3], [f64; 3]),
+}
+
+impl Ior
+{
+	pub fn bk7() -> Self
+	{
+		Ior::Sellmeier([1.03961212, 0.231792344, 1.01046945], [0.00600069867, 0.0200179144, 103.560653])
+	}
+
+	pub fn fused_silica() -> Self
+	{
+		Ior::Sellmeier([0.6961663, 0.4079426, 0.8974794], [0.00467914826, 0.0135120631, 97.9340025])
+	}
+
+	pub fn diamond() -> Self
+	{
+		Ior::Sellmeier([0.3306, 4.3356, 0.0], [0.030625, 0.011236, 0.0])
+	}
+
+	pub fn is_dispersive(&self) -> bool
+	{
+		!matches!(self, Ior::Constant(_))
+	}
+
+	/* lambda in nanometers */
+	pub fn value(&self, lambda: f64) -> f64
+	{
+		let l = lambda / 1000.0;
+		match self
+		{
+			Ior::Constant(ir) => *ir,
+			Ior::Cauchy(a, b) => a + b / (l * l),
+			Ior::Sellmeier(b, c) =>
+			{
+				let l2 = l * l;
+				f64::sqrt(1.0 + (0..3).map(|i| b[i] * l2 / (l2 - c[i])).sum::<f64>())
+			}
+		}
+	}
+}
+
 pub struct Dielectric
 {
-	ir: f64,
+	ior: Ior,
+	roughness: f64,
+	absorption: Vec3d,
+	film_thickness: Option<Arc::<dyn Texture>>,
+	film_ior: f64,
 }
 
 impl Dielectric
 {
 	pub fn new(ir: f64) -> Self
 	{
-		Dielectric{ir}
+		Self::with_ior(Ior::Constant(ir))
 	}
 
-	pub fn reflectance(cosine: f64, ref_idx: f64) -> f64
+	pub fn with_ior(ior: Ior) -> Self
 	{
-		let mut r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
-		r0 = r0 * r0;
-		r0 + (1.0 - r0) * f64::powf(1.0 - cosine, 5.0)
+		Dielectric{ior, roughness: 0.0, absorption: Vec3d::zero(), film_thickness: None, film_ior: 1.0}
 	}
-}
 
-impl Material for Dielectric
-{
-	fn scatter(&self, r: &Ray, rec: &HitRecord) -> Option<(Vec3d, Ray, f64)>
+	pub fn with_roughness(mut self, roughness: f64) -> Self
+	{
+		self.roughness = roughness;
+		self
+	}
+
+	/* absorption coefficient per unit distance travelled inside */
+	pub fn with_absorption(mut self, absorption: Vec3d) -> Self
+	{
+		self.absorption = absorption;
+		self
+	}
+
+	/* thin film on the outside of the surface, e.g. a soap bubble, thickness in nanometers is read from the first channel */
+	pub fn with_thin_film(mut self, thickness: Arc::<dyn Texture>, ior: f64) -> Self
+	{
+		self.film_thickness = Some(thickness);
+		self.film_ior = ior;
+		self
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
 	{
-		let refraction_ratio;
-		if rec.front_face
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let ior = if let Some(cauchy) = child("Cauchy")
+		{
+			Ior::Cauchy(Scene::parse_xml_attr_f64(&cauchy, "a".to_string()), Scene::parse_xml_attr_f64(&cauchy, "b".to_string()))
+		}
+		else if let Some(sellmeier) = child("Sellmeier")
 		{
-			refraction_ratio = 1.0 / self.ir;
+			let coefficient = |name: &str| Scene::parse_xml_attr_f64(&sellmeier, name.to_string());
+			Ior::Sellmeier([coefficient("b1"), coefficient("b2"), coefficient("b3")], [coefficient("c1"), coefficient("c2"), coefficient("c3")])
 		}
 		else
 		{
-			refraction_ratio = self.ir;
+			/* an Ior node names a glass or gives a value, ordinary glass without one */
+			match child("Ior")
+			{
+				None => Ior::Constant(1.5),
+				Some(ior) => match ior.attribute("name")
+				{
+					None => Ior::Constant(Scene::parse_xml_f64(&ior)),
+					Some("bk7") => Ior::bk7(),
+					Some("diamond") => Ior::diamond(),
+					Some("fused_silica") => Ior::fused_silica(),
+					Some(name) => panic!("unknown Ior name {}", name),
+				},
+			}
+		};
+		let mut dielectric = Self::with_ior(ior);
+		if let Some(roughness) = child("Roughness")
+		{
+			dielectric = dielectric.with_roughness(Scene::parse_xml_f64(&roughness));
+		}
+		if let Some(absorption) = child("Absorption")
+		{
+			dielectric = dielectric.with_absorption(Scene::parse_xml_vec3d(&absorption));
+		}
+		if let Some(film) = child("ThinFilm")
+		{
+			let (thickness, ior) = scene.parse_xml_thin_film(&film);
+			dielectric = dielectric.with_thin_film(thickness, ior);
 		}
+		dielectric
+	}
+}
 
-		let unit_direction = Vec3d::normalize(r.dir());
+impl Material for Dielectric
+{
+	fn bsdf(&self, r: &Ray, rec: &HitRecord, lambda: &mut SampledWavelengths) -> Option<Bsdf>
+	{
+		if self.ior.is_dispersive()
+		{
+			lambda.terminate_secondary();
+		}
 
-		let cos_theta = f64::min(Vec3d::dot(rec.normal, -unit_direction), 1.0);
-		let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+		let ir = self.ior.value(lambda.hero());
+		let eta = if rec.front_face { ir } else { 1.0 / ir };
 
-		let direction;
-		if refraction_ratio * sin_theta > 1.0
+		/* a back face hit means the ray travelled through the interior */
+		let tint = if rec.front_face
 		{
-			direction = Vec3d::reflect(unit_direction, rec.normal);
+			Vec3d::one()
 		}
 		else
 		{
-			let mut rng = rand::thread_rng();
-			if Dielectric::reflectance(cos_theta, refraction_ratio) > rng.gen_range(0.0..1.0)
-			{
-				direction = Vec3d::reflect(unit_direction, rec.normal);
-			}
-			else
-			{
-				direction = Vec3d::refract(unit_direction, rec.normal, refraction_ratio);
-			}
-		}
+			let distance = rec.t * Vec3d::length(r.dir());
+			self.absorption.for_each(&|a| f64::exp(-a * distance))
+		};
 
-		Some((Vec3d::newv(1.0), Ray::with_time(rec.p, direction, r.time()), 1.0))
+		let distribution = TrowbridgeReitz::from_roughness(self.roughness, self.roughness);
+		let mut bxdf = DielectricBxdf::new(eta, distribution, tint);
+		if let Some(thickness) = &self.film_thickness
+		{
+			let incident_ior = if rec.front_face { 1.0 } else { ir };
+			bxdf = bxdf.with_film(FilmFresnel::new(ThinFilm::new(self.film_ior, thickness.value(rec.uv, rec.p).x(), incident_ior), lambda));
+		}
+		Some(Bsdf::new(rec.normal, rec.shading_tangent(), Box::new(bxdf)))
 	}
 }
 
-impl<'a, 'input> From<&roxmltree::Node<'a, 'input>> for Dielectric
+#[cfg(test)]
+mod tests
 {
-	fn from(node: &roxmltree::Node) -> Self
+
+	use super::*;
+	use crate::math::vec::Vec2d;
+
+	/* the helium d line */
+	const LAMBDA_D: f64 = 587.6;
+
+	#[test]
+	fn test_constant()
 	{
-		Self::new(1.5)
+		assert_eq!(Ior::Constant(1.5).value(LAMBDA_D), 1.5);
+		assert!(!Ior::Constant(1.5).is_dispersive());
+	}
+
+	#[test]
+	fn test_cauchy()
+	{
+		let ior = Ior::Cauchy(1.5, 0.004);
+		assert!((ior.value(LAMBDA_D) - 1.511585).abs() < 1e-6);
+		assert!(ior.value(450.0) > ior.value(650.0));
+	}
+
+	#[test]
+	fn test_sellmeier()
+	{
+		assert!((Ior::bk7().value(LAMBDA_D) - 1.5168).abs() < 1e-4);
+		assert!((Ior::fused_silica().value(LAMBDA_D) - 1.4585).abs() < 1e-4);
+		assert!((Ior::diamond().value(LAMBDA_D) - 2.4175).abs() < 1e-3);
+		assert!(Ior::bk7().value(450.0) > Ior::bk7().value(650.0));
+	}
+
+	#[test]
+	fn test_dispersion_keeps_the_hero_wavelength()
+	{
+		let r = Ray::new(Vec3d::new(0.0, 1.0, 0.0), Vec3d::new(0.0, -1.0, 0.0));
+		let bsdf = |material: Dielectric, lambda: &mut SampledWavelengths|
+		{
+			let material: Arc::<dyn Material> = Arc::new(material);
+			let rec = HitRecord::new(&r, Vec3d::zero(), 1.0, Vec2d::zero(), Vec3d::new(0.0, 1.0, 0.0), material.clone());
+			material.bsdf(&r, &rec, lambda)
+		};
+
+		let mut lambda = SampledWavelengths::sample_uniform(0.5, true);
+		assert!(bsdf(Dielectric::new(1.5), &mut lambda).is_some());
+		assert!(!lambda.secondary_terminated());
+		assert!(bsdf(Dielectric::with_ior(Ior::bk7()), &mut lambda).is_some());
+		assert!(lambda.secondary_terminated());
 	}
 }
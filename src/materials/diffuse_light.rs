@@ -1,46 +1,254 @@
 use crate::hittable::HitRecord;
+use crate::ies::IesProfile;
 use crate::math::vec::
 {
 	Vec2d,
 	Vec3d,
+	Vec4d,
 };
+use crate::onb::Onb;
 use crate::ray::Ray;
 use crate::scene::Scene;
+use crate::spectrum::
+{
+	RgbSpectrum,
+	SampledWavelengths,
+	Spectrum,
+	spectrum_to_rgb,
+};
 use crate::textures::Texture;
 
 use std::sync::Arc;
 
 use super::Material;
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EmissionSides
+{
+	Front,
+	Back,
+	Both,
+}
+
 pub struct DiffuseLight
 {
 	emit: Arc::<dyn Texture>,
+	sides: EmissionSides,
+	strength: f64,
+	mask: Option::<Arc::<dyn Texture>>,
+	profile: Option::<(Arc::<IesProfile>, Onb)>,
+	spectrum: Option::<(Arc::<dyn Spectrum>, f64, Vec3d)>,
 }
 
 impl DiffuseLight
 {
 	pub fn new(emit: Arc::<dyn Texture>) -> Self
 	{
-		Self{emit}
+		Self{emit, sides: EmissionSides::Front, strength: 1.0, mask: None, profile: None, spectrum: None}
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let mut light = Self::new(scene.parse_xml_color(&child("Emit").expect("no Emit node found for DiffuseLight")));
+		if let Some(sides) = child("Sides")
+		{
+			light = light.with_sides(match Scene::parse_xml_attr_str(&sides, "value".to_string()).as_str()
+			{
+				"front" => EmissionSides::Front,
+				"back"  => EmissionSides::Back,
+				"both"  => EmissionSides::Both,
+				sides   => panic!("unknown emission sides: {}", sides),
+			});
+		}
+		if let Some(strength) = child("Strength")
+		{
+			light = light.with_strength(Scene::parse_xml_f64(&strength));
+		}
+		if let Some(mask) = child("Mask")
+		{
+			light = light.with_mask(scene.parse_xml_color(&mask));
+		}
+		if let Some(profile) = child("Profile")
+		{
+			let (profile, axis) = Scene::parse_xml_profile(&profile);
+			light = light.with_profile(profile, axis);
+		}
+		if let Some(spectrum) = child("Spectrum")
+		{
+			light = light.with_spectrum(Scene::parse_xml_spectrum(&spectrum));
+		}
+		light
+	}
+
+	pub fn with_sides(mut self, sides: EmissionSides) -> Self
+	{
+		self.sides = sides;
+		self
+	}
+
+	pub fn with_strength(mut self, strength: f64) -> Self
+	{
+		self.strength = strength;
+		self
+	}
+
+	/* the luminance of the mask scales the emission, so a base color map can be lit up as is */
+	pub fn with_mask(mut self, mask: Arc::<dyn Texture>) -> Self
+	{
+		self.mask = Some(mask);
+		self
+	}
+
+	/* axis is the nadir direction of the fixture, the direction the profile's zero vertical angle points to */
+	pub fn with_profile(mut self, profile: Arc::<IesProfile>, axis: Vec3d) -> Self
+	{
+		self.profile = Some((profile, Onb::from_w(axis)));
+		self
+	}
+
+	/* emission spectrum tinted by the emit texture, scaled to unit luminance */
+	pub fn with_spectrum(mut self, spectrum: Arc::<dyn Spectrum>) -> Self
+	{
+		let rgb = spectrum_to_rgb(spectrum.as_ref());
+		let scale = 1.0 / rgb.luminance();
+		self.spectrum = Some((spectrum, scale, rgb * scale));
+		self
+	}
+
+	fn profile_value(&self, r: &Ray) -> f64
+	{
+		match &self.profile
+		{
+			None => 1.0,
+			Some((profile, frame)) => profile.value_towards(frame, -r.dir()),
+		}
+	}
+
+	fn emission_scale(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d) -> f64
+	{
+		let emits = match self.sides
+		{
+			EmissionSides::Front => rec.front_face,
+			EmissionSides::Back  => !rec.front_face,
+			EmissionSides::Both  => true,
+		};
+
+		if !emits
+		{
+			return 0.0;
+		}
+
+		let mask = match &self.mask
+		{
+			None => 1.0,
+			Some(mask) => mask.value(uv, p).luminance(),
+		};
+
+		self.strength * mask * self.profile_value(r)
 	}
 }
 
 impl Material for DiffuseLight
 {
-	fn scatter(&self, _r: &Ray, _rec: &HitRecord) -> Option<(Vec3d, Ray, f64)>
+	fn emitted(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
 	{
-		None
+		let scale = self.emission_scale(r, rec, uv, p);
+		if scale == 0.0
+		{
+			return Vec3d::zero();
+		}
+
+		match &self.spectrum
+		{
+			None => self.emit.value(uv, p) * scale,
+			Some((_, _, rgb)) => self.emit.value(uv, p) * *rgb * scale,
+		}
 	}
 
-	fn emitted(&self, _r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
+	fn emitted_spectrum(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d, lambda: &SampledWavelengths) -> Option<Vec4d>
 	{
-		if rec.front_face
+		let (spectrum, normalization, _) = self.spectrum.as_ref()?;
+		let scale = self.emission_scale(r, rec, uv, p);
+		if scale == 0.0
 		{
-			self.emit.value(uv, p)
+			return Some(Vec4d::newv(0.0));
 		}
-		else
+
+		Some(RgbSpectrum::new(self.emit.value(uv, p)).sample(lambda) * spectrum.sample(lambda) * (normalization * scale))
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::light_bvh::LightBounds;
+	use crate::math::aabb::Aabb;
+	use crate::textures::solid_color::SolidColor;
+
+	use std::f64::consts::PI;
+
+	fn color(v: Vec3d) -> Arc::<dyn Texture>
+	{
+		Arc::new(SolidColor::new(v))
+	}
+
+	/* emission seen from the front and from the back of a surface facing +y */
+	fn emission(light: DiffuseLight) -> (Vec3d, Vec3d)
+	{
+		let light: Arc::<dyn Material> = Arc::new(light);
+		let n = Vec3d::new(0.0, 1.0, 0.0);
+		let front = Ray::new(n, -n);
+		let back = Ray::new(-n, n);
+		let front_rec = HitRecord::new(&front, Vec3d::zero(), 1.0, Vec2d::zero(), n, light.clone());
+		let back_rec = HitRecord::new(&back, Vec3d::zero(), 1.0, Vec2d::zero(), n, light.clone());
+		(light.emitted(&front, &front_rec, Vec2d::zero(), Vec3d::zero()), light.emitted(&back, &back_rec, Vec2d::zero(), Vec3d::zero()))
+	}
+
+	#[test]
+	fn test_sides()
+	{
+		let emit = Vec3d::new(1.0, 2.0, 3.0);
+		assert_eq!(emission(DiffuseLight::new(color(emit))), (emit, Vec3d::zero()));
+		assert_eq!(emission(DiffuseLight::new(color(emit)).with_sides(EmissionSides::Back)), (Vec3d::zero(), emit));
+		assert_eq!(emission(DiffuseLight::new(color(emit)).with_sides(EmissionSides::Both)), (emit, emit));
+	}
+
+	#[test]
+	fn test_strength_and_mask_scale_the_emission()
+	{
+		let emit = Vec3d::new(1.0, 2.0, 3.0);
+		let (front, _) = emission(DiffuseLight::new(color(emit)).with_strength(4.0).with_mask(color(Vec3d::newv(0.25))));
+		assert!(Vec3d::length(front - emit) < 1e-9);
+
+		let (front, _) = emission(DiffuseLight::new(color(emit)).with_mask(color(Vec3d::zero())));
+		assert_eq!(front, Vec3d::zero());
+	}
+
+	#[test]
+	fn test_light_bounds_follow_the_sides()
+	{
+		let aabb = Aabb::new(Vec3d::zero(), Vec3d::one());
+		let n = Vec3d::new(0.0, 1.0, 0.0);
+		let samples = [(Vec3d::zero(), n, Vec2d::zero())];
+		let bounds = |sides: EmissionSides| -> Option<LightBounds>
 		{
-			Vec3d::zero()
-		}
+			let light: Arc::<dyn Material> = Arc::new(DiffuseLight::new(color(Vec3d::one())).with_sides(sides));
+			LightBounds::from_surface(aabb, 2.0, n, &light, &samples)
+		};
+
+		let front = bounds(EmissionSides::Front).unwrap();
+		assert!(!front.two_sided && front.w == n);
+		assert!((front.phi - 2.0 * PI).abs() < 1e-9);
+		assert!(bounds(EmissionSides::Back).unwrap().w == -n);
+
+		let both = bounds(EmissionSides::Both).unwrap();
+		assert!(both.two_sided && (both.phi - 4.0 * PI).abs() < 1e-9);
+
+		let dark: Arc::<dyn Material> = Arc::new(DiffuseLight::new(color(Vec3d::zero())));
+		assert!(LightBounds::from_surface(aabb, 2.0, n, &dark, &samples).is_none());
 	}
 }
@@ -1,32 +1,62 @@
+use crate::bsdf::
+{
+	Bsdf,
+	isotropic_bxdf::IsotropicBxdf,
+	phase_bxdf::PhaseBxdf,
+};
 use crate::hittable::HitRecord;
-use crate::math::vec::Vec3d;
+use crate::phase_functions::PhaseFunction;
 use crate::ray::Ray;
 use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
 use crate::textures::Texture;
 
 use std::sync::Arc;
 
 use super::Material;
 
+/* scattering event inside a medium, uniform unless a phase function is given */
 pub struct Isotropic
 {
 	albedo: Arc::<dyn Texture>,
+	phase: Option::<Arc::<dyn PhaseFunction>>,
 }
 
 impl Isotropic
 {
 	pub fn new(albedo: Arc::<dyn Texture>) -> Self
 	{
-		Self{albedo}
+		Self{albedo, phase: None}
+	}
+
+	pub fn with_phase(mut self, phase: Arc::<dyn PhaseFunction>) -> Self
+	{
+		self.phase = Some(phase);
+		self
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let isotropic = Self::new(scene.parse_xml_color(&child("Albedo").expect("no Albedo node found for Isotropic")));
+		match child("Phase")
+		{
+			None => isotropic,
+			Some(phase) => isotropic.with_phase(Scene::parse_xml_phase(&phase)),
+		}
 	}
 }
 
 impl Material for Isotropic
 {
-	fn scatter(&self, r: &Ray, rec: &HitRecord) -> Option<(Vec3d, Ray, f64)>
+	fn bsdf(&self, _r: &Ray, rec: &HitRecord, _lambda: &mut SampledWavelengths) -> Option<Bsdf>
 	{
-		let scattered = Ray::with_time(rec.p, Vec3d::random_in_unit_sphere(), r.time());
-		let attenuation = self.albedo.value(rec.uv, rec.p);
-		Some((attenuation, scattered, 1.0))
+		let albedo = self.albedo.value(rec.uv, rec.p);
+		match &self.phase
+		{
+			None => Some(Bsdf::new(rec.normal, rec.shading_tangent(), Box::new(IsotropicBxdf::new(albedo)))),
+			Some(phase) => Some(Bsdf::new(rec.normal, rec.shading_tangent(), Box::new(PhaseBxdf::new(albedo, phase.clone())))),
+		}
 	}
 }
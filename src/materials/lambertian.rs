@@ -1,8 +1,12 @@
+use crate::bsdf::
+{
+	Bsdf,
+	diffuse_bxdf::DiffuseBxdf,
+};
 use crate::hittable::HitRecord;
-use crate::math::vec::Vec3d;
-use crate::onb::Onb;
 use crate::ray::Ray;
 use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
 use crate::textures::Texture;
 
 use std::sync::Arc;
@@ -20,38 +24,17 @@ impl Lambertian
 	{
 		Self{albedo}
 	}
-}
 
-impl Material for Lambertian
-{
-	fn scatter(&self, r: &Ray, rec: &HitRecord) -> Option<(Vec3d, Ray, f64)>
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
 	{
-		/*let mut scatter_direction = rec.normal + Vec3d::random_unit_vector();
-		if scatter_direction.near_zero()
-		{
-			scatter_direction = rec.normal;
-		}
-
-		scatter_direction = Vec3d::normalize(scatter_direction);
-		let pdf = Vec3d::dot(rec.normal, scatter_direction) / std::f64::consts::PI;
-		Some((self.albedo.value(rec.uv, rec.p), Ray::with_time(rec.p, scatter_direction, r.time()), pdf))*/
-		let uvw = Onb::from_w(rec.normal);
-		let direction = Vec3d::normalize(uvw.local(Vec3d::random_cosine_direction()));
-		Some((self.albedo.value(rec.uv, rec.p),
-		      Ray::with_time(rec.p, direction, r.time()),
-		      Vec3d::dot(uvw.w(), direction) / std::f64::consts::PI))
+		Self::new(scene.parse_xml_color(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Albedo").expect("no Albedo node found for Lambertian")))
 	}
+}
 
-	fn scattering_pdf(&self, r: &Ray, rec: &HitRecord, scattered: &Ray) -> f64
+impl Material for Lambertian
+{
+	fn bsdf(&self, _r: &Ray, rec: &HitRecord, _lambda: &mut SampledWavelengths) -> Option<Bsdf>
 	{
-		let cosine = Vec3d::dot(rec.normal, Vec3d::normalize(scattered.dir()));
-		if cosine < 0.0
-		{
-			0.0
-		}
-		else
-		{
-			cosine / std::f64::consts::PI
-		}
+		Some(Bsdf::new(rec.normal, rec.shading_tangent(), Box::new(DiffuseBxdf::new(self.albedo.value(rec.uv, rec.p)))))
 	}
 }
@@ -0,0 +1,39 @@
+use crate::hittable::HitRecord;
+use crate::media::Medium;
+use crate::scene::Scene;
+
+use std::sync::Arc;
+
+use super::Material;
+
+/* invisible boundary of a medium, rays cross it unchanged and enter the medium through its front face */
+pub struct MediumInterface
+{
+	medium: Arc::<dyn Medium>,
+}
+
+impl MediumInterface
+{
+	pub fn new(medium: Arc::<dyn Medium>) -> Self
+	{
+		Self{medium}
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		Self::new(scene.parse_xml_medium(node))
+	}
+}
+
+impl Material for MediumInterface
+{
+	fn medium(&self, _rec: &HitRecord) -> Option<Arc::<dyn Medium>>
+	{
+		Some(self.medium.clone())
+	}
+
+	fn is_interface(&self) -> bool
+	{
+		true
+	}
+}
@@ -1,7 +1,12 @@
+use crate::bsdf::
+{
+	Bsdf,
+	metal_bxdf::MetalBxdf,
+};
 use crate::hittable::HitRecord;
-use crate::math::vec::Vec3d;
 use crate::ray::Ray;
 use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
 use crate::textures::Texture;
 
 use std::sync::Arc;
@@ -20,19 +25,21 @@ impl Metal
 	{
 		Self{albedo, fuzz}
 	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let albedo = scene.parse_xml_color(&child("Albedo").expect("no Albedo node found for Metal"));
+		let fuzz = child("Fuzz").map_or(0.0, |fuzz| Scene::parse_xml_f64(&fuzz));
+		Self::new(albedo, fuzz)
+	}
 }
 
 impl Material for Metal
 {
-	fn scatter(&self, r: &Ray, rec: &HitRecord) -> Option<(Vec3d, Ray, f64)>
+	fn bsdf(&self, _r: &Ray, rec: &HitRecord, _lambda: &mut SampledWavelengths) -> Option<Bsdf>
 	{
-		let reflected = Vec3d::reflect(Vec3d::normalize(r.dir()), rec.normal);
-		let scattered = Ray::with_time(rec.p, reflected + Vec3d::random_in_unit_sphere() * self.fuzz, r.time());
-		if Vec3d::dot(scattered.dir(), rec.normal) > 0.0
-		{
-			return Some((self.albedo.value(rec.uv, rec.p), scattered, 1.0));
-		}
-
-		None
+		Some(Bsdf::new(rec.normal, rec.shading_tangent(), Box::new(MetalBxdf::new(self.albedo.value(rec.uv, rec.p), self.fuzz))))
 	}
 }
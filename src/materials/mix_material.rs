@@ -0,0 +1,126 @@
+use crate::bsdf::
+{
+	Bsdf,
+	mix_bxdf::MixBxdf,
+};
+use crate::color::PathColor;
+use crate::hittable::HitRecord;
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+	Vec4d,
+};
+use crate::media::Medium;
+use crate::onb::Onb;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
+use crate::textures::
+{
+	Texture,
+	solid_color::SolidColor,
+};
+
+use std::sync::Arc;
+
+use super::Material;
+
+/* blends two materials, the first channel of the weight texture is the fraction of b */
+pub struct MixMaterial
+{
+	a: Arc::<dyn Material>,
+	b: Arc::<dyn Material>,
+	weight: Arc::<dyn Texture>,
+}
+
+impl MixMaterial
+{
+	pub fn new(a: Arc::<dyn Material>, b: Arc::<dyn Material>, weight: Arc::<dyn Texture>) -> Self
+	{
+		Self{a, b, weight}
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name).unwrap_or_else(|| panic!("no {} node found for MixMaterial", name));
+
+		let a = scene.material(Scene::parse_xml_attr_str(&child("A"), "material".to_string()));
+		let b = scene.material(Scene::parse_xml_attr_str(&child("B"), "material".to_string()));
+		let weight = child("Weight");
+		let weight: Arc::<dyn Texture> = match weight.attribute("texture")
+		{
+			Some(texture) => scene.texture(texture.to_string()),
+			None => Arc::new(SolidColor::new(Vec3d::newv(Scene::parse_xml_f64(&weight)))),
+		};
+		Self::new(a, b, weight)
+	}
+
+	fn weight(&self, uv: Vec2d, p: Vec3d) -> f64
+	{
+		f64::clamp(self.weight.value(uv, p).x(), 0.0, 1.0)
+	}
+}
+
+impl Material for MixMaterial
+{
+	fn resolve(&self, scene: &Scene)
+	{
+		self.a.resolve(scene);
+		self.b.resolve(scene);
+	}
+
+	fn bsdf(&self, r: &Ray, rec: &HitRecord, lambda: &mut SampledWavelengths) -> Option<Bsdf>
+	{
+		let weight = self.weight(rec.uv, rec.p);
+		if weight <= 0.0
+		{
+			return self.a.bsdf(r, rec, lambda);
+		}
+		if weight >= 1.0
+		{
+			return self.b.bsdf(r, rec, lambda);
+		}
+
+		let a = self.a.bsdf(r, rec, lambda);
+		let b = self.b.bsdf(r, rec, lambda);
+		if a.is_none() && b.is_none()
+		{
+			return None;
+		}
+
+		Some(Bsdf::new(rec.normal, rec.shading_tangent(), Box::new(MixBxdf::new(Onb::from_wu(rec.normal, rec.shading_tangent()), a, b, weight))))
+	}
+
+	fn emitted(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
+	{
+		let weight = self.weight(uv, p);
+		self.a.emitted(r, rec, uv, p) * (1.0 - weight) + self.b.emitted(r, rec, uv, p) * weight
+	}
+
+	fn emitted_spectrum(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d, lambda: &SampledWavelengths) -> Option<Vec4d>
+	{
+		let a = self.a.emitted_spectrum(r, rec, uv, p, lambda);
+		let b = self.b.emitted_spectrum(r, rec, uv, p, lambda);
+		if a.is_none() && b.is_none()
+		{
+			return None;
+		}
+
+		/* a material without spectral emission is upsampled like the integrator would */
+		let spectrum = |s: Option<Vec4d>, material: &Arc::<dyn Material>| s.unwrap_or_else(|| Vec4d::from_rgb(material.emitted(r, rec, uv, p), lambda));
+		let weight = self.weight(uv, p);
+		Some(spectrum(a, &self.a) * (1.0 - weight) + spectrum(b, &self.b) * weight)
+	}
+
+	fn opacity(&self, uv: Vec2d, p: Vec3d) -> f64
+	{
+		let weight = self.weight(uv, p);
+		self.a.opacity(uv, p) * (1.0 - weight) + self.b.opacity(uv, p) * weight
+	}
+
+	fn medium(&self, rec: &HitRecord) -> Option<Arc::<dyn Medium>>
+	{
+		if self.weight(rec.uv, rec.p) < 0.5 { self.a.medium(rec) } else { self.b.medium(rec) }
+	}
+}
@@ -1,18 +1,32 @@
+pub mod bump_map;
+pub mod cloth;
+pub mod coated;
+pub mod conductor;
+pub mod cutout;
 pub mod dielectric;
 pub mod diffuse_light;
 pub mod isotropic;
 pub mod lambertian;
+pub mod medium_interface;
 pub mod metal;
+pub mod mix_material;
 pub mod mtl;
+pub mod normal_map;
+pub mod principled;
+pub mod subsurface;
 
+use crate::bsdf::Bsdf;
 use crate::hittable::HitRecord;
 use crate::math::vec::
 {
 	Vec2d,
 	Vec3d,
+	Vec4d,
 };
+use crate::media::Medium;
 use crate::ray::Ray;
 use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
 
 use std::sync::Arc;
 
@@ -22,18 +36,37 @@ pub trait Material: Send + Sync
 	{
 	}
 
-	fn scatter(&self, _r: &Ray, _rec: &HitRecord) -> Option<(Vec3d, Ray, f64)>
+	fn bsdf(&self, _r: &Ray, _rec: &HitRecord, _lambda: &mut SampledWavelengths) -> Option<Bsdf>
 	{
 		None
 	}
 
-	fn scattering_pdf(&self, _r: &Ray, _rec: &HitRecord, _scattered: &Ray) -> f64
+	fn emitted(&self, _r: &Ray, _rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
 	{
-		0.0
+		Vec3d::zero()
 	}
 
-	fn emitted(&self, _r: &Ray, _rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
+	/* medium filling the interior, entered by transmission through the front face */
+	fn medium(&self, _rec: &HitRecord) -> Option<Arc::<dyn Medium>>
 	{
-		Vec3d::zero()
+		None
+	}
+
+	/* surfaces without a bsdf that only delimit a medium, paths and shadow rays cross them */
+	fn is_interface(&self) -> bool
+	{
+		false
+	}
+
+	/* surfaces with an opacity below one are skipped by the intersection routines */
+	fn opacity(&self, _uv: Vec2d, _p: Vec3d) -> f64
+	{
+		1.0
+	}
+
+	/* emission at the sampled wavelengths, None falls back to upsampling the rgb emission */
+	fn emitted_spectrum(&self, _r: &Ray, _rec: &HitRecord, _uv: Vec2d, _p: Vec3d, _lambda: &SampledWavelengths) -> Option<Vec4d>
+	{
+		None
 	}
 }
@@ -1,161 +1,230 @@
-use crate::hittable::HitRecord;
-use crate::math::vec::
-{
-	Vec2d,
-	Vec3d,
-};
-use crate::ray::Ray;
+use crate::math::vec::Vec3d;
 use crate::textures::
 {
 	Texture,
 	image_texture::ImageTexture,
+	scale_texture::ScaleTexture,
+	solid_color::SolidColor,
 };
 
-use rand::Rng;
-
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
-use super::Material;
+use super::
+{
+	Material,
+	bump_map::BumpMap,
+	cutout::Cutout,
+	normal_map::NormalMap,
+	principled::Principled,
+};
 
 use wavefront_obj::mtl;
 
-enum MtlIllumination
+/* the materials of a Wavefront library by name */
+pub fn load(path: &Path) -> HashMap::<String, Arc::<dyn Material>>
 {
-	Ambient,
-	AmbientDiffuse,
-	AmbientDiffuseSpecular,
-	ReflectionRayTrace,
+	let content = std::fs::read_to_string(path).expect("can't read material library");
+	let (content, normal_maps) = take_normal_maps(&content);
+	let set = mtl::parse(content).unwrap_or_else(|err| panic!("failed to parse {}: {:?}", path.display(), err));
+	let directory = path.parent().unwrap_or(Path::new(""));
+	let mut materials = HashMap::<String, Arc::<dyn Material>>::new();
+	for material in &set.materials
+	{
+		materials.insert(material.name.clone(), to_material(material, normal_maps.get(&material.name), directory));
+	}
+	materials
 }
 
-impl From<mtl::Illumination> for MtlIllumination
+/* the parser does not know the norm statement, its maps are taken out by material name beforehand */
+fn take_normal_maps(content: &str) -> (String, HashMap::<String, String>)
 {
-	fn from(illumination: mtl::Illumination) -> Self
+	let mut stripped = String::new();
+	let mut normal_maps = HashMap::<String, String>::new();
+	let mut name = String::new();
+	for line in content.lines()
 	{
-		match illumination
+		let mut words = line.split_whitespace();
+		match words.next()
 		{
-			mtl::Illumination::Ambient                => MtlIllumination::Ambient,
-			mtl::Illumination::AmbientDiffuse         => MtlIllumination::AmbientDiffuse,
-			mtl::Illumination::AmbientDiffuseSpecular => MtlIllumination::AmbientDiffuseSpecular,
-			mtl::Illumination::ReflectionRayTrace     => MtlIllumination::ReflectionRayTrace,
+			Some("newmtl") => name = words.next().unwrap_or("").to_string(),
+			Some("norm") =>
+			{
+				/* the file name comes after any options */
+				if let Some(filename) = words.last()
+				{
+					normal_maps.insert(name.clone(), filename.to_string());
+				}
+				continue;
+			},
+			_ => (),
 		}
+		stripped.push_str(line);
+		stripped.push('\n');
 	}
+	(stripped, normal_maps)
 }
 
-pub struct Mtl
+/*
+ * best effort conversion of the Wavefront material fields, maps are relative to the directory of the library
+ * the bump or normal map wraps the Principled material, dissolve cuts out of the result
+ */
+pub fn to_material(mtl: &mtl::Material, normal_map: Option<&String>, directory: &Path) -> Arc::<dyn Material>
 {
-	specular_coefficient: f64,
-	color_ambient: Vec3d,
-	color_diffuse: Vec3d,
-	color_specular: Vec3d,
-	color_emissive: Option<Vec3d>,
-	optical_density: Option<f64>,
-	alpha: f64,
-	illumination: MtlIllumination,
-	ambient_map: Option<Arc::<dyn Texture>>,
-	diffuse_map: Option<Arc::<dyn Texture>>,
-	specular_map: Option<Arc::<dyn Texture>>,
-	specular_exponent_map: Option<Arc::<dyn Texture>>,
-	dissolve_map: Option<Arc::<dyn Texture>>,
-	displacement_map: Option<Arc::<dyn Texture>>,
-	decal_map: Option<Arc::<dyn Texture>>,
-	bump_map: Option<Arc::<dyn Texture>>,
-}
-
-impl Mtl
-{
-	pub fn new(mtl: &mtl::Material) -> Self
-	{
-		Self{specular_coefficient:  mtl.specular_coefficient,
-		     color_ambient:         Self::to_color(mtl.color_ambient),
-		     color_diffuse:         Self::to_color(mtl.color_diffuse),
-		     color_specular:        Self::to_color(mtl.color_specular),
-		     color_emissive:        Self::to_opt_color(mtl.color_emissive),
-		     optical_density:       mtl.optical_density,
-		     alpha:                 mtl.alpha,
-		     illumination:          MtlIllumination::from(mtl.illumination),
-		     ambient_map:           Self::to_texture(&mtl.ambient_map),
-		     diffuse_map:           Self::to_texture(&mtl.diffuse_map),
-		     specular_map:          Self::to_texture(&mtl.specular_map),
-		     specular_exponent_map: Self::to_texture(&mtl.specular_exponent_map),
-		     dissolve_map:          Self::to_texture(&mtl.dissolve_map),
-		     displacement_map:      Self::to_texture(&mtl.displacement_map),
-		     decal_map:             Self::to_texture(&mtl.decal_map),
-		     bump_map:              Self::to_texture(&mtl.bump_map)}
+	let image = |filename: &String| -> Arc::<dyn Texture> { Arc::new(ImageTexture::new(&directory.join(filename).to_string_lossy())) };
+	let texture = |file: &Option<String>, factor: Vec3d| -> Arc::<dyn Texture>
+	{
+		match file
+		{
+			None => Arc::new(SolidColor::new(factor)),
+			Some(filename) => Arc::new(ScaleTexture::new(image(filename), factor)),
+		}
+	};
+
+	/* match the lobe width of the Phong exponent, alpha = sqrt(2 / (n + 2)) and alpha = roughness^2 */
+	let roughness = f64::powf(2.0 / (f64::max(mtl.specular_coefficient, 0.0) + 2.0), 0.25);
+	let (specular, metallic) = specular_metallic(mtl);
+
+	let mut principled = Principled::new(texture(&mtl.diffuse_map, color(mtl.color_diffuse)))
+		.with_metallic(Arc::new(SolidColor::new(Vec3d::newv(metallic))))
+		.with_roughness(Arc::new(SolidColor::new(Vec3d::newv(roughness))))
+		.with_specular(texture(&mtl.specular_map, Vec3d::newv(specular)))
+		.with_transmission(texture(&mtl.dissolve_map, Vec3d::newv(f64::clamp(1.0 - mtl.alpha, 0.0, 1.0))));
+	if let Some(ior) = mtl.optical_density
+	{
+		principled = principled.with_ior(ior);
 	}
+	if let Some(emissive) = mtl.color_emissive
+	{
+		principled = principled.with_emission(Arc::new(SolidColor::new(color(emissive))));
+	}
+	let mut material: Arc::<dyn Material> = Arc::new(principled);
 
-	fn to_color(col: mtl::Color) -> Vec3d
+	/* displacement is not supported, the displacement map is used as a bump map when there is none */
+	if let Some(filename) = normal_map
 	{
-		Vec3d::new(col.r, col.g, col.b)
+		material = Arc::new(NormalMap::new(material, image(filename)));
+	}
+	else if let Some(filename) = mtl.bump_map.as_ref().or(mtl.displacement_map.as_ref())
+	{
+		material = Arc::new(BumpMap::new(material, image(filename)));
 	}
 
-	fn to_opt_color(col: Option<mtl::Color>) -> Option<Vec3d>
+	/* d and map_d are coverage, not transmission, the dissolved part lets camera and shadow rays through */
+	if mtl.alpha < 1.0 || mtl.dissolve_map.is_some()
 	{
-		match col
-		{
-			None => None,
-			Some(color) => Some(Self::to_color(color))
-		}
+		material = Arc::new(Cutout::new(material, texture(&mtl.dissolve_map, Vec3d::newv(f64::clamp(mtl.alpha, 0.0, 1.0)))));
 	}
+	material
+}
+
+fn color(col: mtl::Color) -> Vec3d
+{
+	Vec3d::new(col.r, col.g, col.b)
+}
 
-	fn to_texture(file: &Option<String>) -> Option<Arc::<dyn Texture>>
+/* Ks is the reflectance at normal incidence, a dielectric reaches 0.08 at full specular and only a mirror (illum 3) goes metallic beyond */
+fn specular_metallic(mtl: &mtl::Material) -> (f64, f64)
+{
+	let ks = color(mtl.color_specular).luminance();
+	match mtl.illumination
 	{
-		match file
-		{
-			None => None,
-			Some(filename) =>
-			{
-				Some(Arc::new(ImageTexture::new(&filename)))
-			}
-		}
+		mtl::Illumination::Ambient | mtl::Illumination::AmbientDiffuse => (0.0, 0.0),
+		mtl::Illumination::AmbientDiffuseSpecular                     => (f64::clamp(ks / 0.08, 0.0, 1.0), 0.0),
+		mtl::Illumination::ReflectionRayTrace                         => (f64::clamp(ks / 0.08, 0.0, 1.0), f64::clamp((ks - 0.08) / 0.92, 0.0, 1.0)),
 	}
 }
 
-impl Material for Mtl
+#[cfg(test)]
+mod tests
 {
-	fn scatter(&self, r: &Ray, rec: &HitRecord) -> Option<(Vec3d, Ray, f64)>
+
+	use super::*;
+	use crate::hittable::HitRecord;
+	use crate::math::vec::Vec2d;
+	use crate::ray::Ray;
+	use crate::spectrum::SampledWavelengths;
+
+	use image::
 	{
-		let mut rng = rand::thread_rng();
-		let specular_pos = f64::powf(f64::max(0.0, Vec3d::dot(rec.normal, -r.dir())), self.specular_coefficient);
-		if rng.gen_range(0.0..1.0) < specular_pos
-		{
-			let reflected = Vec3d::reflect(Vec3d::normalize(r.dir()), rec.normal);
-			let scattered = Ray::with_time(rec.p, reflected, r.time());
-			if Vec3d::dot(scattered.dir(), rec.normal) > 0.0
-			{
-				let mut color_specular = self.color_specular;
-				if let Some(tex) = &self.specular_map
-				{
-					color_specular += (*tex).value(rec.uv, rec.p);
-				}
+		Rgb,
+		RgbImage,
+	};
 
-				return Some((color_specular, scattered, 1.0));
-			}
+	fn material(illumination: mtl::Illumination, ks: f64) -> mtl::Material
+	{
+		let color = |v: f64| mtl::Color{r: v, g: v, b: v};
+		mtl::Material{name: "test".to_string(),
+		              specular_coefficient: 100.0,
+		              color_ambient: color(0.0),
+		              color_diffuse: color(0.5),
+		              color_specular: color(ks),
+		              color_emissive: None,
+		              optical_density: None,
+		              alpha: 1.0,
+		              illumination,
+		              ambient_map: None,
+		              diffuse_map: None,
+		              specular_map: None,
+		              specular_exponent_map: None,
+		              dissolve_map: None,
+		              displacement_map: None,
+		              decal_map: None,
+		              bump_map: None}
+	}
 
-			return None;
-		}
+	#[test]
+	fn test_mtl_specular()
+	{
+		let (specular, metallic) = specular_metallic(&material(mtl::Illumination::AmbientDiffuseSpecular, 0.04));
+		assert!((specular - 0.5).abs() < 1e-9);
+		assert_eq!(metallic, 0.0);
 
-		/* diffuse */
-		let mut scatter_direction = rec.normal + Vec3d::random_unit_vector();
-		if scatter_direction.near_zero()
-		{
-			scatter_direction = rec.normal;
-		}
+		/* a strong highlight without ray traced reflection is still a dielectric */
+		assert_eq!(specular_metallic(&material(mtl::Illumination::AmbientDiffuseSpecular, 1.0)), (1.0, 0.0));
+		assert_eq!(specular_metallic(&material(mtl::Illumination::AmbientDiffuse, 0.5)), (0.0, 0.0));
+	}
 
-		let mut color_diffuse = self.color_diffuse;
-		if let Some(tex) = &self.diffuse_map
-		{
-			color_diffuse += tex.value(rec.uv, rec.p);
-		}
+	#[test]
+	fn test_mtl_metallic()
+	{
+		assert_eq!(specular_metallic(&material(mtl::Illumination::ReflectionRayTrace, 0.04)).1, 0.0);
+		assert!((specular_metallic(&material(mtl::Illumination::ReflectionRayTrace, 0.54)).1 - 0.5).abs() < 1e-9);
+		assert_eq!(specular_metallic(&material(mtl::Illumination::ReflectionRayTrace, 1.0)), (1.0, 1.0));
+	}
+
+	/* a library with a flat, a bump mapped and a normal mapped material next to a black and white step and a tilted normal */
+	fn library() -> HashMap::<String, Arc::<dyn Material>>
+	{
+		let directory = std::env::temp_dir().join("raytracer_mtl_maps");
+		std::fs::create_dir_all(&directory).unwrap();
+		RgbImage::from_fn(2, 1, |x, _| if x == 0 { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) }).save(directory.join("map.png")).unwrap();
+		RgbImage::from_pixel(1, 1, Rgb([230, 128, 204])).save(directory.join("normal.png")).unwrap();
+
+		let entry = |name: &str, map: &str| format!("newmtl {}\nNs 10\nKa 0 0 0\nKd 0.5 0.5 0.5\nKs 0 0 0\nd 1\nillum 2\n{}\n", name, map);
+		let content = entry("flat", "") + &entry("bumped", "map_bump map.png") + &entry("displaced", "disp map.png") + &entry("normal", "norm -bm 1 normal.png");
+		std::fs::write(directory.join("maps.mtl"), content).unwrap();
+		load(&directory.join("maps.mtl"))
+	}
 
-		Some((color_diffuse, Ray::with_time(rec.p, scatter_direction, r.time()), 1.0))
+	/* diffuse response on the plane z = 0 just left of the step in the map */
+	fn eval(material: &Arc::<dyn Material>) -> Vec3d
+	{
+		let r = Ray::new(Vec3d::new(0.0, 0.0, 1.0), Vec3d::new(0.0, 0.0, -1.0));
+		let rec = HitRecord::new(&r, Vec3d::zero(), 1.0, Vec2d::new(0.4999, 0.5), Vec3d::new(0.0, 0.0, 1.0), material.clone()).with_tangents(Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0));
+		let bsdf = material.bsdf(&r, &rec, &mut SampledWavelengths::sample_uniform(0.5, false)).unwrap();
+		bsdf.eval(Vec3d::new(0.0, 0.0, 1.0), Vec3d::normalize(Vec3d::new(1.0, 0.0, 1.0)))
 	}
 
-	fn emitted(&self, _r: &Ray, _rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
+	#[test]
+	fn test_mtl_bump_and_normal_maps()
 	{
-		match self.color_emissive
+		let materials = library();
+		let flat = eval(&materials["flat"]);
+		for name in ["bumped", "displaced", "normal"]
 		{
-			None => Vec3d::zero(),
-			Some(color) => color,
+			assert!(Vec3d::length(eval(&materials[name]) - flat) > 0.1 * Vec3d::length(flat), "{}", name);
 		}
 	}
 }
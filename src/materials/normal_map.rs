@@ -0,0 +1,178 @@
+use crate::bsdf::Bsdf;
+use crate::hittable::HitRecord;
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+	Vec4d,
+};
+use crate::media::Medium;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
+use crate::textures::Texture;
+
+use std::sync::Arc;
+
+use super::Material;
+
+/* tangent space normal map, x along dpdu, y along dpdv and z along the normal */
+pub struct NormalMap
+{
+	base: Arc::<dyn Material>,
+	map: Arc::<dyn Texture>,
+	strength: f64,
+}
+
+impl NormalMap
+{
+	pub fn new(base: Arc::<dyn Material>, map: Arc::<dyn Texture>) -> Self
+	{
+		Self{base, map, strength: 1.0}
+	}
+
+	/* blend between the surface normal at 0 and the mapped normal at 1 */
+	pub fn with_strength(mut self, strength: f64) -> Self
+	{
+		self.strength = strength;
+		self
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let base = scene.material(Scene::parse_xml_attr_str(&child("Base").expect("no Base node found for NormalMap"), "material".to_string()));
+		let map = scene.texture(Scene::parse_xml_attr_str(&child("Map").expect("no Map node found for NormalMap"), "texture".to_string()));
+		let mut normal_map = Self::new(base, map);
+		if let Some(strength) = child("Strength")
+		{
+			normal_map = normal_map.with_strength(Scene::parse_xml_f64(&strength));
+		}
+		normal_map
+	}
+
+	pub fn perturb(r: &Ray, rec: &mut HitRecord, map: &Arc::<dyn Texture>, strength: f64)
+	{
+		let n = rec.outward_normal();
+		let (dpdu, dpdv) = rec.tangents();
+		let t = Vec3d::normalize(dpdu - n * Vec3d::dot(n, dpdu));
+		let mut b = Vec3d::cross(n, t);
+		if Vec3d::dot(b, dpdv) < 0.0
+		{
+			b = -b;
+		}
+
+		let value = map.value(rec.uv, rec.p) * 2.0 - Vec3d::one();
+		let mapped = Vec3d::normalize(t * value.x() + b * value.y() + n * value.z());
+		rec.set_shading_normal(r, Vec3d::normalize(n * (1.0 - strength) + mapped * strength));
+	}
+}
+
+impl Material for NormalMap
+{
+	fn resolve(&self, scene: &Scene)
+	{
+		self.base.resolve(scene);
+	}
+
+	fn bsdf(&self, r: &Ray, rec: &HitRecord, lambda: &mut SampledWavelengths) -> Option<Bsdf>
+	{
+		let mut rec = rec.clone();
+		Self::perturb(r, &mut rec, &self.map, self.strength);
+		self.base.bsdf(r, &rec, lambda)
+	}
+
+	fn emitted(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
+	{
+		self.base.emitted(r, rec, uv, p)
+	}
+
+	fn emitted_spectrum(&self, r: &Ray, rec: &HitRecord, uv: Vec2d, p: Vec3d, lambda: &SampledWavelengths) -> Option<Vec4d>
+	{
+		self.base.emitted_spectrum(r, rec, uv, p, lambda)
+	}
+
+	fn opacity(&self, uv: Vec2d, p: Vec3d) -> f64
+	{
+		self.base.opacity(uv, p)
+	}
+
+	fn medium(&self, rec: &HitRecord) -> Option<Arc::<dyn Medium>>
+	{
+		self.base.medium(rec)
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::materials::lambertian::Lambertian;
+	use crate::textures::solid_color::SolidColor;
+
+	fn color(v: Vec3d) -> Arc::<dyn Texture>
+	{
+		Arc::new(SolidColor::new(v))
+	}
+
+	/* hit on the plane z = 0 with u along x and v along y, from above or from below */
+	fn hit(from_above: bool) -> (Ray, HitRecord)
+	{
+		let dir = if from_above { Vec3d::new(0.0, 0.0, -1.0) } else { Vec3d::new(0.0, 0.0, 1.0) };
+		let r = Ray::new(-dir, dir);
+		let rec = HitRecord::new(&r, Vec3d::zero(), 1.0, Vec2d::zero(), Vec3d::new(0.0, 0.0, 1.0), Arc::new(Lambertian::new(color(Vec3d::one()))));
+		(r, rec.with_tangents(Vec3d::new(1.0, 0.0, 0.0), Vec3d::new(0.0, 1.0, 0.0)))
+	}
+
+	fn perturbed(from_above: bool, map: Vec3d, strength: f64) -> Vec3d
+	{
+		let (r, mut rec) = hit(from_above);
+		NormalMap::perturb(&r, &mut rec, &color(map), strength);
+		rec.normal
+	}
+
+	#[test]
+	fn test_flat_map_keeps_the_normal()
+	{
+		assert!(Vec3d::length(perturbed(true, Vec3d::new(0.5, 0.5, 1.0), 1.0) - Vec3d::new(0.0, 0.0, 1.0)) < 1e-12);
+	}
+
+	#[test]
+	fn test_map_is_in_tangent_space()
+	{
+		/* (0.8, 0.5, 0.9) encodes (0.6, 0, 0.8) */
+		let expected = Vec3d::new(0.6, 0.0, 0.8);
+		assert!(Vec3d::length(perturbed(true, Vec3d::new(0.8, 0.5, 0.9), 1.0) - expected) < 1e-12);
+		assert!(Vec3d::length(perturbed(true, Vec3d::new(0.5, 0.2, 0.9), 1.0) - Vec3d::new(0.0, -0.6, 0.8)) < 1e-12);
+
+		/* the back face gets the mirrored normal, facing the ray */
+		assert!(Vec3d::length(perturbed(false, Vec3d::new(0.8, 0.5, 0.9), 1.0) + expected) < 1e-12);
+	}
+
+	#[test]
+	fn test_strength_blends_towards_the_surface_normal()
+	{
+		assert!(Vec3d::length(perturbed(true, Vec3d::new(0.8, 0.5, 0.9), 0.0) - Vec3d::new(0.0, 0.0, 1.0)) < 1e-12);
+		let half = perturbed(true, Vec3d::new(0.8, 0.5, 0.9), 0.5);
+		assert!(half.x() > 0.0 && half.x() < 0.6);
+	}
+
+	#[test]
+	fn test_shading_tangent_follows_the_mapped_normal()
+	{
+		/* dpdu made orthogonal to the tilted normal, still pointing along x */
+		let (r, mut rec) = hit(true);
+		NormalMap::perturb(&r, &mut rec, &color(Vec3d::new(0.8, 0.5, 0.9)), 1.0);
+		let tangent = Vec3d::normalize(rec.shading_tangent());
+		assert!(Vec3d::length(tangent - Vec3d::new(0.8, 0.0, -0.6)) < 1e-12);
+	}
+
+	#[test]
+	fn test_normals_facing_away_are_ignored()
+	{
+		/* (0.5, 0.5, 0) encodes a normal pointing into the surface, away from the ray */
+		assert!(Vec3d::length(perturbed(true, Vec3d::new(0.5, 0.5, 0.0), 1.0) - Vec3d::new(0.0, 0.0, 1.0)) < 1e-12);
+	}
+}
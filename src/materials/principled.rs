@@ -0,0 +1,269 @@
+use crate::bsdf::
+{
+	Bsdf,
+	principled_bxdf::
+	{
+		PrincipledBxdf,
+		PrincipledParams,
+	},
+};
+use crate::hittable::HitRecord;
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
+use crate::textures::
+{
+	Texture,
+	channel_texture::ChannelTexture,
+	scale_texture::ScaleTexture,
+	solid_color::SolidColor,
+};
+
+use std::sync::Arc;
+
+use super::Material;
+
+type TextureSetter = fn(Principled, Arc::<dyn Texture>) -> Principled;
+
+/* Disney principled material, scalar parameters are read from the first channel of their texture */
+pub struct Principled
+{
+	base_color: Arc::<dyn Texture>,
+	metallic: Arc::<dyn Texture>,
+	roughness: Arc::<dyn Texture>,
+	specular: Arc::<dyn Texture>,
+	specular_tint: Arc::<dyn Texture>,
+	sheen: Arc::<dyn Texture>,
+	sheen_tint: Arc::<dyn Texture>,
+	clearcoat: Arc::<dyn Texture>,
+	clearcoat_gloss: Arc::<dyn Texture>,
+	transmission: Arc::<dyn Texture>,
+	subsurface: Arc::<dyn Texture>,
+	anisotropic: Arc::<dyn Texture>,
+	emission: Option<Arc::<dyn Texture>>,
+	ior: f64,
+}
+
+impl Principled
+{
+	pub fn new(base_color: Arc::<dyn Texture>) -> Self
+	{
+		Self{base_color,
+		     metallic:        Self::constant(0.0),
+		     roughness:       Self::constant(0.5),
+		     specular:        Self::constant(0.5),
+		     specular_tint:   Self::constant(0.0),
+		     sheen:           Self::constant(0.0),
+		     sheen_tint:      Self::constant(0.5),
+		     clearcoat:       Self::constant(0.0),
+		     clearcoat_gloss: Self::constant(1.0),
+		     transmission:    Self::constant(0.0),
+		     subsurface:      Self::constant(0.0),
+		     anisotropic:     Self::constant(0.0),
+		     emission:        None,
+		     ior:             1.5}
+	}
+
+	fn constant(value: f64) -> Arc::<dyn Texture>
+	{
+		Arc::new(SolidColor::new(Vec3d::newv(value)))
+	}
+
+	pub fn with_metallic(mut self, metallic: Arc::<dyn Texture>) -> Self
+	{
+		self.metallic = metallic;
+		self
+	}
+
+	pub fn with_roughness(mut self, roughness: Arc::<dyn Texture>) -> Self
+	{
+		self.roughness = roughness;
+		self
+	}
+
+	pub fn with_specular(mut self, specular: Arc::<dyn Texture>) -> Self
+	{
+		self.specular = specular;
+		self
+	}
+
+	pub fn with_specular_tint(mut self, specular_tint: Arc::<dyn Texture>) -> Self
+	{
+		self.specular_tint = specular_tint;
+		self
+	}
+
+	pub fn with_sheen(mut self, sheen: Arc::<dyn Texture>) -> Self
+	{
+		self.sheen = sheen;
+		self
+	}
+
+	pub fn with_sheen_tint(mut self, sheen_tint: Arc::<dyn Texture>) -> Self
+	{
+		self.sheen_tint = sheen_tint;
+		self
+	}
+
+	pub fn with_clearcoat(mut self, clearcoat: Arc::<dyn Texture>) -> Self
+	{
+		self.clearcoat = clearcoat;
+		self
+	}
+
+	pub fn with_clearcoat_gloss(mut self, clearcoat_gloss: Arc::<dyn Texture>) -> Self
+	{
+		self.clearcoat_gloss = clearcoat_gloss;
+		self
+	}
+
+	pub fn with_transmission(mut self, transmission: Arc::<dyn Texture>) -> Self
+	{
+		self.transmission = transmission;
+		self
+	}
+
+	pub fn with_subsurface(mut self, subsurface: Arc::<dyn Texture>) -> Self
+	{
+		self.subsurface = subsurface;
+		self
+	}
+
+	pub fn with_anisotropic(mut self, anisotropic: Arc::<dyn Texture>) -> Self
+	{
+		self.anisotropic = anisotropic;
+		self
+	}
+
+	pub fn with_emission(mut self, emission: Arc::<dyn Texture>) -> Self
+	{
+		self.emission = Some(emission);
+		self
+	}
+
+	pub fn with_ior(mut self, ior: f64) -> Self
+	{
+		self.ior = ior;
+		self
+	}
+
+	/* glTF 2.0 metallic-roughness texture, roughness in green and metallic in blue scaled by their factors */
+	pub fn with_metallic_roughness(self, texture: Arc::<dyn Texture>, metallic_factor: f64, roughness_factor: f64) -> Self
+	{
+		self.with_metallic(Arc::new(ScaleTexture::new(Arc::new(ChannelTexture::new(texture.clone(), 2)), Vec3d::newv(metallic_factor))))
+		    .with_roughness(Arc::new(ScaleTexture::new(Arc::new(ChannelTexture::new(texture, 1)), Vec3d::newv(roughness_factor))))
+	}
+
+	/* each parameter is a child node with either a texture name, a value or x y z attributes */
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let param = |name: &str| -> Option<Arc::<dyn Texture>>
+		{
+			let child = node.children().find(|node| node.is_element() && node.tag_name().name() == name)?;
+			if let Some(texture) = child.attribute("texture")
+			{
+				Some(scene.texture(texture.to_string()))
+			}
+			else if child.attribute("value").is_some()
+			{
+				Some(Self::constant(Scene::parse_xml_f64(&child)))
+			}
+			else
+			{
+				Some(Arc::new(SolidColor::new(Scene::parse_xml_vec3d(&child))))
+			}
+		};
+
+		let mut principled = Self::new(param("BaseColor").expect("no BaseColor node found for Principled"));
+		let setters: [(&str, TextureSetter); 12] =
+		[
+			("Metallic",       Self::with_metallic),
+			("Roughness",      Self::with_roughness),
+			("Specular",       Self::with_specular),
+			("SpecularTint",   Self::with_specular_tint),
+			("Sheen",          Self::with_sheen),
+			("SheenTint",      Self::with_sheen_tint),
+			("Clearcoat",      Self::with_clearcoat),
+			("ClearcoatGloss", Self::with_clearcoat_gloss),
+			("Transmission",   Self::with_transmission),
+			("Subsurface",     Self::with_subsurface),
+			("Anisotropic",    Self::with_anisotropic),
+			("Emission",       Self::with_emission),
+		];
+		for (name, setter) in setters
+		{
+			if let Some(texture) = param(name)
+			{
+				principled = setter(principled, texture);
+			}
+		}
+		if let Some(ior) = node.children().find(|node| node.is_element() && node.tag_name().name() == "Ior")
+		{
+			principled = principled.with_ior(Scene::parse_xml_f64(&ior));
+		}
+		/* a packed glTF texture with optional metallic and roughness factors */
+		if let Some(packed) = node.children().find(|node| node.is_element() && node.tag_name().name() == "MetallicRoughness")
+		{
+			let factor = |name: &str| packed.attribute(name).map_or(1.0, |_| Scene::parse_xml_attr_f64(&packed, name.to_string()));
+			principled = principled.with_metallic_roughness(scene.texture(Scene::parse_xml_attr_str(&packed, "texture".to_string())), factor("metallic"), factor("roughness"));
+		}
+		principled
+	}
+
+	fn scalar(texture: &Arc::<dyn Texture>, rec: &HitRecord) -> f64
+	{
+		f64::clamp(texture.value(rec.uv, rec.p).x(), 0.0, 1.0)
+	}
+}
+
+impl Material for Principled
+{
+	fn bsdf(&self, _r: &Ray, rec: &HitRecord, _lambda: &mut SampledWavelengths) -> Option<Bsdf>
+	{
+		let params = PrincipledParams{base_color:      self.base_color.value(rec.uv, rec.p),
+		                              metallic:        Self::scalar(&self.metallic, rec),
+		                              roughness:       Self::scalar(&self.roughness, rec),
+		                              specular:        Self::scalar(&self.specular, rec),
+		                              specular_tint:   Self::scalar(&self.specular_tint, rec),
+		                              sheen:           Self::scalar(&self.sheen, rec),
+		                              sheen_tint:      Self::scalar(&self.sheen_tint, rec),
+		                              clearcoat:       Self::scalar(&self.clearcoat, rec),
+		                              clearcoat_gloss: Self::scalar(&self.clearcoat_gloss, rec),
+		                              transmission:    Self::scalar(&self.transmission, rec),
+		                              subsurface:      Self::scalar(&self.subsurface, rec),
+		                              anisotropic:     Self::scalar(&self.anisotropic, rec),
+		                              eta:             if rec.front_face { self.ior } else { 1.0 / self.ior }};
+		Some(Bsdf::new(rec.normal, rec.shading_tangent(), Box::new(PrincipledBxdf::new(&params))))
+	}
+
+	fn emitted(&self, _r: &Ray, _rec: &HitRecord, uv: Vec2d, p: Vec3d) -> Vec3d
+	{
+		match &self.emission
+		{
+			None => Vec3d::zero(),
+			Some(emission) => emission.value(uv, p),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	#[test]
+	fn test_metallic_roughness_channels()
+	{
+		let packed = Arc::new(SolidColor::new(Vec3d::new(0.0, 0.6, 0.8)));
+		let principled = Principled::new(Principled::constant(1.0)).with_metallic_roughness(packed, 0.5, 1.0);
+		let value = |texture: &Arc::<dyn Texture>| texture.value(Vec2d::zero(), Vec3d::zero()).x();
+		assert!((value(&principled.metallic) - 0.4).abs() < 1e-9);
+		assert!((value(&principled.roughness) - 0.6).abs() < 1e-9);
+	}
+}
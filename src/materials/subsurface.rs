@@ -0,0 +1,180 @@
+use crate::bsdf::
+{
+	Bsdf,
+	dielectric_bxdf::DielectricBxdf,
+	microfacet::TrowbridgeReitz,
+};
+use crate::hittable::HitRecord;
+use crate::math::vec::Vec3d;
+use crate::media::
+{
+	Medium,
+	homogeneous_medium::HomogeneousMedium,
+};
+use crate::phase_functions::henyey_greenstein::HenyeyGreenstein;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::spectrum::SampledWavelengths;
+use crate::textures::
+{
+	Texture,
+	solid_color::SolidColor,
+};
+
+use std::sync::Arc;
+
+use super::Material;
+
+/*
+ * dielectric boundary of a scattering interior, paths entering it random walk through a homogeneous medium
+ * albedo is the single scattering albedo at the entry point, the boundary must be closed
+ */
+pub struct Subsurface
+{
+	albedo: Arc::<dyn Texture>,
+	mean_free_path: Vec3d,
+	ior: f64,
+	roughness: f64,
+	anisotropy: f64,
+}
+
+impl Subsurface
+{
+	pub fn new(albedo: Arc::<dyn Texture>, mean_free_path: Vec3d) -> Self
+	{
+		Self{albedo, mean_free_path, ior: 1.33, roughness: 0.0, anisotropy: 0.0}
+	}
+
+	pub fn with_ior(mut self, ior: f64) -> Self
+	{
+		self.ior = ior;
+		self
+	}
+
+	pub fn with_roughness(mut self, roughness: f64) -> Self
+	{
+		self.roughness = roughness;
+		self
+	}
+
+	/* Henyey-Greenstein asymmetry of the interior, skin and most tissues scatter strongly forward */
+	pub fn with_anisotropy(mut self, anisotropy: f64) -> Self
+	{
+		self.anisotropy = anisotropy;
+		self
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let albedo = child("Albedo").expect("no Albedo node found for Subsurface");
+		let albedo: Arc::<dyn Texture> = match albedo.attribute("texture")
+		{
+			Some(texture) => scene.texture(texture.to_string()),
+			None => Arc::new(SolidColor::new(Scene::parse_xml_vec3d(&albedo))),
+		};
+		let mean_free_path = Scene::parse_xml_vec3d(&child("MeanFreePath").expect("no MeanFreePath node found for Subsurface"));
+		let mut subsurface = Self::new(albedo, mean_free_path);
+		if let Some(ior) = child("Ior")
+		{
+			subsurface = subsurface.with_ior(Scene::parse_xml_f64(&ior));
+		}
+		if let Some(roughness) = child("Roughness")
+		{
+			subsurface = subsurface.with_roughness(Scene::parse_xml_f64(&roughness));
+		}
+		if let Some(anisotropy) = child("Anisotropy")
+		{
+			subsurface = subsurface.with_anisotropy(Scene::parse_xml_f64(&anisotropy));
+		}
+		subsurface
+	}
+}
+
+impl Material for Subsurface
+{
+	fn bsdf(&self, _r: &Ray, rec: &HitRecord, _lambda: &mut SampledWavelengths) -> Option<Bsdf>
+	{
+		let eta = if rec.front_face { self.ior } else { 1.0 / self.ior };
+		let distribution = TrowbridgeReitz::from_roughness(self.roughness, self.roughness);
+		Some(Bsdf::new(rec.normal, rec.shading_tangent(), Box::new(DielectricBxdf::new(eta, distribution, Vec3d::one()))))
+	}
+
+	fn medium(&self, rec: &HitRecord) -> Option<Arc::<dyn Medium>>
+	{
+		let albedo = self.albedo.value(rec.uv, rec.p).for_each(&|a| f64::clamp(a, 0.0, 1.0));
+		Some(Arc::new(HomogeneousMedium::from_mean_free_path(self.mean_free_path, albedo).with_phase(Arc::new(HenyeyGreenstein::new(self.anisotropy)))))
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::bsdf::BsdfSample;
+	use crate::math::vec::Vec2d;
+	use crate::media::sample_interaction;
+
+	fn skin() -> Arc::<dyn Material>
+	{
+		Arc::new(Subsurface::new(Arc::new(SolidColor::new(Vec3d::new(0.9, 0.6, 1.5))), Vec3d::new(0.5, 0.2, 0.1)))
+	}
+
+	/* hit on the plane z = 0 at 60 degrees from the normal, from outside or from inside */
+	fn hit(outside: bool) -> (Ray, HitRecord)
+	{
+		let dir = Vec3d::new(f64::sin(f64::to_radians(60.0)), 0.0, -0.5);
+		let dir = if outside { dir } else { -dir };
+		let r = Ray::new(-dir, dir);
+		let rec = HitRecord::new(&r, Vec3d::zero(), 1.0, Vec2d::zero(), Vec3d::new(0.0, 0.0, 1.0), skin());
+		(r, rec)
+	}
+
+	fn sample(outside: bool) -> BsdfSample
+	{
+		let (r, rec) = hit(outside);
+		let bsdf = skin().bsdf(&r, &rec, &mut SampledWavelengths::sample_uniform(0.5, false)).unwrap();
+		bsdf.sample(-r.dir(), 0.99, Vec2d::new(0.5, 0.5)).unwrap()
+	}
+
+	#[test]
+	fn test_boundary_refracts_into_the_interior()
+	{
+		let sample = sample(true);
+		assert!(sample.is_transmission());
+		let sin_t = f64::sqrt(sample.wi.x() * sample.wi.x() + sample.wi.y() * sample.wi.y());
+		assert!((sin_t - f64::sin(f64::to_radians(60.0)) / 1.33).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_boundary_reflects_beyond_the_critical_angle_inside()
+	{
+		assert!(!sample(false).is_transmission());
+	}
+
+	#[test]
+	fn test_interior_medium()
+	{
+		/* the albedo is clamped to one and the mean free path sets the extinction */
+		let (_, rec) = hit(true);
+		let medium = skin().medium(&rec).unwrap();
+		let sigma_t = medium.sigma_a(rec.p) + medium.sigma_s(rec.p);
+		assert!(Vec3d::length(sigma_t - Vec3d::new(2.0, 5.0, 10.0)) < 1e-9);
+		assert!(Vec3d::length(medium.sigma_s(rec.p) / sigma_t - Vec3d::new(0.9, 0.6, 1.0)) < 1e-9);
+	}
+
+	#[test]
+	fn test_free_flights_average_the_mean_free_path()
+	{
+		let (_, rec) = hit(true);
+		let medium = Subsurface::new(Arc::new(SolidColor::new(Vec3d::newv(0.8))), Vec3d::newv(0.25)).medium(&rec).unwrap();
+		let r = Ray::new(Vec3d::zero(), Vec3d::new(0.0, 0.0, -1.0));
+		let lambda = SampledWavelengths::sample_uniform(0.5, false);
+		let mut rng = rand::thread_rng();
+		let n = 20000;
+		let mean = (0..n).map(|_| -sample_interaction::<Vec3d>(medium.as_ref(), &r, 1e6, &lambda, &mut rng).scatter.unwrap().z()).sum::<f64>() / n as f64;
+		assert!((mean - 0.25).abs() < 0.01, "{}", mean);
+	}
+}
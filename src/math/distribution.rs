@@ -0,0 +1,210 @@
+use super::vec::Vec2d;
+
+pub struct Distribution1D
+{
+	func: Vec::<f64>,
+	cdf: Vec::<f64>,
+	func_int: f64,
+}
+
+impl Distribution1D
+{
+	pub fn new(func: Vec::<f64>) -> Self
+	{
+		let n = func.len();
+		let mut cdf = vec![0.0; n + 1];
+		for i in 1..n + 1
+		{
+			cdf[i] = cdf[i - 1] + func[i - 1] / n as f64;
+		}
+
+		let func_int = cdf[n];
+		for (i, c) in cdf.iter_mut().enumerate()
+		{
+			if func_int > 0.0
+			{
+				*c /= func_int;
+			}
+			else
+			{
+				*c = i as f64 / n as f64;
+			}
+		}
+
+		Self{func, cdf, func_int}
+	}
+
+	pub fn count(&self) -> usize
+	{
+		self.func.len()
+	}
+
+	pub fn integral(&self) -> f64
+	{
+		self.func_int
+	}
+
+	fn find_interval(&self, u: f64) -> usize
+	{
+		let idx = self.cdf.partition_point(|c| *c <= u);
+		usize::clamp(idx, 1, self.cdf.len() - 1) - 1
+	}
+
+	pub fn sample_continuous(&self, u: f64) -> (f64, f64, usize)
+	{
+		let offset = self.find_interval(u);
+		let mut du = u - self.cdf[offset];
+		if self.cdf[offset + 1] - self.cdf[offset] > 0.0
+		{
+			du /= self.cdf[offset + 1] - self.cdf[offset];
+		}
+
+		let pdf = self.continuous_pdf(offset);
+		((offset as f64 + du) / self.count() as f64, pdf, offset)
+	}
+
+	pub fn continuous_pdf(&self, offset: usize) -> f64
+	{
+		if self.func_int > 0.0
+		{
+			self.func[offset] / self.func_int
+		}
+		else
+		{
+			1.0
+		}
+	}
+}
+
+pub struct Distribution2D
+{
+	conditional: Vec::<Distribution1D>,
+	marginal: Distribution1D,
+}
+
+impl Distribution2D
+{
+	pub fn new(func: &[f64], nu: usize, nv: usize) -> Self
+	{
+		let conditional: Vec::<Distribution1D> = (0..nv).map(|v| Distribution1D::new(func[v * nu..(v + 1) * nu].to_vec())).collect();
+		let marginal = Distribution1D::new(conditional.iter().map(|c| c.integral()).collect());
+		Self{conditional, marginal}
+	}
+
+	pub fn integral(&self) -> f64
+	{
+		self.marginal.integral()
+	}
+
+	pub fn sample_continuous(&self, u: Vec2d) -> (Vec2d, f64)
+	{
+		let (d1, pdf1, v) = self.marginal.sample_continuous(u.y());
+		let (d0, pdf0, _) = self.conditional[v].sample_continuous(u.x());
+		(Vec2d::new(d0, d1), pdf0 * pdf1)
+	}
+
+	pub fn pdf(&self, p: Vec2d) -> f64
+	{
+		let nu = self.conditional[0].count();
+		let nv = self.marginal.count();
+		let iu = usize::min((f64::max(p.x(), 0.0) * nu as f64) as usize, nu - 1);
+		let iv = usize::min((f64::max(p.y(), 0.0) * nv as f64) as usize, nv - 1);
+		if self.marginal.integral() > 0.0
+		{
+			self.conditional[iv].func[iu] / self.marginal.integral()
+		}
+		else
+		{
+			1.0
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	const EPSILON: f64 = 1e-9;
+
+	fn func() -> Vec::<f64>
+	{
+		vec![0.5, 2.0, 0.0, 1.0, 3.5, 0.25]
+	}
+
+	/* cdf of the piecewise constant function at x */
+	fn cdf(dist: &Distribution1D, x: f64) -> f64
+	{
+		let n = dist.count();
+		let i = usize::min((x * n as f64) as usize, n - 1);
+		dist.cdf[i] + (x * n as f64 - i as f64) * (dist.cdf[i + 1] - dist.cdf[i])
+	}
+
+	#[test]
+	fn test_1d_pdf_integrates_to_one()
+	{
+		let dist = Distribution1D::new(func());
+		let integral: f64 = (0..dist.count()).map(|i| dist.continuous_pdf(i) / dist.count() as f64).sum();
+		assert!((integral - 1.0).abs() < EPSILON);
+	}
+
+	#[test]
+	fn test_1d_sample_inverts_cdf()
+	{
+		let dist = Distribution1D::new(func());
+		for i in 0..100
+		{
+			let u = (i as f64 + 0.5) / 100.0;
+			let (x, pdf, offset) = dist.sample_continuous(u);
+			assert!((cdf(&dist, x) - u).abs() < EPSILON);
+			assert!((pdf - dist.continuous_pdf(offset)).abs() < EPSILON);
+			assert!(pdf > 0.0);
+		}
+	}
+
+	#[test]
+	fn test_1d_zero_function_is_uniform()
+	{
+		let dist = Distribution1D::new(vec![0.0; 4]);
+		let (x, pdf, _) = dist.sample_continuous(0.3);
+		assert!((x - 0.3).abs() < EPSILON);
+		assert_eq!(pdf, 1.0);
+	}
+
+	#[test]
+	fn test_2d_pdf_integrates_to_one()
+	{
+		let (nu, nv) = (3, 2);
+		let dist = Distribution2D::new(&func(), nu, nv);
+		let mut integral = 0.0;
+		for v in 0..nv
+		{
+			for u in 0..nu
+			{
+				let p = Vec2d::new((u as f64 + 0.5) / nu as f64, (v as f64 + 0.5) / nv as f64);
+				integral += dist.pdf(p) / (nu * nv) as f64;
+			}
+		}
+		assert!((integral - 1.0).abs() < EPSILON);
+	}
+
+	#[test]
+	fn test_2d_sample_inverts_cdf()
+	{
+		let (nu, nv) = (3, 2);
+		let dist = Distribution2D::new(&func(), nu, nv);
+		for i in 0..20
+		{
+			for j in 0..20
+			{
+				let u = Vec2d::new((i as f64 + 0.5) / 20.0, (j as f64 + 0.5) / 20.0);
+				let (p, pdf) = dist.sample_continuous(u);
+				let row = usize::min((p.y() * nv as f64) as usize, nv - 1);
+				assert!((cdf(&dist.marginal, p.y()) - u.y()).abs() < EPSILON);
+				assert!((cdf(&dist.conditional[row], p.x()) - u.x()).abs() < EPSILON);
+				assert!((pdf - dist.pdf(p)).abs() < EPSILON);
+			}
+		}
+	}
+}
@@ -1,2 +1,3 @@
 pub mod aabb;
+pub mod distribution;
 pub mod vec;
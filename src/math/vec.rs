@@ -159,6 +159,11 @@ impl Vec3d
 		Self{v: [x, y, z]}
 	}
 
+	pub fn luminance(self) -> f64
+	{
+		0.2126 * self.x() + 0.7152 * self.y() + 0.0722 * self.z()
+	}
+
 	pub fn near_zero(self) -> bool
 	{
 		self.v.iter().any(|a| f64::abs(*a) < f64::EPSILON)
@@ -0,0 +1,79 @@
+use crate::math::vec::Vec3d;
+use crate::phase_functions::
+{
+	PhaseFunction,
+	isotropic::IsotropicPhase,
+};
+
+use std::sync::Arc;
+
+use super::Medium;
+
+pub struct HomogeneousMedium
+{
+	sigma_a: Vec3d,
+	sigma_s: Vec3d,
+	emission: Vec3d,
+	phase: Arc::<dyn PhaseFunction>,
+}
+
+impl HomogeneousMedium
+{
+	pub fn new(sigma_a: Vec3d, sigma_s: Vec3d) -> Self
+	{
+		Self{sigma_a, sigma_s, emission: Vec3d::zero(), phase: Arc::new(IsotropicPhase::new())}
+	}
+
+	/* radiance emitted by the absorbing particles, glowing gas needs a non zero sigma_a */
+	pub fn with_emission(mut self, emission: Vec3d) -> Self
+	{
+		self.emission = emission;
+		self
+	}
+
+	pub fn with_phase(mut self, phase: Arc::<dyn PhaseFunction>) -> Self
+	{
+		self.phase = phase;
+		self
+	}
+
+	/* mean distance between interactions and probability of scattering rather than absorbing */
+	pub fn from_mean_free_path(mean_free_path: Vec3d, albedo: Vec3d) -> Self
+	{
+		let sigma_t = mean_free_path.for_each(&|d| if d > 0.0 { 1.0 / d } else { 0.0 });
+		Self::new(sigma_t * (Vec3d::one() - albedo), sigma_t * albedo)
+	}
+}
+
+impl Medium for HomogeneousMedium
+{
+	fn sigma_a(&self, _p: Vec3d) -> Vec3d
+	{
+		self.sigma_a
+	}
+
+	fn sigma_s(&self, _p: Vec3d) -> Vec3d
+	{
+		self.sigma_s
+	}
+
+	fn majorant(&self) -> Vec3d
+	{
+		self.sigma_a + self.sigma_s
+	}
+
+	fn phase(&self) -> &dyn PhaseFunction
+	{
+		self.phase.as_ref()
+	}
+
+	fn emission(&self, _p: Vec3d) -> Vec3d
+	{
+		self.emission
+	}
+
+	fn is_homogeneous(&self) -> bool
+	{
+		true
+	}
+}
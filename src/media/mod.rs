@@ -0,0 +1,419 @@
+pub mod homogeneous_medium;
+pub mod textured_medium;
+
+use crate::color::PathColor;
+use crate::hittable::HitRecord;
+use crate::math::vec::Vec3d;
+use crate::phase_functions::PhaseFunction;
+use crate::ray::Ray;
+use crate::spectrum::SampledWavelengths;
+
+use rand::Rng;
+
+use std::sync::Arc;
+
+/* participating medium filling the interior of a closed surface */
+pub trait Medium: Send + Sync
+{
+	/* absorption coefficient per unit of distance */
+	fn sigma_a(&self, p: Vec3d) -> Vec3d;
+	/* scattering coefficient per unit of distance */
+	fn sigma_s(&self, p: Vec3d) -> Vec3d;
+	/* upper bound of sigma_a + sigma_s over the whole medium */
+	fn majorant(&self) -> Vec3d;
+	/* directional distribution at scattering events */
+	fn phase(&self) -> &dyn PhaseFunction;
+
+	/* radiance emitted by the absorbing particles, the medium adds sigma_a * emission per unit of distance */
+	fn emission(&self, _p: Vec3d) -> Vec3d
+	{
+		Vec3d::zero()
+	}
+
+	/* coefficients are the same everywhere, so free flights can be sampled analytically */
+	fn is_homogeneous(&self) -> bool
+	{
+		false
+	}
+}
+
+/*
+ * media a path is nested in, the innermost last, on top of the global medium around everything
+ * a surface without a medium, e.g. glass inside fog, is entered as an empty medium
+ */
+#[derive(Clone)]
+pub struct MediumStack
+{
+	outer: Option<Arc::<dyn Medium>>,
+	extent: f64,
+	stack: Vec::<Option<Arc::<dyn Medium>>>,
+}
+
+impl MediumStack
+{
+	/* the global medium comes with the distance rays leaving the scene travel through it */
+	pub fn new(outer: Option<(Arc::<dyn Medium>, f64)>) -> Self
+	{
+		match outer
+		{
+			None => Self{outer: None, extent: 0.0, stack: Vec::new()},
+			Some((medium, extent)) => Self{outer: Some(medium), extent, stack: Vec::new()},
+		}
+	}
+
+	/* ray parameter where the current medium ends, at the surface hit or at the end of the global medium */
+	pub fn tmax(&self, r: &Ray, hit: Option<f64>) -> f64
+	{
+		match hit
+		{
+			Some(t) => t,
+			None if self.stack.is_empty() => self.extent / Vec3d::length(r.dir()),
+			None => f64::INFINITY,
+		}
+	}
+
+	pub fn current(&self) -> Option<Arc::<dyn Medium>>
+	{
+		match self.stack.last()
+		{
+			None => self.outer.clone(),
+			Some(medium) => medium.clone(),
+		}
+	}
+
+	/* crossing the surface of rec, entering through its front face and leaving through its back face */
+	pub fn cross(&mut self, rec: &HitRecord)
+	{
+		if rec.front_face
+		{
+			self.stack.push(rec.material.medium(rec));
+		}
+		else
+		{
+			self.stack.pop();
+		}
+	}
+}
+
+pub struct MediumInteraction<C: PathColor>
+{
+	/* position of the scattering event, None if the ray reached tmax */
+	pub scatter: Option<Vec3d>,
+	/* transmittance, times the scattering coefficient at a scattering event, over the pdf */
+	pub weight: C,
+	/* radiance emitted along the ray before the event, already divided by its pdf */
+	pub emitted: C,
+}
+
+/* free flight along r up to tmax, the ray direction need not be normalized */
+pub fn sample_interaction<C: PathColor>(medium: &dyn Medium, r: &Ray, tmax: f64, lambda: &SampledWavelengths, rng: &mut impl Rng) -> MediumInteraction<C>
+{
+	/* only a ray leaking out of a closed medium gets here, it would never leave it */
+	if tmax.is_infinite()
+	{
+		return MediumInteraction{scatter: None, weight: C::one(), emitted: C::zero()};
+	}
+
+	if medium.is_homogeneous()
+	{
+		sample_homogeneous(medium, r, tmax, lambda, rng)
+	}
+	else
+	{
+		sample_tracking(medium, r, tmax, lambda, rng)
+	}
+}
+
+/*
+ * the distance is sampled on a random channel and weighted by the average pdf over all channels
+ * so chromatic coefficients stay unbiased
+ */
+fn sample_homogeneous<C: PathColor>(medium: &dyn Medium, r: &Ray, tmax: f64, lambda: &SampledWavelengths, rng: &mut impl Rng) -> MediumInteraction<C>
+{
+	let sigma_a = C::from_rgb(medium.sigma_a(r.orig()), lambda);
+	let sigma_s = C::from_rgb(medium.sigma_s(r.orig()), lambda);
+	let mut sigma_t = sigma_a;
+	sigma_t += sigma_s;
+
+	let length = Vec3d::length(r.dir());
+	let max_distance = tmax * length;
+	let channel = usize::min((rng.gen_range(0.0..1.0) * C::channels() as f64) as usize, C::channels() - 1);
+	let sigma = sigma_t.channel(channel);
+	let distance = if sigma > 0.0 { -f64::ln(1.0 - rng.gen_range(0.0..1.0)) / sigma } else { f64::INFINITY };
+
+	if distance < max_distance
+	{
+		let p = r.orig() + r.dir() * (distance / length);
+		let transmittance = (sigma_t * -distance).map(&f64::exp);
+		let pdf = (sigma_t * transmittance).average();
+		if pdf <= 0.0
+		{
+			return MediumInteraction{scatter: Some(p), weight: C::zero(), emitted: C::zero()};
+		}
+
+		let emitted = sigma_a * C::from_rgb(medium.emission(p), lambda) * transmittance / pdf;
+		return MediumInteraction{scatter: Some(p), weight: sigma_s * transmittance / pdf, emitted};
+	}
+
+	let transmittance = (sigma_t * -max_distance).map(&f64::exp);
+	let pdf = transmittance.average();
+	let weight = if pdf > 0.0 { transmittance / pdf } else { C::zero() };
+	MediumInteraction{scatter: None, weight, emitted: C::zero()}
+}
+
+fn max_channel<C: PathColor>(c: C) -> f64
+{
+	(0..C::channels()).fold(0.0, |max, i| f64::max(max, c.channel(i)))
+}
+
+/*
+ * spectral tracking of Kutz et al., tentative collisions are sampled with the largest majorant channel
+ * and are either real scattering or null collisions with probabilities following the path weight
+ * emission is gathered at every tentative collision
+ */
+fn sample_tracking<C: PathColor>(medium: &dyn Medium, r: &Ray, tmax: f64, lambda: &SampledWavelengths, rng: &mut impl Rng) -> MediumInteraction<C>
+{
+	let majorant = max_channel(C::from_rgb(medium.majorant(), lambda));
+	let length = Vec3d::length(r.dir());
+	let max_distance = tmax * length;
+	let dir = r.dir() / length;
+
+	let mut weight = C::one();
+	let mut emitted = C::zero();
+	if majorant <= 0.0
+	{
+		return MediumInteraction{scatter: None, weight, emitted};
+	}
+
+	let mut distance = 0.0;
+	loop
+	{
+		distance -= f64::ln(1.0 - rng.gen_range(0.0..1.0)) / majorant;
+		if distance >= max_distance
+		{
+			return MediumInteraction{scatter: None, weight, emitted};
+		}
+
+		let p = r.orig() + dir * distance;
+		let sigma_a = C::from_rgb(medium.sigma_a(p), lambda);
+		let sigma_s = C::from_rgb(medium.sigma_s(p), lambda);
+		let sigma_n = (sigma_a + sigma_s).map(&|sigma_t| majorant - sigma_t);
+		emitted += weight * sigma_a * C::from_rgb(medium.emission(p), lambda) / majorant;
+
+		let p_scatter = (weight * sigma_s).average();
+		let p_null = (weight * sigma_n).map(&f64::abs).average();
+		if p_scatter + p_null <= 0.0
+		{
+			return MediumInteraction{scatter: None, weight: C::zero(), emitted};
+		}
+
+		if rng.gen_range(0.0..1.0) * (p_scatter + p_null) < p_scatter
+		{
+			weight = weight * sigma_s * ((p_scatter + p_null) / (majorant * p_scatter));
+			return MediumInteraction{scatter: Some(p), weight, emitted};
+		}
+
+		weight = weight * sigma_n * ((p_scatter + p_null) / (majorant * p_null));
+	}
+}
+
+/* transmittance along r up to tmax, ratio tracking for heterogeneous media */
+pub fn transmittance<C: PathColor>(medium: &dyn Medium, r: &Ray, tmax: f64, lambda: &SampledWavelengths, rng: &mut impl Rng) -> C
+{
+	/* leak guard, see sample_interaction */
+	if tmax.is_infinite()
+	{
+		return C::one();
+	}
+
+	let length = Vec3d::length(r.dir());
+	let max_distance = tmax * length;
+	if medium.is_homogeneous()
+	{
+		let sigma_t = C::from_rgb(medium.sigma_a(r.orig()) + medium.sigma_s(r.orig()), lambda);
+		return (sigma_t * -max_distance).map(&f64::exp);
+	}
+
+	let majorant = max_channel(C::from_rgb(medium.majorant(), lambda));
+	let dir = r.dir() / length;
+	let mut transmittance = C::one();
+	if majorant <= 0.0
+	{
+		return transmittance;
+	}
+
+	let mut distance = 0.0;
+	loop
+	{
+		distance -= f64::ln(1.0 - rng.gen_range(0.0..1.0)) / majorant;
+		if distance >= max_distance
+		{
+			return transmittance;
+		}
+
+		let p = r.orig() + dir * distance;
+		let sigma_t = C::from_rgb(medium.sigma_a(p) + medium.sigma_s(p), lambda);
+		transmittance *= sigma_t.map(&|sigma_t| 1.0 - sigma_t / majorant);
+
+		/* russian roulette once the estimate gets small */
+		let max = max_channel(transmittance.map(&f64::abs));
+		if max < 0.05
+		{
+			if rng.gen_range(0.0..1.0) >= max * 10.0
+			{
+				return C::zero();
+			}
+			transmittance = transmittance / (max * 10.0);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::materials::medium_interface::MediumInterface;
+	use crate::math::vec::Vec2d;
+	use crate::textures::solid_color::SolidColor;
+
+	use homogeneous_medium::HomogeneousMedium;
+	use textured_medium::TexturedMedium;
+
+	const N: usize = 20000;
+
+	fn fog() -> Arc::<dyn Medium>
+	{
+		Arc::new(HomogeneousMedium::new(Vec3d::newv(0.1), Vec3d::newv(0.4)))
+	}
+
+	fn lambda() -> SampledWavelengths
+	{
+		SampledWavelengths::sample_uniform(0.5, false)
+	}
+
+	#[test]
+	fn test_escaping_rays_end_at_the_extent()
+	{
+		let media = MediumStack::new(Some((fog(), 4.0)));
+		let r = Ray::new(Vec3d::zero(), Vec3d::new(0.0, 0.0, 2.0));
+		assert_eq!(media.tmax(&r, Some(1.5)), 1.5);
+		assert!((media.tmax(&r, None) - 2.0).abs() < 1e-12);
+
+		let t: Vec3d = transmittance(fog().as_ref(), &r, media.tmax(&r, None), &lambda(), &mut rand::thread_rng());
+		assert!((t.x() - f64::exp(-0.5 * 4.0)).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_global_medium_scatters_escaping_rays()
+	{
+		let media = MediumStack::new(Some((fog(), 4.0)));
+		let r = Ray::new(Vec3d::zero(), Vec3d::new(1.0, 0.0, 0.0));
+		let mut rng = rand::thread_rng();
+		let scattered = (0..N).filter(|_| sample_interaction::<Vec3d>(fog().as_ref(), &r, media.tmax(&r, None), &lambda(), &mut rng).scatter.is_some()).count();
+		let expected = 1.0 - f64::exp(-0.5 * 4.0);
+		assert!((scattered as f64 / N as f64 - expected).abs() < 0.02);
+	}
+
+	#[test]
+	fn test_leaking_rays_are_not_attenuated()
+	{
+		let r = Ray::new(Vec3d::zero(), Vec3d::new(1.0, 0.0, 0.0));
+		let interaction: MediumInteraction<Vec3d> = sample_interaction(fog().as_ref(), &r, f64::INFINITY, &lambda(), &mut rand::thread_rng());
+		assert!(interaction.scatter.is_none());
+		assert_eq!(interaction.weight, Vec3d::one());
+		assert_eq!(transmittance::<Vec3d>(fog().as_ref(), &r, f64::INFINITY, &lambda(), &mut rand::thread_rng()), Vec3d::one());
+	}
+
+	#[test]
+	fn test_ratio_tracking_matches_beer_lambert()
+	{
+		/* half the majorant, so null collisions are tracked */
+		let medium = TexturedMedium::new(Arc::new(SolidColor::new(Vec3d::newv(0.5))), 1.0, Vec3d::newv(0.2), Vec3d::newv(0.6));
+		let r = Ray::new(Vec3d::zero(), Vec3d::new(1.0, 0.0, 0.0));
+		let mut rng = rand::thread_rng();
+		let mean = (0..N).map(|_| transmittance::<Vec3d>(&medium, &r, 3.0, &lambda(), &mut rng).x()).sum::<f64>() / N as f64;
+		assert!((mean - f64::exp(-0.4 * 3.0)).abs() < 0.01);
+	}
+
+	#[test]
+	fn test_tracking_weights_are_unbiased()
+	{
+		/* the no-scatter weights average to the transmittance and the scatter weights to the scattered fraction times the albedo */
+		let medium = TexturedMedium::new(Arc::new(SolidColor::new(Vec3d::newv(0.5))), 1.0, Vec3d::newv(0.2), Vec3d::newv(0.6));
+		let r = Ray::new(Vec3d::zero(), Vec3d::new(1.0, 0.0, 0.0));
+		let mut rng = rand::thread_rng();
+		let (mut escaped, mut scattered) = (0.0, 0.0);
+		for _ in 0..N
+		{
+			let interaction: MediumInteraction<Vec3d> = sample_interaction(&medium, &r, 3.0, &lambda(), &mut rng);
+			match interaction.scatter
+			{
+				None => escaped += interaction.weight.x(),
+				Some(_) => scattered += interaction.weight.x(),
+			}
+		}
+		let transmittance = f64::exp(-0.4 * 3.0);
+		assert!((escaped / N as f64 - transmittance).abs() < 0.01);
+		assert!((scattered / N as f64 - 0.3 / 0.4 * (1.0 - transmittance)).abs() < 0.01);
+	}
+
+	#[test]
+	fn test_emission_of_an_absorbing_medium()
+	{
+		/* with no scattering the emitted radiance is le * (1 - exp(-sigma_a * d)) on average */
+		let medium = HomogeneousMedium::new(Vec3d::new(0.5, 1.0, 2.0), Vec3d::zero()).with_emission(Vec3d::new(3.0, 2.0, 1.0));
+		let r = Ray::new(Vec3d::zero(), Vec3d::new(1.0, 0.0, 0.0));
+		let mut rng = rand::thread_rng();
+		let mut emitted = Vec3d::zero();
+		for _ in 0..N
+		{
+			let interaction: MediumInteraction<Vec3d> = sample_interaction(&medium, &r, 2.0, &lambda(), &mut rng);
+			emitted += interaction.emitted;
+		}
+		emitted /= N as f64;
+		let expected = Vec3d::new(3.0 * (1.0 - f64::exp(-1.0)), 2.0 * (1.0 - f64::exp(-2.0)), 1.0 - f64::exp(-4.0));
+		assert!(Vec3d::length(emitted - expected) < 0.03, "{:?} {:?}", emitted, expected);
+	}
+
+	#[test]
+	fn test_chromatic_free_flights_are_unbiased()
+	{
+		/* the transmittance estimate of every channel averages to its own exponential */
+		let medium = HomogeneousMedium::new(Vec3d::new(0.1, 0.5, 1.5), Vec3d::new(0.3, 0.2, 0.1));
+		let r = Ray::new(Vec3d::zero(), Vec3d::new(1.0, 0.0, 0.0));
+		let mut rng = rand::thread_rng();
+		let mut escaped = Vec3d::zero();
+		for _ in 0..N
+		{
+			let interaction: MediumInteraction<Vec3d> = sample_interaction(&medium, &r, 1.5, &lambda(), &mut rng);
+			if interaction.scatter.is_none()
+			{
+				escaped += interaction.weight;
+			}
+		}
+		escaped /= N as f64;
+		let expected = Vec3d::new(f64::exp(-0.4 * 1.5), f64::exp(-0.7 * 1.5), f64::exp(-1.6 * 1.5));
+		assert!(Vec3d::length(escaped - expected) < 0.02, "{:?} {:?}", escaped, expected);
+	}
+
+	#[test]
+	fn test_medium_stack_nests_interfaces()
+	{
+		let inner: Arc::<dyn Medium> = Arc::new(HomogeneousMedium::new(Vec3d::one(), Vec3d::zero()));
+		let interface = Arc::new(MediumInterface::new(inner.clone()));
+		let entering = Ray::new(Vec3d::new(0.0, 0.0, 1.0), Vec3d::new(0.0, 0.0, -1.0));
+		let leaving = Ray::new(Vec3d::new(0.0, 0.0, -1.0), Vec3d::new(0.0, 0.0, -1.0));
+		let normal = Vec3d::new(0.0, 0.0, 1.0);
+
+		let mut media = MediumStack::new(Some((fog(), 10.0)));
+		media.cross(&HitRecord::new(&entering, Vec3d::zero(), 1.0, Vec2d::zero(), normal, interface.clone()));
+		assert!(Arc::ptr_eq(&media.current().unwrap(), &inner));
+		/* rays escaping from inside a closed medium are leaking */
+		assert_eq!(media.tmax(&entering, None), f64::INFINITY);
+
+		media.cross(&HitRecord::new(&leaving, Vec3d::zero(), 1.0, Vec2d::zero(), -normal, interface));
+		assert!(!Arc::ptr_eq(&media.current().unwrap(), &inner));
+		assert_eq!(media.tmax(&entering, None), 10.0);
+	}
+}
@@ -0,0 +1,87 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+use crate::phase_functions::
+{
+	PhaseFunction,
+	isotropic::IsotropicPhase,
+};
+use crate::textures::Texture;
+
+use std::sync::Arc;
+
+use super::Medium;
+
+/*
+ * coefficients scaled by a density read from the first channel of a texture, e.g. a grid or a noise
+ * max_density must bound the density everywhere inside the medium
+ */
+pub struct TexturedMedium
+{
+	density: Arc::<dyn Texture>,
+	max_density: f64,
+	sigma_a: Vec3d,
+	sigma_s: Vec3d,
+	emission: Option<Arc::<dyn Texture>>,
+	phase: Arc::<dyn PhaseFunction>,
+}
+
+impl TexturedMedium
+{
+	pub fn new(density: Arc::<dyn Texture>, max_density: f64, sigma_a: Vec3d, sigma_s: Vec3d) -> Self
+	{
+		Self{density, max_density, sigma_a, sigma_s, emission: None, phase: Arc::new(IsotropicPhase::new())}
+	}
+
+	/* emitted radiance at each point, e.g. a temperature grid mapped to blackbody colors for fire */
+	pub fn with_emission(mut self, emission: Arc::<dyn Texture>) -> Self
+	{
+		self.emission = Some(emission);
+		self
+	}
+
+	pub fn with_phase(mut self, phase: Arc::<dyn PhaseFunction>) -> Self
+	{
+		self.phase = phase;
+		self
+	}
+
+	fn density(&self, p: Vec3d) -> f64
+	{
+		f64::max(self.density.value(Vec2d::zero(), p).x(), 0.0)
+	}
+}
+
+impl Medium for TexturedMedium
+{
+	fn sigma_a(&self, p: Vec3d) -> Vec3d
+	{
+		self.sigma_a * self.density(p)
+	}
+
+	fn sigma_s(&self, p: Vec3d) -> Vec3d
+	{
+		self.sigma_s * self.density(p)
+	}
+
+	fn majorant(&self) -> Vec3d
+	{
+		(self.sigma_a + self.sigma_s) * self.max_density
+	}
+
+	fn phase(&self) -> &dyn PhaseFunction
+	{
+		self.phase.as_ref()
+	}
+
+	fn emission(&self, p: Vec3d) -> Vec3d
+	{
+		match &self.emission
+		{
+			None => Vec3d::zero(),
+			Some(emission) => emission.value(Vec2d::zero(), p),
+		}
+	}
+}
@@ -17,6 +17,7 @@ use crate::math::
 		Vec3d,
 	}
 };
+use crate::phase_functions::PhaseFunction;
 use crate::ray::Ray;
 use crate::scene::Scene;
 use crate::textures::Texture;
@@ -29,6 +30,7 @@ pub struct ConstantMedium
 {
 	boundary: Arc::<dyn Hittable>,
 	phase_function: Arc::<dyn Material>,
+	albedo: Arc::<dyn Texture>,
 	neg_inv_density: f64,
 }
 
@@ -36,7 +38,27 @@ impl ConstantMedium
 {
 	pub fn new(b: Arc::<dyn Hittable>, d: f64, a: Arc::<dyn Texture>) -> Self
 	{
-		Self{boundary: b, phase_function: Arc::new(Isotropic::new(a)), neg_inv_density: -1.0 / d}
+		Self{boundary: b, phase_function: Arc::new(Isotropic::new(a.clone())), albedo: a, neg_inv_density: -1.0 / d}
+	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let albedo = scene.parse_xml_color(&child("Albedo").expect("no Albedo node found for ConstantMedium"));
+		let medium = Self::new(scene.parse_xml_inner_object(node), Scene::parse_xml_attr_f64(node, "density".to_string()), albedo);
+		match child("Phase")
+		{
+			None => medium,
+			Some(phase) => medium.with_phase(Scene::parse_xml_phase(&phase)),
+		}
+	}
+
+	/* scatters with the given phase function instead of uniformly */
+	pub fn with_phase(mut self, phase: Arc::<dyn PhaseFunction>) -> Self
+	{
+		self.phase_function = Arc::new(Isotropic::new(self.albedo.clone()).with_phase(phase));
+		self
 	}
 }
 
@@ -72,7 +94,7 @@ impl Hittable for ConstantMedium
 
 				let ray_length = Vec3d::length(r.dir());
 				let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
-				let hit_distance = self.neg_inv_density * f64::log(rng.gen_range(0.0..1.0), 2.0);
+				let hit_distance = self.neg_inv_density * f64::ln(rng.gen_range(0.0..1.0));
 
 				if hit_distance > distance_inside_boundary
 				{
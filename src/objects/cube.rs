@@ -15,6 +15,7 @@ use crate::scene::Scene;
 
 use std::sync::Arc;
 
+use super::flip_face::FlipFace;
 use super::rect::
 {
 	XYRect,
@@ -31,22 +32,32 @@ pub struct Cube
 
 impl Cube
 {
+	/* the faces at p0 are flipped so every face points outwards */
 	pub fn new(p0: Vec3d, p1: Vec3d, material: Arc::<dyn Material>) -> Self
 	{
 		let mut objects: Vec::<Arc::<dyn Hittable>> = Vec::new();
 
 		objects.push(Arc::new(XYRect::new(p0.x(), p1.x(), p0.y(), p1.y(), p1.z(), material.clone())));
-		objects.push(Arc::new(XYRect::new(p0.x(), p1.x(), p0.y(), p1.y(), p0.z(), material.clone())));
+		objects.push(Arc::new(FlipFace::new(Arc::new(XYRect::new(p0.x(), p1.x(), p0.y(), p1.y(), p0.z(), material.clone())))));
 
 		objects.push(Arc::new(XZRect::new(p0.x(), p1.x(), p0.z(), p1.z(), p1.y(), material.clone())));
-		objects.push(Arc::new(XZRect::new(p0.x(), p1.x(), p0.z(), p1.z(), p0.y(), material.clone())));
+		objects.push(Arc::new(FlipFace::new(Arc::new(XZRect::new(p0.x(), p1.x(), p0.z(), p1.z(), p0.y(), material.clone())))));
 
 		objects.push(Arc::new(YZRect::new(p0.y(), p1.y(), p0.z(), p1.z(), p1.x(), material.clone())));
-		objects.push(Arc::new(YZRect::new(p0.y(), p1.y(), p0.z(), p1.z(), p0.x(), material.clone())));
+		objects.push(Arc::new(FlipFace::new(Arc::new(YZRect::new(p0.y(), p1.y(), p0.z(), p1.z(), p0.x(), material.clone())))));
 
 		let bvh = BvhNode::new(objects, 0.0, 0.0);
 		Self{p0, p1, bvh}
 	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let p0 = Scene::parse_xml_vec3d(&child("P0").expect("no P0 node found for Cube"));
+		let p1 = Scene::parse_xml_vec3d(&child("P1").expect("no P1 node found for Cube"));
+		Self::new(p0, p1, scene.material(Scene::parse_xml_attr_str(node, "material".to_string())))
+	}
 }
 
 impl Hittable for Cube
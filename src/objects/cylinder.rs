@@ -32,6 +32,15 @@ impl Cylinder
 		Cylinder{center, radius, material}
 	}
 
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let center = Scene::parse_xml_vec3d(&child("Center").expect("no Center node found for Cylinder"));
+		let radius = Scene::parse_xml_f64(&child("Radius").expect("no Radius node found for Cylinder"));
+		Self::new(center, radius, scene.material(Scene::parse_xml_attr_str(node, "material".to_string())))
+	}
+
 	pub fn get_uv(&self, p: &Vec3d) -> Vec2d
 	{
 		let theta = f64::acos(0.0);
@@ -57,21 +66,27 @@ impl Hittable for Cylinder
 			return None;
 		}
 
+		/* the far root is still a candidate when the near one is cut out */
 		let sqrt_d = f64::sqrt(d);
-		let mut t = (-half_b - sqrt_d) / a;
-		if t < tmin || t > tmax
+		for t in [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a]
 		{
-			t = (-half_b + sqrt_d) / a;
 			if t < tmin || t > tmax
 			{
-				return None;
+				continue;
+			}
+
+			let p = r.at(t);
+			let mut normal = (p - self.center) / self.radius;
+			normal = Vec3d::new(normal.x(), 0.0, normal.z());
+			let dpdu = Vec3d::new(normal.z(), 0.0, -normal.x()) * (2.0 * std::f64::consts::PI * self.radius);
+			let rec = HitRecord::new(r, p, t, self.get_uv(&normal), normal, self.material.clone()).with_tangents(dpdu, Vec3d::new(0.0, 1.0, 0.0));
+			if !rec.is_cut_out(r)
+			{
+				return Some(rec);
 			}
 		}
 
-		let p = r.at(t);
-		let mut normal = (p - self.center) / self.radius;
-		normal = Vec3d::new(normal.x(), 0.0, normal.z());
-		Some(HitRecord::new(r, p, t, self.get_uv(&normal), normal, self.material.clone()))
+		None
 	}
 
 	fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb>
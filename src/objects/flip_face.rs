@@ -3,6 +3,7 @@ use crate::hittable::
 	HitRecord,
 	Hittable,
 };
+use crate::light_bvh::LightBounds;
 use crate::math::
 {
 	aabb::Aabb,
@@ -24,6 +25,11 @@ impl FlipFace
 	{
 		Self{obj}
 	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		Self::new(scene.parse_xml_inner_object(node))
+	}
 }
 
 impl Hittable for FlipFace
@@ -48,4 +54,21 @@ impl Hittable for FlipFace
 	{
 		self.obj.bvh_depth(&r, tmin, tmax)
 	}
+
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
+	{
+		self.obj.pdf_value(o, v, time)
+	}
+
+	fn random(&self, o: Vec3d, time: f64) -> Vec3d
+	{
+		self.obj.random(o, time)
+	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		let mut bounds = self.obj.light_bounds()?;
+		bounds.w = -bounds.w;
+		Some(bounds)
+	}
 }
@@ -11,3 +11,4 @@ pub mod sphere;
 pub mod stl;
 pub mod translate;
 pub mod triangle;
+pub mod visibility;
@@ -3,6 +3,7 @@ use crate::hittable::
 	HitRecord,
 	Hittable,
 };
+use crate::light_bvh::LightBounds;
 use crate::materials::Material;
 use crate::math::
 {
@@ -13,11 +14,17 @@ use crate::math::
 		Vec3d,
 	}
 };
-use crate::ray::Ray;
+use crate::ray::
+{
+	Ray,
+	RayKind,
+};
 use crate::scene::Scene;
 
 use std::sync::Arc;
 
+use super::sphere::Sphere;
+
 pub struct MovingSphere
 {
 	center0: Vec3d,
@@ -35,6 +42,17 @@ impl MovingSphere
 		MovingSphere{center0, center1, time0, time1, radius, material}
 	}
 
+	/* the sphere moves from Center0 to Center1 over the time range 0 to 1 */
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let center0 = Scene::parse_xml_vec3d(&child("Center0").expect("no Center0 node found for MovingSphere"));
+		let center1 = Scene::parse_xml_vec3d(&child("Center1").expect("no Center1 node found for MovingSphere"));
+		let radius = Scene::parse_xml_f64(&child("Radius").expect("no Radius node found for MovingSphere"));
+		Self::new(center0, center1, 0.0, 1.0, radius, scene.material(Scene::parse_xml_attr_str(node, "material".to_string())))
+	}
+
 	pub fn get_uv(&self, p: &Vec3d) -> Vec2d
 	{
 		let theta = f64::acos(-p.y());
@@ -62,20 +80,26 @@ impl Hittable for MovingSphere
 			return None;
 		}
 
+		/* the far root is still a candidate when the near one is cut out */
 		let sqrt_d = f64::sqrt(d);
-		let mut t = (-half_b - sqrt_d) / a;
-		if t < tmin || t > tmax
+		for t in [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a]
 		{
-			t = (-half_b + sqrt_d) / a;
 			if t < tmin || t > tmax
 			{
-				return None;
+				continue;
+			}
+
+			let p = r.at(t);
+			let normal = (p - self.center(r.time())) / self.radius;
+			let (dpdu, dpdv) = Sphere::get_tangents(&normal, self.radius);
+			let rec = HitRecord::new(r, p, t, self.get_uv(&normal), normal, self.material.clone()).with_tangents(dpdu, dpdv);
+			if !rec.is_cut_out(r)
+			{
+				return Some(rec);
 			}
 		}
 
-		let p = r.at(t);
-		let normal = (p - self.center(r.time())) / self.radius;
-		Some(HitRecord::new(r, p, t, self.get_uv(&normal), normal, self.material.clone()))
+		None
 	}
 
 	fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb>
@@ -86,4 +110,25 @@ impl Hittable for MovingSphere
 		                     self.center(time1) + Vec3d::newv(self.radius));
 		Some(Aabb::surrounding_box(&box0, &box1))
 	}
+
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
+	{
+		if self.hit(&Ray::with_kind(o, v, time, RayKind::Shadow), 0.001, f64::INFINITY).is_none()
+		{
+			return 0.0;
+		}
+
+		Sphere::solid_angle_pdf(self.center(time), self.radius, o)
+	}
+
+	fn random(&self, o: Vec3d, time: f64) -> Vec3d
+	{
+		Sphere::random_to_sphere(self.center(time), self.radius, o)
+	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		let center = (self.center(self.time0) + self.center(self.time1)) * 0.5;
+		Sphere::sphere_light_bounds(center, self.radius, self.bounding_box(self.time0, self.time1)?, &self.material)
+	}
 }
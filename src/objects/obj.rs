@@ -17,15 +17,14 @@ use crate::materials::
 {
 	Material,
 	lambertian::Lambertian,
+	mtl,
 };
 use crate::ray::Ray;
 use crate::scene::Scene;
-use crate::textures::
-{
-	Texture,
-	solid_color::SolidColor,
-};
+use crate::textures::solid_color::SolidColor;
 
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
 use super::triangle::Triangle;
@@ -47,12 +46,20 @@ impl Obj
 		let res = obj::parse(content);
 		if let Result::Ok(wf) = &res
 		{
+			let materials = match &wf.material_library
+			{
+				None => HashMap::new(),
+				Some(library) => mtl::load(&Path::new(filename).with_file_name(library)),
+			};
+
 			let mut objects: Vec::<Arc::<dyn Hittable>> = Vec::new();
 			for obj in &wf.objects
 			{
 				let mut geometries: Vec::<Arc::<dyn Hittable>> = Vec::new();
 				for geometry in &obj.geometry
 				{
+					/* faces without a known material stay white */
+					let mat = geometry.material_name.as_ref().and_then(|name| materials.get(name)).unwrap_or(&mat);
 					let mut shapes: Vec::<Arc::<dyn Hittable>> = Vec::new();
 					for shape in &geometry.shapes
 					{
@@ -92,6 +99,15 @@ impl Obj
 		panic!("failed to parse {}: {:?}", filename, res);
 	}
 
+	pub fn from(_scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let origin = child("Origin").map_or(Vec3d::zero(), |origin| Scene::parse_xml_vec3d(&origin));
+		let scale = child("Scale").map_or(Vec3d::one(), |scale| Scene::parse_xml_vec3d(&scale));
+		Self::new(&Scene::parse_xml_attr_str(node, "filename".to_string()), origin, scale)
+	}
+
 	fn get_vertices(obj: &obj::Object, i: &obj::VTNIndex, j: &obj::VTNIndex, k: &obj::VTNIndex, origin: Vec3d, scale: Vec3d) -> [Vec3d; 3]
 	{
 		let vi = obj.vertices[i.0];
@@ -160,3 +176,71 @@ impl Hittable for Obj
 		self.bvh.bvh_depth(r, tmin, tmax)
 	}
 }
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::ray::RayKind;
+
+	use image::
+	{
+		Rgb,
+		RgbImage,
+	};
+
+	/* a unit quad in the plane z = 0 with a single material next to an opacity map, black on the left half */
+	fn quad(name: &str, material: &str) -> Obj
+	{
+		let directory = std::env::temp_dir().join(format!("raytracer_obj_{}", name));
+		std::fs::create_dir_all(&directory).unwrap();
+		RgbImage::from_fn(2, 1, |x, _| if x == 0 { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) }).save(directory.join("opacity.png")).unwrap();
+
+		std::fs::write(directory.join("quad.mtl"), format!("newmtl quad\nNs 10\nKa 0 0 0\nKd 0.5 0.5 0.5\nKs 0 0 0\n{}\n", material)).unwrap();
+		std::fs::write(directory.join("quad.obj"), "mtllib quad.mtl\no quad\nv -1 -1 0\nv 1 -1 0\nv 1 1 0\nv -1 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nusemtl quad\nf 1/1 2/2 3/3\nf 1/1 3/3 4/4\n").unwrap();
+		Obj::new(&directory.join("quad.obj").to_string_lossy(), Vec3d::zero(), Vec3d::one())
+	}
+
+	/* fraction of rays of a kind hitting the quad, on the left and on the right half, off the diagonal edge */
+	fn coverage(obj: &Obj, kind: RayKind) -> (f64, f64)
+	{
+		let n = 50;
+		let mut hits = (0, 0);
+		for i in 0..n
+		{
+			for j in 0..n
+			{
+				let x = 0.98 * (i as f64 + 0.5) / n as f64;
+				let y = 1.98 * (j as f64 + 0.5) / n as f64 - 0.985;
+				hits.0 += obj.hit(&Ray::with_kind(Vec3d::new(-x, y, 1.0), Vec3d::new(0.0, 0.0, -1.0), 0.0, kind), 0.001, f64::INFINITY).is_some() as u32;
+				hits.1 += obj.hit(&Ray::with_kind(Vec3d::new(x, y, 1.0), Vec3d::new(0.0, 0.0, -1.0), 0.0, kind), 0.001, f64::INFINITY).is_some() as u32;
+			}
+		}
+		(hits.0 as f64 / (n * n) as f64, hits.1 as f64 / (n * n) as f64)
+	}
+
+	#[test]
+	fn test_mtl_dissolve()
+	{
+		let opaque = quad("opaque", "d 1\nillum 2");
+		let dissolved = quad("dissolved", "d 0.5\nillum 2");
+		for kind in [RayKind::Camera, RayKind::Shadow]
+		{
+			assert_eq!(coverage(&opaque, kind), (1.0, 1.0));
+
+			let (left, right) = coverage(&dissolved, kind);
+			assert!((left - 0.5).abs() < 0.1 && (right - 0.5).abs() < 0.1, "{} {}", left, right);
+		}
+	}
+
+	#[test]
+	fn test_mtl_dissolve_map()
+	{
+		let mapped = quad("mapped", "d 1\nillum 2\nmap_d opacity.png");
+		for kind in [RayKind::Camera, RayKind::Shadow]
+		{
+			assert_eq!(coverage(&mapped, kind), (0.0, 1.0));
+		}
+	}
+}
@@ -3,6 +3,7 @@ use crate::hittable::
 	HitRecord,
 	Hittable,
 };
+use crate::light_bvh::LightBounds;
 use crate::materials::Material;
 use crate::math::
 {
@@ -13,7 +14,11 @@ use crate::math::
 		Vec3d,
 	}
 };
-use crate::ray::Ray;
+use crate::ray::
+{
+	Ray,
+	RayKind,
+};
 use crate::scene::Scene;
 
 use rand::Rng;
@@ -36,6 +41,13 @@ impl XYRect
 	{
 		Self{x0, x1, y0, y1, k, material}
 	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let attr = |name: &str| Scene::parse_xml_attr_f64(node, name.to_string());
+
+		Self::new(attr("x0"), attr("x1"), attr("y0"), attr("y1"), attr("k"), scene.material(Scene::parse_xml_attr_str(node, "material".to_string())))
+	}
 }
 
 impl Hittable for XYRect
@@ -59,7 +71,13 @@ impl Hittable for XYRect
 		                    (y - self.y0) / (self.y1 - self.y0));
 		let norm = Vec3d::new(0.0, 0.0, 1.0);
 		let p = r.at(t);
-		Some(HitRecord::new(r, p, t, uv, norm, self.material.clone()))
+		let rec = HitRecord::new(r, p, t, uv, norm, self.material.clone()).with_tangents(Vec3d::new(self.x1 - self.x0, 0.0, 0.0), Vec3d::new(0.0, self.y1 - self.y0, 0.0));
+		if rec.is_cut_out(r)
+		{
+			return None;
+		}
+
+		Some(rec)
 	}
 
 	fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb>
@@ -67,6 +85,39 @@ impl Hittable for XYRect
 		Some(Aabb::new(Vec3d::new(self.x0, self.y0, self.k - std::f64::EPSILON),
 		               Vec3d::new(self.x1, self.y1, self.k + std::f64::EPSILON)))
 	}
+
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
+	{
+		if let Some(rec) = self.hit(&Ray::with_kind(o, v, time, RayKind::Shadow), 0.001, f64::INFINITY)
+		{
+			let area = (self.x1 - self.x0)  * (self.y1 - self.y0);
+			let distance_squared = rec.t * rec.t * Vec3d::dot(v, v);
+			let cosine = f64::abs(Vec3d::dot(v, rec.normal) / Vec3d::length(v));
+			return distance_squared / (cosine * area);
+		}
+
+		0.0
+	}
+
+	fn random(&self, o: Vec3d, _time: f64) -> Vec3d
+	{
+		let mut rng = rand::thread_rng();
+		let random_point = Vec3d::new(rng.gen_range(self.x0..self.x1), rng.gen_range(self.y0..self.y1), self.k);
+		random_point - o
+	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		let mut rng = rand::thread_rng();
+		let samples: Vec::<(Vec3d, Vec3d, Vec2d)> = (0..16).map(|_|
+		{
+			let uv = Vec2d::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0));
+			let p = Vec3d::new(self.x0 + uv.x() * (self.x1 - self.x0), self.y0 + uv.y() * (self.y1 - self.y0), self.k);
+			(p, Vec3d::new(0.0, 0.0, 1.0), uv)
+		}).collect();
+		let area = (self.x1 - self.x0) * (self.y1 - self.y0);
+		LightBounds::from_surface(self.bounding_box(0.0, 0.0)?, area, Vec3d::new(0.0, 0.0, 1.0), &self.material, &samples)
+	}
 }
 
 pub struct XZRect
@@ -85,6 +136,13 @@ impl XZRect
 	{
 		Self{x0, x1, z0, z1, k, material}
 	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let attr = |name: &str| Scene::parse_xml_attr_f64(node, name.to_string());
+
+		Self::new(attr("x0"), attr("x1"), attr("z0"), attr("z1"), attr("k"), scene.material(Scene::parse_xml_attr_str(node, "material".to_string())))
+	}
 }
 
 impl Hittable for XZRect
@@ -108,7 +166,13 @@ impl Hittable for XZRect
 		                    (z - self.z0) / (self.z1 - self.z0));
 		let norm = Vec3d::new(0.0, 1.0, 0.0);
 		let p = r.at(t);
-		Some(HitRecord::new(r, p, t, uv, norm, self.material.clone()))
+		let rec = HitRecord::new(r, p, t, uv, norm, self.material.clone()).with_tangents(Vec3d::new(self.x1 - self.x0, 0.0, 0.0), Vec3d::new(0.0, 0.0, self.z1 - self.z0));
+		if rec.is_cut_out(r)
+		{
+			return None;
+		}
+
+		Some(rec)
 	}
 
 	fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb>
@@ -117,9 +181,9 @@ impl Hittable for XZRect
 		               Vec3d::new(self.x1, self.k + std::f64::EPSILON, self.z1)))
 	}
 
-	fn pdf_value(&self, o: Vec3d, v: Vec3d) -> f64
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
 	{
-		if let Some(rec) = self.hit(&Ray::new(o, v), 0.001, f64::INFINITY)
+		if let Some(rec) = self.hit(&Ray::with_kind(o, v, time, RayKind::Shadow), 0.001, f64::INFINITY)
 		{
 			let area = (self.x1 - self.x0)  * (self.z1 - self.z0);
 			let distance_squared = rec.t * rec.t * Vec3d::dot(v, v);
@@ -130,12 +194,25 @@ impl Hittable for XZRect
 		0.0
 	}
 
-	fn random(&self, o: Vec3d) -> Vec3d
+	fn random(&self, o: Vec3d, _time: f64) -> Vec3d
 	{
 		let mut rng = rand::thread_rng();
 		let random_point = Vec3d::new(rng.gen_range(self.x0..self.x1), self.k, rng.gen_range(self.z0..self.z1));
 		random_point - o
 	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		let mut rng = rand::thread_rng();
+		let samples: Vec::<(Vec3d, Vec3d, Vec2d)> = (0..16).map(|_|
+		{
+			let uv = Vec2d::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0));
+			let p = Vec3d::new(self.x0 + uv.x() * (self.x1 - self.x0), self.k, self.z0 + uv.y() * (self.z1 - self.z0));
+			(p, Vec3d::new(0.0, 1.0, 0.0), uv)
+		}).collect();
+		let area = (self.x1 - self.x0) * (self.z1 - self.z0);
+		LightBounds::from_surface(self.bounding_box(0.0, 0.0)?, area, Vec3d::new(0.0, 1.0, 0.0), &self.material, &samples)
+	}
 }
 
 pub struct YZRect
@@ -154,6 +231,13 @@ impl YZRect
 	{
 		Self{y0, y1, z0, z1, k, material}
 	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let attr = |name: &str| Scene::parse_xml_attr_f64(node, name.to_string());
+
+		Self::new(attr("y0"), attr("y1"), attr("z0"), attr("z1"), attr("k"), scene.material(Scene::parse_xml_attr_str(node, "material".to_string())))
+	}
 }
 
 impl Hittable for YZRect
@@ -177,7 +261,13 @@ impl Hittable for YZRect
 		                    (z - self.z0) / (self.z1 - self.z0));
 		let norm = Vec3d::new(1.0, 0.0, 0.0);
 		let p = r.at(t);
-		Some(HitRecord::new(r, p, t, uv, norm, self.material.clone()))
+		let rec = HitRecord::new(r, p, t, uv, norm, self.material.clone()).with_tangents(Vec3d::new(0.0, self.y1 - self.y0, 0.0), Vec3d::new(0.0, 0.0, self.z1 - self.z0));
+		if rec.is_cut_out(r)
+		{
+			return None;
+		}
+
+		Some(rec)
 	}
 
 	fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb>
@@ -185,4 +275,37 @@ impl Hittable for YZRect
 		Some(Aabb::new(Vec3d::new(self.k - std::f64::EPSILON, self.y0, self.z0),
 		               Vec3d::new(self.k + std::f64::EPSILON, self.y1, self.z1)))
 	}
+
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
+	{
+		if let Some(rec) = self.hit(&Ray::with_kind(o, v, time, RayKind::Shadow), 0.001, f64::INFINITY)
+		{
+			let area = (self.y1 - self.y0)  * (self.z1 - self.z0);
+			let distance_squared = rec.t * rec.t * Vec3d::dot(v, v);
+			let cosine = f64::abs(Vec3d::dot(v, rec.normal) / Vec3d::length(v));
+			return distance_squared / (cosine * area);
+		}
+
+		0.0
+	}
+
+	fn random(&self, o: Vec3d, _time: f64) -> Vec3d
+	{
+		let mut rng = rand::thread_rng();
+		let random_point = Vec3d::new(self.k, rng.gen_range(self.y0..self.y1), rng.gen_range(self.z0..self.z1));
+		random_point - o
+	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		let mut rng = rand::thread_rng();
+		let samples: Vec::<(Vec3d, Vec3d, Vec2d)> = (0..16).map(|_|
+		{
+			let uv = Vec2d::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0));
+			let p = Vec3d::new(self.k, self.y0 + uv.x() * (self.y1 - self.y0), self.z0 + uv.y() * (self.z1 - self.z0));
+			(p, Vec3d::new(1.0, 0.0, 0.0), uv)
+		}).collect();
+		let area = (self.y1 - self.y0) * (self.z1 - self.z0);
+		LightBounds::from_surface(self.bounding_box(0.0, 0.0)?, area, Vec3d::new(1.0, 0.0, 0.0), &self.material, &samples)
+	}
 }
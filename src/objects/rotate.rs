@@ -3,6 +3,7 @@ use crate::hittable::
 	HitRecord,
 	Hittable,
 };
+use crate::light_bvh::LightBounds;
 use crate::math::
 {
 	aabb::Aabb,
@@ -135,6 +136,11 @@ impl RotateY
 		Self{obj: obj.clone(), sin_theta, cos_theta, aabb}
 	}
 
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		Self::new(scene.parse_xml_inner_object(node), Scene::parse_xml_attr_f64(node, "angle".to_string()))
+	}
+
 	fn transform(c: f64, s: f64, v: Vec3d) -> Vec3d
 	{
 		Vec3d::new
@@ -162,7 +168,7 @@ impl Hittable for RotateY
 	{
 		let orig = Self::transform_inv(self.cos_theta, self.sin_theta, r.orig());
 		let dir = Self::transform_inv(self.cos_theta, self.sin_theta, r.dir());
-		let rotated_r = Ray::with_time(orig, dir, r.time());
+		let rotated_r = Ray::with_kind(orig, dir, r.time(), r.kind());
 
 		if let Some(mut rec) = self.obj.hit(&rotated_r, tmin, tmax)
 		{
@@ -170,6 +176,8 @@ impl Hittable for RotateY
 			let normal = Self::transform(self.cos_theta, self.sin_theta, rec.normal);
 
 			rec.p = p;
+			rec.dpdu = Self::transform(self.cos_theta, self.sin_theta, rec.dpdu);
+			rec.dpdv = Self::transform(self.cos_theta, self.sin_theta, rec.dpdv);
 			rec.set_face_normal(&rotated_r, normal);
 			return Some(rec);
 		}
@@ -186,7 +194,7 @@ impl Hittable for RotateY
 	{
 		let orig = Self::transform_inv(self.cos_theta, self.sin_theta, r.orig());
 		let dir = Self::transform_inv(self.cos_theta, self.sin_theta, r.dir());
-		let rotated_r = Ray::with_time(orig, dir, r.time());
+		let rotated_r = Ray::with_kind(orig, dir, r.time(), r.kind());
 
 		if let Some(rec) = self.obj.bvh_depth(&rotated_r, tmin, tmax)
 		{
@@ -195,4 +203,25 @@ impl Hittable for RotateY
 
 		None
 	}
+
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
+	{
+		let orig = Self::transform_inv(self.cos_theta, self.sin_theta, o);
+		let dir = Self::transform_inv(self.cos_theta, self.sin_theta, v);
+		self.obj.pdf_value(orig, dir, time)
+	}
+
+	fn random(&self, o: Vec3d, time: f64) -> Vec3d
+	{
+		let orig = Self::transform_inv(self.cos_theta, self.sin_theta, o);
+		Self::transform(self.cos_theta, self.sin_theta, self.obj.random(orig, time))
+	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		let mut bounds = self.obj.light_bounds()?;
+		bounds.aabb = self.aabb?;
+		bounds.w = Self::transform(self.cos_theta, self.sin_theta, bounds.w);
+		Some(bounds)
+	}
 }
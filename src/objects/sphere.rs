@@ -3,6 +3,7 @@ use crate::hittable::
 	HitRecord,
 	Hittable,
 };
+use crate::light_bvh::LightBounds;
 use crate::materials::Material;
 use crate::math::
 {
@@ -13,9 +14,16 @@ use crate::math::
 		Vec3d,
 	}
 };
-use crate::ray::Ray;
+use crate::onb::Onb;
+use crate::ray::
+{
+	Ray,
+	RayKind,
+};
 use crate::scene::Scene;
 
+use rand::Rng;
+
 use std::sync::Arc;
 
 pub struct Sphere
@@ -32,12 +40,85 @@ impl Sphere
 		Sphere{center, radius, material}
 	}
 
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let center = Scene::parse_xml_vec3d(&child("Center").expect("no Center node found for Sphere"));
+		let radius = Scene::parse_xml_f64(&child("Radius").expect("no Radius node found for Sphere"));
+		Self::new(center, radius, scene.material(Scene::parse_xml_attr_str(node, "material".to_string())))
+	}
+
 	pub fn get_uv(&self, p: &Vec3d) -> Vec2d
 	{
 		let theta = f64::acos(-p.y());
 		let phi = f64::atan2(-p.z(), p.x()) + std::f64::consts::PI;
 		Vec2d::new(phi / (2.0 * std::f64::consts::PI), theta / std::f64::consts::PI)
 	}
+
+	/* derivatives of the position along the uv of get_uv, zero at the poles */
+	pub fn get_tangents(n: &Vec3d, radius: f64) -> (Vec3d, Vec3d)
+	{
+		let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - n.y() * n.y()));
+		if sin_theta == 0.0
+		{
+			return (Vec3d::zero(), Vec3d::zero());
+		}
+
+		let dpdu = Vec3d::new(n.z(), 0.0, -n.x()) * (2.0 * std::f64::consts::PI * radius);
+		let dpdv = Vec3d::new(-n.x() * n.y() / sin_theta, sin_theta, -n.y() * n.z() / sin_theta) * (std::f64::consts::PI * radius);
+		(dpdu, dpdv)
+	}
+
+	pub fn solid_angle_pdf(center: Vec3d, radius: f64, o: Vec3d) -> f64
+	{
+		let distance_squared = Vec3d::dot(center - o, center - o);
+		if distance_squared <= radius * radius
+		{
+			return 1.0 / (4.0 * std::f64::consts::PI);
+		}
+
+		let cos_theta_max = f64::sqrt(1.0 - radius * radius / distance_squared);
+		1.0 / (2.0 * std::f64::consts::PI * (1.0 - cos_theta_max))
+	}
+
+	pub fn random_to_sphere(center: Vec3d, radius: f64, o: Vec3d) -> Vec3d
+	{
+		let direction = center - o;
+		let distance_squared = Vec3d::dot(direction, direction);
+		if distance_squared <= radius * radius
+		{
+			return Vec3d::random_unit_vector();
+		}
+
+		let mut rng = rand::thread_rng();
+		let r1 = rng.gen_range(0.0..1.0);
+		let r2 = rng.gen_range(0.0..1.0);
+		let z = 1.0 + r2 * (f64::sqrt(1.0 - radius * radius / distance_squared) - 1.0);
+		let phi = 2.0 * std::f64::consts::PI * r1;
+		let x = f64::cos(phi) * f64::sqrt(1.0 - z * z);
+		let y = f64::sin(phi) * f64::sqrt(1.0 - z * z);
+		Onb::from_w(direction).local(Vec3d::new(x, y, z))
+	}
+
+	pub fn sphere_light_bounds(center: Vec3d, radius: f64, aabb: Aabb, material: &Arc::<dyn Material>) -> Option<LightBounds>
+	{
+		let samples: Vec::<(Vec3d, Vec3d, Vec2d)> = (0..32).map(|_|
+		{
+			let n = Vec3d::random_unit_vector();
+			let theta = f64::acos(-n.y());
+			let phi = f64::atan2(-n.z(), n.x()) + std::f64::consts::PI;
+			(center + n * radius, n, Vec2d::new(phi / (2.0 * std::f64::consts::PI), theta / std::f64::consts::PI))
+		}).collect();
+		let (front, back) = LightBounds::estimate_radiance(material, &samples);
+		if front <= 0.0
+		{
+			return None;
+		}
+
+		let area = 4.0 * std::f64::consts::PI * radius * radius;
+		Some(LightBounds::new(aabb, front * area * std::f64::consts::PI, Vec3d::new(0.0, 1.0, 0.0), -1.0, 0.0, back > 0.0))
+	}
 }
 
 impl Hittable for Sphere
@@ -54,24 +135,50 @@ impl Hittable for Sphere
 			return None;
 		}
 
+		/* the far root is still a candidate when the near one is cut out */
 		let sqrt_d = f64::sqrt(d);
-		let mut t = (-half_b - sqrt_d) / a;
-		if t < tmin || t > tmax
+		for t in [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a]
 		{
-			t = (-half_b + sqrt_d) / a;
 			if t < tmin || t > tmax
 			{
-				return None;
+				continue;
+			}
+
+			let p = r.at(t);
+			let normal = (p - self.center) / self.radius;
+			let (dpdu, dpdv) = Sphere::get_tangents(&normal, self.radius);
+			let rec = HitRecord::new(r, p, t, self.get_uv(&normal), normal, self.material.clone()).with_tangents(dpdu, dpdv);
+			if !rec.is_cut_out(r)
+			{
+				return Some(rec);
 			}
 		}
 
-		let p = r.at(t);
-		let normal = (p - self.center) / self.radius;
-		Some(HitRecord::new(r, p, t, self.get_uv(&normal), normal, self.material.clone()))
+		None
 	}
 
 	fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb>
 	{
 		Some(Aabb::new(self.center - Vec3d::newv(self.radius), self.center + Vec3d::newv(self.radius)))
 	}
+
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
+	{
+		if self.hit(&Ray::with_kind(o, v, time, RayKind::Shadow), 0.001, f64::INFINITY).is_none()
+		{
+			return 0.0;
+		}
+
+		Self::solid_angle_pdf(self.center, self.radius, o)
+	}
+
+	fn random(&self, o: Vec3d, _time: f64) -> Vec3d
+	{
+		Self::random_to_sphere(self.center, self.radius, o)
+	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		Self::sphere_light_bounds(self.center, self.radius, self.bounding_box(0.0, 0.0)?, &self.material)
+	}
 }
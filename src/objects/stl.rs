@@ -57,6 +57,15 @@ impl Stl
 
 		panic!("can't open file {}", filename);
 	}
+
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let origin = child("Origin").map_or(Vec3d::zero(), |origin| Scene::parse_xml_vec3d(&origin));
+		let scale = child("Scale").map_or(Vec3d::one(), |scale| Scene::parse_xml_vec3d(&scale));
+		Self::new(&Scene::parse_xml_attr_str(node, "filename".to_string()), origin, scale, scene.material(Scene::parse_xml_attr_str(node, "material".to_string())))
+	}
 }
 
 impl Hittable for Stl
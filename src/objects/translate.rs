@@ -3,6 +3,7 @@ use crate::hittable::
 	HitRecord,
 	Hittable,
 };
+use crate::light_bvh::LightBounds;
 use crate::math::
 {
 	aabb::Aabb,
@@ -25,13 +26,19 @@ impl Translate
 	{
 		Self{offset, obj}
 	}
+
+	/* the offset is given by the x, y and z attributes */
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		Self::new(scene.parse_xml_inner_object(node), Scene::parse_xml_vec3d(node))
+	}
 }
 
 impl Hittable for Translate
 {
 	fn hit(&self, r: &Ray, tmin: f64, tmax: f64) -> Option<HitRecord>
 	{
-		let moved_r = Ray::with_time(r.orig() - self.offset, r.dir(), r.time());
+		let moved_r = Ray::with_kind(r.orig() - self.offset, r.dir(), r.time(), r.kind());
 		if let Some(mut rec) = self.obj.hit(&moved_r, tmin, tmax)
 		{
 			rec.p += self.offset;
@@ -55,7 +62,7 @@ impl Hittable for Translate
 
 	fn bvh_depth(&self, r: &Ray, tmin: f64, tmax: f64) -> Option<(f64, u32)>
 	{
-		let moved_r = Ray::with_time(r.orig() - self.offset, r.dir(), r.time());
+		let moved_r = Ray::with_kind(r.orig() - self.offset, r.dir(), r.time(), r.kind());
 		if let Some(rec) = self.obj.bvh_depth(&moved_r, tmin, tmax)
 		{
 			return Some(rec);
@@ -63,4 +70,21 @@ impl Hittable for Translate
 
 		None
 	}
+
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
+	{
+		self.obj.pdf_value(o - self.offset, v, time)
+	}
+
+	fn random(&self, o: Vec3d, time: f64) -> Vec3d
+	{
+		self.obj.random(o - self.offset, time)
+	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		let mut bounds = self.obj.light_bounds()?;
+		bounds.aabb = Aabb::new(bounds.aabb.min() + self.offset, bounds.aabb.max() + self.offset);
+		Some(bounds)
+	}
 }
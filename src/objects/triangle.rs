@@ -24,6 +24,8 @@ pub struct Triangle
 	e: [Vec3d; 2],
 	uv: [Vec2d; 3],
 	norm: [Vec3d; 3],
+	dpdu: Vec3d,
+	dpdv: Vec3d,
 	material: Arc::<dyn Material>,
 }
 
@@ -32,7 +34,26 @@ impl Triangle
 	pub fn new(p: [Vec3d; 3], uv: [Vec2d; 3], norm: [Vec3d; 3], material: Arc::<dyn Material>) -> Self
 	{
 		let e = [p[1] - p[0], p[2] - p[0]];
-		Self{p, e, uv, norm, material}
+		let (dpdu, dpdv) = Self::get_tangents(&p, &uv);
+		Self{p, e, uv, norm, dpdu, dpdv, material}
+	}
+
+	/* solve the edges as linear combinations of dpdu and dpdv, zero without usable texture coordinates */
+	fn get_tangents(p: &[Vec3d; 3], uv: &[Vec2d; 3]) -> (Vec3d, Vec3d)
+	{
+		let duv02 = uv[0] - uv[2];
+		let duv12 = uv[1] - uv[2];
+		let dp02 = p[0] - p[2];
+		let dp12 = p[1] - p[2];
+		let det = duv02.x() * duv12.y() - duv02.y() * duv12.x();
+		if f64::abs(det) < 1e-12
+		{
+			return (Vec3d::zero(), Vec3d::zero());
+		}
+
+		let dpdu = (dp02 * duv12.y() - dp12 * duv02.y()) / det;
+		let dpdv = (dp12 * duv02.x() - dp02 * duv12.x()) / det;
+		(dpdu, dpdv)
 	}
 
 	fn get_uv(&self, u: f64, v: f64) -> Vec2d
@@ -79,7 +100,13 @@ impl Hittable for Triangle
 		}
 
 		let p = r.at(t);
-		Some(HitRecord::new(r, p, t, self.get_uv(u, v), self.get_norm(u, v), self.material.clone()))
+		let rec = HitRecord::new(r, p, t, self.get_uv(u, v), self.get_norm(u, v), self.material.clone()).with_tangents(self.dpdu, self.dpdv);
+		if rec.is_cut_out(r)
+		{
+			return None;
+		}
+
+		Some(rec)
 	}
 
 	fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb>
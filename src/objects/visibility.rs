@@ -0,0 +1,229 @@
+use crate::hittable::
+{
+	HitRecord,
+	Hittable,
+};
+use crate::light_bvh::LightBounds;
+use crate::math::
+{
+	aabb::Aabb,
+	vec::Vec3d,
+};
+use crate::ray::
+{
+	Ray,
+	RayKind,
+};
+use crate::scene::Scene;
+
+use std::str::FromStr;
+
+use std::sync::Arc;
+
+pub const VISIBLE_CAMERA: u32     = 1 << 0;
+pub const VISIBLE_DIFFUSE: u32    = 1 << 1;
+pub const VISIBLE_REFLECTION: u32 = 1 << 2;
+pub const VISIBLE_REFRACTION: u32 = 1 << 3;
+/* emitters are only found by light sampling when visible to shadow rays */
+pub const VISIBLE_SHADOW: u32     = 1 << 4;
+pub const CASTS_SHADOWS: u32      = 1 << 5;
+pub const VISIBLE_ALL: u32        = VISIBLE_CAMERA | VISIBLE_DIFFUSE | VISIBLE_REFLECTION | VISIBLE_REFRACTION | VISIBLE_SHADOW | CASTS_SHADOWS;
+
+pub struct Visibility
+{
+	obj: Arc::<dyn Hittable>,
+	flags: u32,
+	light_links: u64,
+	light_group: u64,
+}
+
+impl Visibility
+{
+	pub fn new(obj: Arc::<dyn Hittable>) -> Self
+	{
+		Self{obj, flags: VISIBLE_ALL, light_links: !0, light_group: 1}
+	}
+
+	pub fn with_flags(mut self, flags: u32) -> Self
+	{
+		self.flags = flags;
+		self
+	}
+
+	/* bit mask of the light groups that illuminate this object */
+	pub fn with_light_links(mut self, light_links: u64) -> Self
+	{
+		self.light_links = light_links;
+		self
+	}
+
+	/* bit mask of the light groups this object emits as */
+	pub fn with_light_group(mut self, light_group: u64) -> Self
+	{
+		self.light_group = light_group;
+		self
+	}
+
+	/*
+	 * the camera, diffuse, reflection, refraction, shadow and casts_shadows attributes clear a flag
+	 * when false, light_links and light_group are bit masks
+	 */
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let mut flags = 0;
+		for (name, flag) in [("camera", VISIBLE_CAMERA), ("diffuse", VISIBLE_DIFFUSE), ("reflection", VISIBLE_REFLECTION), ("refraction", VISIBLE_REFRACTION), ("shadow", VISIBLE_SHADOW), ("casts_shadows", CASTS_SHADOWS)]
+		{
+			if node.attribute(name) != Some("false")
+			{
+				flags |= flag;
+			}
+		}
+
+		let mask = |name: &str, default: u64| node.attribute(name).map_or(default, |mask| u64::from_str(mask).expect("invalid light mask"));
+		Self::new(scene.parse_xml_inner_object(node)).with_flags(flags).with_light_links(mask("light_links", !0)).with_light_group(mask("light_group", 1))
+	}
+
+	fn visible(&self, flag: u32) -> bool
+	{
+		self.flags & flag != 0
+	}
+}
+
+impl Hittable for Visibility
+{
+	fn hit(&self, r: &Ray, tmin: f64, tmax: f64) -> Option<HitRecord>
+	{
+		let visible = match r.kind()
+		{
+			RayKind::Camera     => self.visible(VISIBLE_CAMERA),
+			RayKind::Diffuse    => self.visible(VISIBLE_DIFFUSE),
+			RayKind::Reflection => self.visible(VISIBLE_REFLECTION),
+			RayKind::Refraction => self.visible(VISIBLE_REFRACTION),
+			RayKind::Shadow     => self.visible(VISIBLE_SHADOW) || self.visible(CASTS_SHADOWS),
+		};
+
+		if !visible
+		{
+			return None;
+		}
+
+		let mut rec = self.obj.hit(r, tmin, tmax)?;
+		if r.kind() == RayKind::Shadow
+		{
+			let emitting = rec.material.emitted(r, &rec, rec.uv, rec.p) != Vec3d::zero();
+			if (emitting && !self.visible(VISIBLE_SHADOW)) || (!emitting && !self.visible(CASTS_SHADOWS))
+			{
+				return None;
+			}
+		}
+
+		rec.light_links = self.light_links;
+		rec.light_group = self.light_group;
+		Some(rec)
+	}
+
+	fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb>
+	{
+		self.obj.bounding_box(time0, time1)
+	}
+
+	fn bvh_depth(&self, r: &Ray, tmin: f64, tmax: f64) -> Option<(f64, u32)>
+	{
+		self.obj.bvh_depth(r, tmin, tmax)
+	}
+
+	/* light sampling must not pick an emitter that shadow rays cannot reach */
+	fn pdf_value(&self, o: Vec3d, v: Vec3d, time: f64) -> f64
+	{
+		if !self.visible(VISIBLE_SHADOW)
+		{
+			return 0.0;
+		}
+
+		self.obj.pdf_value(o, v, time)
+	}
+
+	fn random(&self, o: Vec3d, time: f64) -> Vec3d
+	{
+		self.obj.random(o, time)
+	}
+
+	fn light_bounds(&self) -> Option<LightBounds>
+	{
+		if !self.visible(VISIBLE_SHADOW)
+		{
+			return None;
+		}
+
+		self.obj.light_bounds()
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use crate::materials::
+	{
+		Material,
+		diffuse_light::DiffuseLight,
+		lambertian::Lambertian,
+	};
+	use crate::objects::sphere::Sphere;
+	use crate::textures::solid_color::SolidColor;
+
+	fn sphere(material: Arc::<dyn Material>) -> Arc::<dyn Hittable>
+	{
+		Arc::new(Sphere::new(Vec3d::zero(), 1.0, material))
+	}
+
+	fn diffuse() -> Arc::<dyn Material>
+	{
+		Arc::new(Lambertian::new(Arc::new(SolidColor::new(Vec3d::newv(0.5)))))
+	}
+
+	fn light() -> Arc::<dyn Material>
+	{
+		Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(Vec3d::one()))))
+	}
+
+	fn hits(object: &Visibility, kind: RayKind) -> bool
+	{
+		object.hit(&Ray::with_kind(Vec3d::new(0.0, 0.0, -5.0), Vec3d::new(0.0, 0.0, 1.0), 0.0, kind), 0.001, f64::INFINITY).is_some()
+	}
+
+	#[test]
+	fn test_ray_kinds()
+	{
+		let object = Visibility::new(sphere(diffuse())).with_flags(VISIBLE_ALL & !VISIBLE_CAMERA & !VISIBLE_REFRACTION);
+		assert!(!hits(&object, RayKind::Camera));
+		assert!(hits(&object, RayKind::Diffuse));
+		assert!(hits(&object, RayKind::Reflection));
+		assert!(!hits(&object, RayKind::Refraction));
+		assert!(hits(&object, RayKind::Shadow));
+	}
+
+	#[test]
+	fn test_shadow_rays()
+	{
+		/* an occluder that casts no shadow and a light that light sampling cannot see */
+		let occluder = Visibility::new(sphere(diffuse())).with_flags(VISIBLE_ALL & !CASTS_SHADOWS);
+		let emitter = Visibility::new(sphere(light())).with_flags(VISIBLE_ALL & !VISIBLE_SHADOW);
+		assert!(!hits(&occluder, RayKind::Shadow));
+		assert!(hits(&occluder, RayKind::Camera));
+		assert!(!hits(&emitter, RayKind::Shadow));
+		assert!(hits(&emitter, RayKind::Camera));
+		assert_eq!(emitter.pdf_value(Vec3d::new(0.0, 0.0, -5.0), Vec3d::new(0.0, 0.0, 1.0), 0.0), 0.0);
+		assert!(emitter.light_bounds().is_none());
+	}
+
+	#[test]
+	fn test_light_masks()
+	{
+		let object = Visibility::new(sphere(diffuse())).with_light_links(0b101).with_light_group(0b10);
+		let rec = object.hit(&Ray::new(Vec3d::new(0.0, 0.0, -5.0), Vec3d::new(0.0, 0.0, 1.0)), 0.001, f64::INFINITY).unwrap();
+		assert_eq!(rec.light_links, 0b101);
+		assert_eq!(rec.light_group, 0b10);
+	}
+}
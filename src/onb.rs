@@ -24,11 +24,32 @@ impl Onb
 		Self{axis: [au, av, aw]}
 	}
 
+	/* w along n and u along the part of t orthogonal to it, an arbitrary u when t is parallel to n */
+	pub fn from_wu(n: Vec3d, t: Vec3d) -> Self
+	{
+		let aw = Vec3d::normalize(n);
+		let t = t - aw * Vec3d::dot(aw, t);
+		let length = Vec3d::length(t);
+		if length <= 1e-12 || length.is_nan()
+		{
+			return Self::from_w(n);
+		}
+
+		let au = t / length;
+		let av = Vec3d::cross(aw, au);
+		Self{axis: [au, av, aw]}
+	}
+
 	pub fn local(&self, a: Vec3d) -> Vec3d
 	{
 		self.axis[0] * a.x() + self.axis[1] * a.y() + self.axis[2] * a.z()
 	}
 
+	pub fn to_local(&self, a: Vec3d) -> Vec3d
+	{
+		Vec3d::new(Vec3d::dot(a, self.axis[0]), Vec3d::dot(a, self.axis[1]), Vec3d::dot(a, self.axis[2]))
+	}
+
 	pub fn u(&self) -> Vec3d
 	{
 		self.axis[0]
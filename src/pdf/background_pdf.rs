@@ -0,0 +1,32 @@
+use crate::backgrounds::Background;
+use crate::math::vec::Vec3d;
+
+use std::sync::Arc;
+
+use super::Pdf;
+
+pub struct BackgroundPdf
+{
+	background: Arc::<dyn Background>,
+}
+
+impl BackgroundPdf
+{
+	pub fn new(background: Arc::<dyn Background>) -> Self
+	{
+		Self{background}
+	}
+}
+
+impl Pdf for BackgroundPdf
+{
+	fn value(&self, direction: Vec3d) -> f64
+	{
+		self.background.pdf_value(direction)
+	}
+
+	fn generate(&self) -> Vec3d
+	{
+		self.background.random()
+	}
+}
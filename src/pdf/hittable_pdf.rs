@@ -8,14 +8,15 @@ use super::Pdf;
 pub struct HittablePdf
 {
 	o: Vec3d,
+	time: f64,
 	obj: Arc::<dyn Hittable>,
 }
 
 impl HittablePdf
 {
-	pub fn new(obj: Arc::<dyn Hittable>, o: Vec3d) -> Self
+	pub fn new(obj: Arc::<dyn Hittable>, o: Vec3d, time: f64) -> Self
 	{
-		Self{o, obj}
+		Self{o, time, obj}
 	}
 }
 
@@ -23,11 +24,11 @@ impl Pdf for HittablePdf
 {
 	fn value(&self, direction: Vec3d) -> f64
 	{
-		self.obj.pdf_value(self.o, direction)
+		self.obj.pdf_value(self.o, direction, self.time)
 	}
 
 	fn generate(&self) -> Vec3d
 	{
-		self.obj.random(self.o)
+		self.obj.random(self.o, self.time)
 	}
 }
@@ -8,14 +8,14 @@ use super::Pdf;
 
 pub struct MixturePdf
 {
-	p: [Arc::<dyn Pdf>; 2],
+	p: Vec::<Arc::<dyn Pdf>>,
 }
 
 impl MixturePdf
 {
-	pub fn new(p0: Arc::<dyn Pdf>, p1: Arc::<dyn Pdf>) -> Self
+	pub fn new(p: Vec::<Arc::<dyn Pdf>>) -> Self
 	{
-		Self{p: [p0, p1]}
+		Self{p}
 	}
 }
 
@@ -23,19 +23,12 @@ impl Pdf for MixturePdf
 {
 	fn value(&self, dir: Vec3d) -> f64
 	{
-		0.5 * self.p[0].value(dir) + 0.5 * self.p[1].value(dir)
+		self.p.iter().map(|p| p.value(dir)).sum::<f64>() / self.p.len() as f64
 	}
 
 	fn generate(&self) -> Vec3d
 	{
 		let mut rng = rand::thread_rng();
-		if rng.gen_range(0.0..1.0) > 0.5
-		{
-			self.p[0].generate()
-		}
-		else
-		{
-			self.p[1].generate()
-		}
+		self.p[rng.gen_range(0..self.p.len())].generate()
 	}
 }
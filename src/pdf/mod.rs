@@ -1,4 +1,4 @@
-pub mod cosine_pdf;
+pub mod background_pdf;
 pub mod hittable_pdf;
 pub mod mixture_pdf;
 
@@ -0,0 +1,59 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use super::
+{
+	PhaseFunction,
+	PhaseSample,
+	cos_scattering,
+	henyey_greenstein::HenyeyGreenstein,
+	scattered_direction,
+};
+
+/* blend of two Henyey-Greenstein lobes, typically a strong forward lobe and a weaker backward one for clouds */
+pub struct DoubleHenyeyGreenstein
+{
+	g1: f64,
+	g2: f64,
+	weight: f64,
+}
+
+impl DoubleHenyeyGreenstein
+{
+	/* weight is the fraction of light scattered by the first lobe */
+	pub fn new(g1: f64, g2: f64, weight: f64) -> Self
+	{
+		Self{g1: f64::clamp(g1, -0.99, 0.99), g2: f64::clamp(g2, -0.99, 0.99), weight: f64::clamp(weight, 0.0, 1.0)}
+	}
+
+	fn value(&self, cos_theta: f64) -> f64
+	{
+		self.weight * HenyeyGreenstein::value(self.g1, cos_theta) + (1.0 - self.weight) * HenyeyGreenstein::value(self.g2, cos_theta)
+	}
+}
+
+impl PhaseFunction for DoubleHenyeyGreenstein
+{
+	fn p(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		self.value(cos_scattering(wo, wi))
+	}
+
+	fn sample(&self, wo: Vec3d, u: Vec2d) -> PhaseSample
+	{
+		let cos_theta = if u.x() < self.weight
+		{
+			HenyeyGreenstein::sample_cos_theta(self.g1, u.x() / self.weight)
+		}
+		else
+		{
+			HenyeyGreenstein::sample_cos_theta(self.g2, (u.x() - self.weight) / (1.0 - self.weight))
+		};
+
+		let p = self.value(cos_theta);
+		PhaseSample::new(scattered_direction(wo, cos_theta, u.y()), p, p)
+	}
+}
@@ -0,0 +1,61 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use std::f64::consts::PI;
+
+use super::
+{
+	PhaseFunction,
+	PhaseSample,
+	cos_scattering,
+	scattered_direction,
+};
+
+/* g is the mean cosine of the scattering angle, positive values scatter forward and negative ones backward */
+pub struct HenyeyGreenstein
+{
+	g: f64,
+}
+
+impl HenyeyGreenstein
+{
+	pub fn new(g: f64) -> Self
+	{
+		Self{g: f64::clamp(g, -0.99, 0.99)}
+	}
+
+	pub fn value(g: f64, cos_theta: f64) -> f64
+	{
+		let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+		(1.0 - g * g) / (4.0 * PI * denom * f64::sqrt(f64::max(denom, 0.0)))
+	}
+
+	pub fn sample_cos_theta(g: f64, u: f64) -> f64
+	{
+		if f64::abs(g) < 1e-3
+		{
+			return 1.0 - 2.0 * u;
+		}
+
+		let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+		f64::clamp((1.0 + g * g - s * s) / (2.0 * g), -1.0, 1.0)
+	}
+}
+
+impl PhaseFunction for HenyeyGreenstein
+{
+	fn p(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		Self::value(self.g, cos_scattering(wo, wi))
+	}
+
+	fn sample(&self, wo: Vec3d, u: Vec2d) -> PhaseSample
+	{
+		let cos_theta = Self::sample_cos_theta(self.g, u.x());
+		let p = Self::value(self.g, cos_theta);
+		PhaseSample::new(scattered_direction(wo, cos_theta, u.y()), p, p)
+	}
+}
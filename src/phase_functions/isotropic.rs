@@ -0,0 +1,39 @@
+use crate::bsdf::uniform_sphere;
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use std::f64::consts::PI;
+
+use super::
+{
+	PhaseFunction,
+	PhaseSample,
+};
+
+pub struct IsotropicPhase
+{
+}
+
+impl IsotropicPhase
+{
+	pub fn new() -> Self
+	{
+		Self{}
+	}
+}
+
+impl PhaseFunction for IsotropicPhase
+{
+	fn p(&self, _wo: Vec3d, _wi: Vec3d) -> f64
+	{
+		1.0 / (4.0 * PI)
+	}
+
+	fn sample(&self, _wo: Vec3d, u: Vec2d) -> PhaseSample
+	{
+		PhaseSample::new(uniform_sphere(u), 1.0 / (4.0 * PI), 1.0 / (4.0 * PI))
+	}
+}
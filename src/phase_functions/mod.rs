@@ -0,0 +1,124 @@
+pub mod double_henyey_greenstein;
+pub mod henyey_greenstein;
+pub mod isotropic;
+pub mod rayleigh;
+
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+use crate::onb::Onb;
+
+use std::f64::consts::PI;
+
+pub struct PhaseSample
+{
+	pub wi: Vec3d,
+	pub p: f64,
+	pub pdf: f64,
+}
+
+impl PhaseSample
+{
+	pub fn new(wi: Vec3d, p: f64, pdf: f64) -> Self
+	{
+		Self{wi, p, pdf}
+	}
+}
+
+/*
+ * angular distribution of light scattered in a medium, wo and wi both point away from the scattering point
+ * so forward scattering sends light arriving along -wo out along wi = -wo
+ */
+pub trait PhaseFunction: Send + Sync
+{
+	fn p(&self, wo: Vec3d, wi: Vec3d) -> f64;
+
+	fn sample(&self, wo: Vec3d, u: Vec2d) -> PhaseSample;
+
+	fn pdf(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		self.p(wo, wi)
+	}
+}
+
+/* cosine of the scattering angle, one for forward scattering */
+pub fn cos_scattering(wo: Vec3d, wi: Vec3d) -> f64
+{
+	-Vec3d::dot(Vec3d::normalize(wo), Vec3d::normalize(wi))
+}
+
+/* direction making an angle of acos(cos_theta) with the propagation direction -wo */
+pub fn scattered_direction(wo: Vec3d, cos_theta: f64, u: f64) -> Vec3d
+{
+	let sin_theta = f64::sqrt(f64::max(0.0, 1.0 - cos_theta * cos_theta));
+	let phi = 2.0 * PI * u;
+	Onb::from_w(-Vec3d::normalize(wo)).local(Vec3d::new(sin_theta * f64::cos(phi), sin_theta * f64::sin(phi), cos_theta))
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+	use super::
+	{
+		double_henyey_greenstein::DoubleHenyeyGreenstein,
+		henyey_greenstein::HenyeyGreenstein,
+		isotropic::IsotropicPhase,
+		rayleigh::Rayleigh,
+	};
+
+	const N: usize = 256;
+
+	fn wo() -> Vec3d
+	{
+		Vec3d::normalize(Vec3d::new(0.3, -0.5, 0.8))
+	}
+
+	/* phase functions with their mean cosine */
+	fn phase_functions() -> Vec::<(Box::<dyn PhaseFunction>, f64)>
+	{
+		vec!
+		[
+			(Box::new(IsotropicPhase::new()), 0.0),
+			(Box::new(HenyeyGreenstein::new(0.7)), 0.7),
+			(Box::new(HenyeyGreenstein::new(-0.3)), -0.3),
+			(Box::new(DoubleHenyeyGreenstein::new(0.8, -0.4, 0.75)), 0.75 * 0.8 - 0.25 * 0.4),
+			(Box::new(Rayleigh::new()), 0.0),
+		]
+	}
+
+	#[test]
+	fn test_pdf_integrates_to_one()
+	{
+		/* symmetric around the propagation direction, so a single integral over the cosine */
+		let n = 100000;
+		for (phase, _) in phase_functions()
+		{
+			let integral = (0..n).map(|i| phase.pdf(wo(), scattered_direction(wo(), -1.0 + 2.0 * (i as f64 + 0.5) / n as f64, 0.3))).sum::<f64>() * 4.0 * PI / n as f64;
+			assert!((integral - 1.0).abs() < 1e-3, "{}", integral);
+		}
+	}
+
+	#[test]
+	fn test_sample_matches_pdf()
+	{
+		for (phase, g) in phase_functions()
+		{
+			let mut mean_cos = 0.0;
+			for i in 0..N * N
+			{
+				let u = Vec2d::new(((i % N) as f64 + 0.5) / N as f64, ((i / N) as f64 + 0.5) / N as f64);
+				let sample = phase.sample(wo(), u);
+				assert!((Vec3d::length(sample.wi) - 1.0).abs() < 1e-9);
+				assert!((sample.pdf - phase.pdf(wo(), sample.wi)).abs() < 1e-6 * sample.pdf);
+				assert!((sample.p - phase.p(wo(), sample.wi)).abs() < 1e-6 * sample.p);
+				mean_cos += cos_scattering(wo(), sample.wi);
+			}
+			mean_cos /= (N * N) as f64;
+			assert!((mean_cos - g).abs() < 1e-3, "{} {}", mean_cos, g);
+		}
+	}
+}
@@ -0,0 +1,51 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use std::f64::consts::PI;
+
+use super::
+{
+	PhaseFunction,
+	PhaseSample,
+	cos_scattering,
+	scattered_direction,
+};
+
+/* scattering by particles much smaller than the wavelength, such as air molecules */
+pub struct Rayleigh
+{
+}
+
+impl Rayleigh
+{
+	pub fn new() -> Self
+	{
+		Self{}
+	}
+
+	fn value(cos_theta: f64) -> f64
+	{
+		3.0 / (16.0 * PI) * (1.0 + cos_theta * cos_theta)
+	}
+}
+
+impl PhaseFunction for Rayleigh
+{
+	fn p(&self, wo: Vec3d, wi: Vec3d) -> f64
+	{
+		Self::value(cos_scattering(wo, wi))
+	}
+
+	/* inverts the cdf (cos^3 + 3 cos + 4) / 8 with Cardano's formula */
+	fn sample(&self, wo: Vec3d, u: Vec2d) -> PhaseSample
+	{
+		let q = 4.0 * u.x() - 2.0;
+		let z = f64::cbrt(q + f64::sqrt(q * q + 1.0));
+		let cos_theta = f64::clamp(z - 1.0 / z, -1.0, 1.0);
+		let p = Self::value(cos_theta);
+		PhaseSample::new(scattered_direction(wo, cos_theta, u.y()), p, p)
+	}
+}
@@ -1,10 +1,22 @@
 use crate::math::vec::Vec3d;
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RayKind
+{
+	Camera,
+	Diffuse,
+	Reflection,
+	Refraction,
+	Shadow,
+}
+
+#[derive(Copy, Clone)]
 pub struct Ray
 {
 	orig: Vec3d,
 	dir: Vec3d,
 	tm: f64,
+	kind: RayKind,
 }
 
 impl Ray
@@ -16,7 +28,12 @@ impl Ray
 
 	pub fn with_time(orig: Vec3d, dir: Vec3d, tm: f64) -> Self
 	{
-		Self{orig, dir, tm}
+		Ray::with_kind(orig, dir, tm, RayKind::Camera)
+	}
+
+	pub fn with_kind(orig: Vec3d, dir: Vec3d, tm: f64, kind: RayKind) -> Self
+	{
+		Self{orig, dir, tm, kind}
 	}
 
 	pub fn at(&self, t: f64) -> Vec3d
@@ -38,4 +55,9 @@ impl Ray
 	{
 		self.tm
 	}
+
+	pub fn kind(&self) -> RayKind
+	{
+		self.kind
+	}
 }
@@ -1,24 +1,77 @@
+use crate::apertures::
+{
+	Aperture,
+	image_aperture::ImageAperture,
+	polygonal_aperture::PolygonalAperture,
+};
+use crate::backgrounds::
+{
+	Background,
+	physical_sky::PhysicalSky,
+	solid_background::SolidBackground,
+};
 use crate::bvh::BvhNode;
-use crate::camera::Camera;
+use crate::cameras::
+{
+	Camera,
+	cubemap_camera::CubemapCamera,
+	equirectangular_camera::EquirectangularCamera,
+	fisheye_camera::
+	{
+		FisheyeCamera,
+		FisheyeMapping,
+	},
+	ods_camera::OdsCamera,
+	orthographic_camera::OrthographicCamera,
+	perspective_camera::PerspectiveCamera,
+};
 use crate::hittable::
 {
 	Hittable,
 	HittableList
 };
+use crate::ies::IesProfile;
+use crate::light_bvh::LightBvh;
+use crate::lights::
+{
+	Light,
+	point_light::PointLight,
+	spot_light::SpotLight,
+};
 use crate::materials::
 {
 	Material,
+	bump_map::BumpMap,
+	cloth::Cloth,
+	coated::Coated,
+	conductor::Conductor,
+	cutout::Cutout,
 	dielectric::Dielectric,
-	diffuse_light::DiffuseLight,
+	diffuse_light::
+	{
+		DiffuseLight,
+		EmissionSides,
+	},
 	isotropic::Isotropic,
 	lambertian::Lambertian,
+	medium_interface::MediumInterface,
 	metal::Metal,
+	mix_material::MixMaterial,
+	normal_map::NormalMap,
+	principled::Principled,
+	subsurface::Subsurface,
 };
 use crate::math::vec::
 {
 	Vec2u,
 	Vec3d,
 };
+use crate::media::
+{
+	Medium,
+	homogeneous_medium::HomogeneousMedium,
+	textured_medium::TexturedMedium,
+};
 use crate::objects::
 {
 	cone::Cone,
@@ -42,13 +95,32 @@ use crate::objects::
 	stl::Stl,
 	translate::Translate,
 	triangle::Triangle,
+	visibility::Visibility,
+};
+use crate::phase_functions::
+{
+	PhaseFunction,
+	double_henyey_greenstein::DoubleHenyeyGreenstein,
+	henyey_greenstein::HenyeyGreenstein,
+	isotropic::IsotropicPhase,
+	rayleigh::Rayleigh,
+};
+use crate::spectrum::
+{
+	BlackbodySpectrum,
+	ConstantSpectrum,
+	SampledSpectrum,
+	Spectrum,
+	illuminant_a,
 };
 use crate::textures::
 {
 	Texture,
 	checker_texture::CheckerTexture,
+	grid_texture::GridTexture,
 	image_texture::ImageTexture,
 	noise_texture::NoiseTexture,
+	scale_texture::ScaleTexture,
 	solid_color::SolidColor,
 };
 
@@ -62,35 +134,101 @@ use std::str::FromStr;
 
 pub struct Scene
 {
-	camera: Camera,
+	camera: Arc::<dyn Camera>,
+	background: Arc::<dyn Background>,
 	textures: HashMap::<String, Arc::<dyn Texture>>,
 	materials: HashMap::<String, Arc::<dyn Material>>,
 	objects: Arc::<HittableList>,
-	lights: Arc::<HittableList>,
+	lights: Option<Arc::<dyn Hittable>>,
+	point_lights: Vec::<Arc::<dyn Light>>,
+	medium: Option<(Arc::<dyn Medium>, f64)>,
+	spectral: bool,
 }
 
 impl Scene
 {
-	pub fn new(camera: Camera) -> Self
+	pub fn new(camera: Arc::<dyn Camera>) -> Self
 	{
-		Self{camera, textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(HittableList::new()), lights: Arc::new(HittableList::new())}
+		Self{camera, background: Arc::new(SolidBackground::new(Vec3d::zero())), textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(HittableList::new()), lights: None, point_lights: Vec::new(), medium: None, spectral: false}
 	}
 
-	pub fn camera(&self) -> &Camera
+	pub fn camera(&self) -> &dyn Camera
 	{
-		&self.camera
+		self.camera.as_ref()
 	}
 	
+	pub fn background(&self) -> Arc::<dyn Background>
+	{
+		self.background.clone()
+	}
+
+	pub fn set_background(&mut self, background: Arc::<dyn Background>)
+	{
+		self.background = background;
+	}
+
 	pub fn objects(&self) -> Arc::<dyn Hittable>
 	{
 		self.objects.clone()
 	}
 
-	pub fn lights(&self) -> Arc::<dyn Hittable>
+	pub fn set_objects(&mut self, objects: Vec::<Arc::<dyn Hittable>>)
+	{
+		let mut world = HittableList::new();
+		world.push(Arc::new(BvhNode::new(objects, 0.0, 1.0)));
+		self.objects = Arc::new(world);
+	}
+
+	pub fn lights(&self) -> Option<Arc::<dyn Hittable>>
 	{
 		self.lights.clone()
 	}
 
+	pub fn set_lights(&mut self, lights: Vec::<Arc::<dyn Hittable>>)
+	{
+		let bvh = LightBvh::new(lights);
+		self.lights = if bvh.is_empty() { None } else { Some(Arc::new(bvh)) };
+	}
+
+	/* lights at a single position, which are not part of the objects */
+	pub fn point_lights(&self) -> &[Arc::<dyn Light>]
+	{
+		&self.point_lights
+	}
+
+	pub fn add_point_light(&mut self, light: Arc::<dyn Light>)
+	{
+		self.point_lights.push(light);
+	}
+
+	pub fn spectral(&self) -> bool
+	{
+		self.spectral
+	}
+
+	pub fn set_spectral(&mut self, spectral: bool)
+	{
+		self.spectral = spectral;
+	}
+
+	/* medium filling the space around every object and its extent, the camera sits inside it */
+	pub fn medium(&self) -> Option<(Arc::<dyn Medium>, f64)>
+	{
+		self.medium.clone()
+	}
+
+	/* rays leaving the scene go through extent of the medium before reaching the background */
+	pub fn set_medium(&mut self, medium: Arc::<dyn Medium>, extent: f64)
+	{
+		self.medium = Some((medium, extent));
+	}
+
+	/* diagonal of the box bounding every object, the default extent of the global medium */
+	pub fn extent(&self) -> f64
+	{
+		self.objects.bounding_box(0.0, 1.0).map_or(0.0, |bbox| Vec3d::length(bbox.max() - bbox.min()))
+	}
+
 	pub fn texture(&self, name: String) -> Arc::<dyn Texture>
 	{
 		match self.textures.get(&name)
@@ -119,7 +257,7 @@ impl Scene
 		self.materials.insert(name, material);
 	}
 
-	fn parse_xml_attr_f64(node: &roxmltree::Node, name: String) -> f64
+	pub fn parse_xml_attr_f64(node: &roxmltree::Node, name: String) -> f64
 	{
 		return f64::from_str(&Self::parse_xml_attr_str(node, name)).expect("invalid float value");
 	}
@@ -134,12 +272,29 @@ impl Scene
 		node.attribute(name.as_str()).expect("no Value attribute found").to_string()
 	}
 
-	fn parse_xml_f64(node: &roxmltree::Node) -> f64
+	pub fn parse_xml_f64(node: &roxmltree::Node) -> f64
 	{
 		return Self::parse_xml_attr_f64(node, "value".to_string());
 	}
 
-	fn parse_xml_vec3d(node: &roxmltree::Node) -> Vec3d
+	/* a node naming a texture, holding a gray value or the x, y and z of a color */
+	pub fn parse_xml_color(&self, node: &roxmltree::Node) -> Arc::<dyn Texture>
+	{
+		if let Some(texture) = node.attribute("texture")
+		{
+			self.texture(texture.to_string())
+		}
+		else if node.attribute("value").is_some()
+		{
+			Arc::new(SolidColor::new(Vec3d::newv(Self::parse_xml_f64(node))))
+		}
+		else
+		{
+			Arc::new(SolidColor::new(Self::parse_xml_vec3d(node)))
+		}
+	}
+
+	pub fn parse_xml_vec3d(node: &roxmltree::Node) -> Vec3d
 	{
 		Vec3d::new(Self::parse_xml_attr_f64(node, "x".to_string()), Self::parse_xml_attr_f64(node, "y".to_string()), Self::parse_xml_attr_f64(node, "z".to_string()))
 	}
@@ -149,15 +304,130 @@ impl Scene
 		Vec2u::new(Self::parse_xml_attr_u32(node, "x".to_string()), Self::parse_xml_attr_u32(node, "y".to_string()))
 	}
 
-	fn parse_xml_camera(node: &roxmltree::Node) -> Camera
+	fn parse_xml_camera(node: &roxmltree::Node) -> Arc::<dyn Camera>
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let position = Self::parse_xml_vec3d(&child("Position").expect("no Position node found for Camera"));
+		let direction = Self::parse_xml_vec3d(&child("Direction").expect("no Direction node found for Camera"));
+		let size = Self::parse_xml_vec2u(&child("Size").expect("no Size node found for Camera"));
+		let vup = Vec3d::new(0.0, 1.0, 0.0);
+		/* times the shutter opens and closes, for motion blur */
+		let (time0, time1) = child("Shutter").map_or((0.0, 0.0), |shutter| (Self::parse_xml_attr_f64(&shutter, "open".to_string()), Self::parse_xml_attr_f64(&shutter, "close".to_string())));
+		match node.attribute("type").unwrap_or("perspective")
+		{
+			"perspective" =>
+			{
+				let fov = Self::parse_xml_f64(&child("Fov").expect("no Fov node found for Camera"));
+				let aperture = Self::parse_xml_f64(&child("Aperture").expect("no Aperture node found for Camera"));
+				let focus_dist = Self::parse_xml_f64(&child("FocusDistance").expect("no FocusDistance node found for Camera"));
+				let mut camera = PerspectiveCamera::new(position, direction, vup, fov, size, aperture, focus_dist).with_shutter(time0, time1);
+				if let Some(shape) = child("ApertureShape")
+				{
+					let aperture: Arc::<dyn Aperture> = match shape.attribute("image")
+					{
+						Some(filename) => Arc::new(ImageAperture::new(filename)),
+						None =>
+						{
+							let rotation = shape.attribute("rotation").map_or(0.0, |_| Self::parse_xml_attr_f64(&shape, "rotation".to_string()));
+							Arc::new(PolygonalAperture::new(Self::parse_xml_attr_u32(&shape, "blades".to_string()), rotation))
+						},
+					};
+					camera = camera.with_aperture(aperture);
+				}
+				if let Some(cat_eye) = child("CatEye")
+				{
+					camera = camera.with_cat_eye(Self::parse_xml_f64(&cat_eye));
+				}
+				if let Some(squeeze) = child("AnamorphicSqueeze")
+				{
+					camera = camera.with_anamorphic_squeeze(Self::parse_xml_f64(&squeeze));
+				}
+				Arc::new(camera)
+			},
+			"orthographic" =>
+			{
+				let height = Self::parse_xml_f64(&child("Height").expect("no Height node found for orthographic Camera"));
+				Arc::new(OrthographicCamera::new(position, direction, vup, height, size).with_shutter(time0, time1))
+			},
+			"fisheye" =>
+			{
+				let fov = Self::parse_xml_f64(&child("Fov").expect("no Fov node found for Camera"));
+				let mapping = match child("Mapping").map_or("equisolid".to_string(), |mapping| Self::parse_xml_attr_str(&mapping, "value".to_string())).as_str()
+				{
+					"equidistant" => FisheyeMapping::Equidistant,
+					"equisolid"   => FisheyeMapping::Equisolid,
+					mapping       => panic!("unknown fisheye mapping: {}", mapping),
+				};
+				Arc::new(FisheyeCamera::new(position, direction, vup, fov, size, mapping).with_shutter(time0, time1))
+			},
+			"equirectangular" => Arc::new(EquirectangularCamera::new(position, direction, vup, size).with_shutter(time0, time1)),
+			"cubemap"         => Arc::new(CubemapCamera::new(position, direction, vup, size).with_shutter(time0, time1)),
+			"ods"             =>
+			{
+				/* interpupillary distance in scene units, the default is the average for meters */
+				let ipd = child("Ipd").map_or(0.064, |ipd| Self::parse_xml_f64(&ipd));
+				Arc::new(OdsCamera::new(position, direction, vup, ipd, size).with_shutter(time0, time1))
+			},
+			camera            => panic!("unknown camera: {}", camera),
+		}
+	}
+
+	/* g is the mean cosine of a Henyey-Greenstein lobe, weight the share of the first of two lobes */
+	pub fn parse_xml_phase(node: &roxmltree::Node) -> Arc::<dyn PhaseFunction>
+	{
+		let attr = |name: &str| Self::parse_xml_attr_f64(node, name.to_string());
+		match Self::parse_xml_attr_str(node, "type".to_string()).as_str()
+		{
+			"double_hg" => Arc::new(DoubleHenyeyGreenstein::new(attr("g1"), attr("g2"), attr("weight"))),
+			"hg"        => Arc::new(HenyeyGreenstein::new(attr("g"))),
+			"isotropic" => Arc::new(IsotropicPhase::new()),
+			"rayleigh"  => Arc::new(Rayleigh::new()),
+			phase       => panic!("unknown phase function: {}", phase),
+		}
+	}
+
+	/* SigmaA, SigmaS and Emission are colors, a Density node with a texture and its max turns the coefficients into a heterogeneous medium and the Emission node may name a texture too */
+	pub fn parse_xml_medium(&self, node: &roxmltree::Node) -> Arc::<dyn Medium>
 	{
-		let position = Self::parse_xml_vec3d(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Position").expect("no Position node found for Camera"));
-		let direction = Self::parse_xml_vec3d(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Direction").expect("no Direction node found for Camera"));
-		let fov = Self::parse_xml_f64(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Fov").expect("no Fov node found for Camera"));
-		let size = Self::parse_xml_vec2u(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Size").expect("no Size node found for Camera"));
-		let aperture = Self::parse_xml_f64(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Aperture").expect("no Aperture node found for Camera"));
-		let focus_dist = Self::parse_xml_f64(&node.children().find(|node| node.is_element() && node.tag_name().name() == "FocusDistance").expect("no FocusDistance node found for Camera"));
-		Camera::new(position, direction, Vec3d::new(0.0, 1.0, 0.0), fov, size, aperture, focus_dist)
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+		let color = |name: &str| child(name).map_or(Vec3d::zero(), |node| Self::parse_xml_vec3d(&node));
+
+		let sigma_a = color("SigmaA");
+		let sigma_s = color("SigmaS");
+		let phase = match child("Phase")
+		{
+			None => Arc::new(HenyeyGreenstein::new(child("Anisotropy").map_or(0.0, |node| Self::parse_xml_f64(&node)))),
+			Some(phase) => Self::parse_xml_phase(&phase),
+		};
+		match child("Density")
+		{
+			None => Arc::new(HomogeneousMedium::new(sigma_a, sigma_s).with_emission(color("Emission")).with_phase(phase)),
+			Some(density) =>
+			{
+				/* a grid bounds its own density, a texture needs the max */
+				let (texture, max_density): (Arc::<dyn Texture>, f64) = match density.attribute("grid")
+				{
+					Some(filename) =>
+					{
+						let grid = GridTexture::from_vol(filename);
+						let max_density = density.attribute("max").map_or(grid.max_value(), |_| Self::parse_xml_attr_f64(&density, "max".to_string()));
+						(Arc::new(grid), max_density)
+					},
+					None => (self.texture(Self::parse_xml_attr_str(&density, "texture".to_string())), Self::parse_xml_attr_f64(&density, "max".to_string())),
+				};
+				let mut medium = TexturedMedium::new(texture, max_density, sigma_a, sigma_s).with_phase(phase);
+				if let Some(emission) = child("Emission")
+				{
+					medium = match emission.attribute("texture")
+					{
+						Some(texture) => medium.with_emission(self.texture(texture.to_string())),
+						None => medium.with_emission(Arc::new(SolidColor::new(Self::parse_xml_vec3d(&emission)))),
+					};
+				}
+				Arc::new(medium)
+			}
+		}
 	}
 
 	fn parse_xml_texture(&mut self, node: &roxmltree::Node)
@@ -166,11 +436,11 @@ impl Scene
 		{
 			return match node.tag_name().name()
 			{
-				/*"CheckerTexture" => self.insert_texture(name.to_string(), Arc::new(CheckerTexture::from(&self, node))),
-				"ImageTexture"   => self.insert_texture(name.to_string(), Arc::new(ImageTexture::from(&self, node))),
-				"NoiseTexture"   => self.insert_texture(name.to_string(), Arc::new(NoiseTexture::from(&self, node))),
-				"SolidColor"     => self.insert_texture(name.to_string(), Arc::new(SolidColor::from(&self, node))),*/
-				_                => panic!("unknown material: {}", node.tag_name().name()),
+				"CheckerTexture" => self.insert_texture(name.to_string(), Arc::new(CheckerTexture::from(self, node))),
+				"ImageTexture"   => self.insert_texture(name.to_string(), Arc::new(ImageTexture::from(self, node))),
+				"NoiseTexture"   => self.insert_texture(name.to_string(), Arc::new(NoiseTexture::from(self, node))),
+				"SolidColor"     => self.insert_texture(name.to_string(), Arc::new(SolidColor::from(self, node))),
+				_                => panic!("unknown texture: {}", node.tag_name().name()),
 			};
 		}
 
@@ -195,14 +465,25 @@ impl Scene
 	{
 		if let Some(name) = node.attribute("name")
 		{
-			match node.tag_name().name()
+			return match node.tag_name().name()
 			{
-				/*"Dielectric"   => self.insert_material(name.to_string(), Arc::new(Dielectric::from(&self, node))),
-				"DiffuseLight" => self.insert_material(name.to_string(), Arc::new(DiffuseLight::from(&self, node))),
-				"Isotropic"    => self.insert_material(name.to_string(), Arc::new(Isotropic::from(&self, node))),
-				"Lambertian"   => self.insert_material(name.to_string(), Arc::new(Lambertian::from(&self, node))),*/
-				_              => panic!("unknown material: {}", node.tag_name().name()),
-			}
+				"BumpMap"         => self.insert_material(name.to_string(), Arc::new(BumpMap::from(self, node))),
+				"Cloth"           => self.insert_material(name.to_string(), Arc::new(Cloth::from(self, node))),
+				"Coated"          => self.insert_material(name.to_string(), Arc::new(Coated::from(self, node))),
+				"Conductor"       => self.insert_material(name.to_string(), Arc::new(Conductor::from(self, node))),
+				"Cutout"          => self.insert_material(name.to_string(), Arc::new(Cutout::from(self, node))),
+				"Dielectric"      => self.insert_material(name.to_string(), Arc::new(Dielectric::from(self, node))),
+				"DiffuseLight"    => self.insert_material(name.to_string(), Arc::new(DiffuseLight::from(self, node))),
+				"Isotropic"       => self.insert_material(name.to_string(), Arc::new(Isotropic::from(self, node))),
+				"Lambertian"      => self.insert_material(name.to_string(), Arc::new(Lambertian::from(self, node))),
+				"MediumInterface" => self.insert_material(name.to_string(), Arc::new(MediumInterface::from(self, node))),
+				"Metal"           => self.insert_material(name.to_string(), Arc::new(Metal::from(self, node))),
+				"MixMaterial"     => self.insert_material(name.to_string(), Arc::new(MixMaterial::from(self, node))),
+				"NormalMap"       => self.insert_material(name.to_string(), Arc::new(NormalMap::from(self, node))),
+				"Principled"      => self.insert_material(name.to_string(), Arc::new(Principled::from(self, node))),
+				"Subsurface"      => self.insert_material(name.to_string(), Arc::new(Subsurface::from(self, node))),
+				_                 => panic!("unknown material: {}", node.tag_name().name()),
+			};
 		}
 
 		panic!("no name given for material {:?}", node);
@@ -222,41 +503,157 @@ impl Scene
 		});
 	}
 
-	fn parse_xml_object(&mut self, node: &roxmltree::Node)
+	/* None for nodes that are parameters of their parent rather than objects */
+	fn parse_xml_object(&self, node: &roxmltree::Node) -> Option<Arc::<dyn Hittable>>
+	{
+		Some(match node.tag_name().name()
+		{
+			"Cone"           => Arc::new(Cone::from(self, node)),
+			"ConstantMedium" => Arc::new(ConstantMedium::from(self, node)),
+			"Cube"           => Arc::new(Cube::from(self, node)),
+			"Cylinder"       => Arc::new(Cylinder::from(self, node)),
+			"FlipFace"       => Arc::new(FlipFace::from(self, node)),
+			"Group"          => Arc::new(BvhNode::from(self, node)),
+			"MovingSphere"   => Arc::new(MovingSphere::from(self, node)),
+			"Obj"            => Arc::new(Obj::from(self, node)),
+			"RotateY"        => Arc::new(RotateY::from(self, node)),
+			"Sphere"         => Arc::new(Sphere::from(self, node)),
+			"Stl"            => Arc::new(Stl::from(self, node)),
+			"Translate"      => Arc::new(Translate::from(self, node)),
+			"Visibility"     => Arc::new(Visibility::from(self, node)),
+			"XYRect"         => Arc::new(XYRect::from(self, node)),
+			"XZRect"         => Arc::new(XZRect::from(self, node)),
+			"YZRect"         => Arc::new(YZRect::from(self, node)),
+			_                => return None,
+		})
+	}
+
+	/* objects nested in a Group, a transform or another node wrapping objects */
+	pub fn parse_xml_inner_objects(&self, node: &roxmltree::Node) -> Vec::<Arc::<dyn Hittable>>
+	{
+		node.children().filter(|child| child.is_element()).filter_map(|child| self.parse_xml_object(&child)).collect()
+	}
+
+	pub fn parse_xml_inner_object(&self, node: &roxmltree::Node) -> Arc::<dyn Hittable>
 	{
-		/*self.insert_object(match node.tag_name().name()
+		let mut objects = self.parse_xml_inner_objects(node);
+		if objects.len() != 1
 		{
-			"Group"          => Arc::new(BvhNode::from(&self, node)),
-			"Cone"           => Arc::new(Cone::from(&self, node)),
-			"ConstantMedium" => Arc::new(ConstantMedium::from(&self, node)),
-			"Cube"           => Arc::new(Cube::from(&self, node)),
-			"Cylinder"       => Arc::new(Cylinder::from(&self, node)),
-			"MovingSphere"   => Arc::new(MovingSphere::from(&self, node)),
-			"Obj"            => Arc::new(Obj::from(&self, node)),
-			"XYRect"         => Arc::new(XYRect::from(&self, node)),
-			"XZRect"         => Arc::new(XZRect::from(&self, node)),
-			"YZRect"         => Arc::new(YZRect::from(&self, node)),
-			"RotateY"        => Arc::new(RotateY::from(&self, node)),
-			"Sphere"         => Arc::new(Sphere::from(&self, node)),
-			"Stl"            => Arc::new(Stl::from(&self, node)),
-			"Translate"      => Arc::new(Translate::from(&self, node)),
-			"Triangle"       => Arc::new(Triangle::from(&self, node)),
-			_                => panic!("unknown object: {}", node.tag_name().name()),
-		});*/
+			panic!("{} needs exactly one object, found {}", node.tag_name().name(), objects.len());
+		}
+		objects.remove(0)
 	}
 
+	/* objects with a light attribute set to true are also sampled as lights */
 	fn parse_xml_objects(&mut self, node: &roxmltree::Node)
 	{
+		let mut objects: Vec::<Arc::<dyn Hittable>> = Vec::new();
+		let mut lights: Vec::<Arc::<dyn Hittable>> = Vec::new();
 		node.children().for_each(|child|
 		{
 			match child.node_type()
 			{
-				roxmltree::NodeType::Element => self.parse_xml_object(&child),
+				roxmltree::NodeType::Element =>
+				{
+					let object = self.parse_xml_object(&child).unwrap_or_else(|| panic!("unknown object: {}", child.tag_name().name()));
+					if child.attribute("light") == Some("true")
+					{
+						lights.push(object.clone());
+					}
+					objects.push(object);
+				},
 				roxmltree::NodeType::Comment => (),
 				roxmltree::NodeType::Text => (),
 				_ => panic!("unexpected Objects node {:?}", child),
 			}
 		});
+
+		self.set_objects(objects);
+		self.set_lights(lights);
+	}
+
+	fn parse_xml_lights(&mut self, node: &roxmltree::Node)
+	{
+		node.children().for_each(|child|
+		{
+			match child.node_type()
+			{
+				roxmltree::NodeType::Element =>
+				{
+					let light: Arc::<dyn Light> = match child.tag_name().name()
+					{
+						"PointLight" => Arc::new(PointLight::from(self, &child)),
+						"SpotLight"  => Arc::new(SpotLight::from(self, &child)),
+						_            => panic!("unknown light: {}", child.tag_name().name()),
+					};
+					self.add_point_light(light);
+				},
+				roxmltree::NodeType::Comment => (),
+				roxmltree::NodeType::Text => (),
+				_ => panic!("unexpected Lights node {:?}", child),
+			}
+		});
+	}
+
+	/* an IES file and the nadir direction of the fixture given by x, y and z, straight down by default */
+	pub fn parse_xml_profile(node: &roxmltree::Node) -> (Arc::<IesProfile>, Vec3d)
+	{
+		let profile = Arc::new(IesProfile::new(&Self::parse_xml_attr_str(node, "filename".to_string())));
+		let axis = if node.attribute("x").is_some() { Self::parse_xml_vec3d(node) } else { Vec3d::new(0.0, -1.0, 0.0) };
+		(profile, axis)
+	}
+
+	/* film thickness in nanometers and index of refraction, the thickness attribute scales the texture when one is given */
+	pub fn parse_xml_thin_film(&self, node: &roxmltree::Node) -> (Arc::<dyn Texture>, f64)
+	{
+		let thickness = Self::parse_xml_attr_f64(node, "thickness".to_string());
+		let thickness: Arc::<dyn Texture> = match node.attribute("texture")
+		{
+			None => Arc::new(SolidColor::new(Vec3d::newv(thickness))),
+			Some(texture) => Arc::new(ScaleTexture::new(self.texture(texture.to_string()), Vec3d::newv(thickness))),
+		};
+		(thickness, Self::parse_xml_attr_f64(node, "ior".to_string()))
+	}
+
+	/* a blackbody temperature in kelvin, a standard illuminant or values at regular steps in nanometers */
+	pub fn parse_xml_spectrum(node: &roxmltree::Node) -> Arc::<dyn Spectrum>
+	{
+		match Self::parse_xml_attr_str(node, "type".to_string()).as_str()
+		{
+			"a"         => Arc::new(illuminant_a()),
+			"blackbody" => Arc::new(BlackbodySpectrum::new(Self::parse_xml_attr_f64(node, "temperature".to_string()))),
+			"constant"  => Arc::new(ConstantSpectrum::new(Self::parse_xml_f64(node))),
+			"d65"       => Arc::new(SampledSpectrum::d65()),
+			"sampled"   =>
+			{
+				let values = Self::parse_xml_attr_str(node, "values".to_string()).split_whitespace().map(|value| f64::from_str(value).expect("invalid float value")).collect();
+				Arc::new(SampledSpectrum::new(Self::parse_xml_attr_f64(node, "start".to_string()), Self::parse_xml_attr_f64(node, "step".to_string()), values))
+			},
+			spectrum    => panic!("unknown spectrum: {}", spectrum),
+		}
+	}
+
+	fn parse_xml_background(node: &roxmltree::Node) -> Arc::<dyn Background>
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		match node.attribute("type").unwrap_or("solid")
+		{
+			"solid" => Arc::new(SolidBackground::new(Self::parse_xml_vec3d(&child("Color").expect("no Color node found for Background")))),
+			"sky"   =>
+			{
+				/* Preetham sky, the sun direction points towards the sun */
+				let sun_direction = Self::parse_xml_vec3d(&child("SunDirection").expect("no SunDirection node found for sky Background"));
+				let turbidity = child("Turbidity").map_or(3.0, |turbidity| Self::parse_xml_f64(&turbidity));
+				let ground_albedo = child("GroundAlbedo").map_or(Vec3d::newv(0.3), |albedo| Self::parse_xml_vec3d(&albedo));
+				match child("Intensity")
+				{
+					Some(intensity) => Arc::new(PhysicalSky::with_intensity(sun_direction, turbidity, ground_albedo, Self::parse_xml_f64(&intensity))),
+					None => Arc::new(PhysicalSky::new(sun_direction, turbidity, ground_albedo)),
+				}
+			},
+			background => panic!("unknown background: {}", background),
+		}
 	}
 
 	pub fn from_file(filename: &str) -> Self
@@ -277,10 +674,24 @@ impl Scene
 							"Scene" =>
 							{
 								let mut scene = Scene::new(Self::parse_xml_camera(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Camera").expect("no Camera node found")));
+								scene.set_spectral(node.attribute("spectral") == Some("true"));
 
 								scene.parse_xml_textures(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Textures").expect("no Textures node found"));
 								scene.parse_xml_materials(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Materials").expect("no Materials node found"));
 								scene.parse_xml_objects(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Objects").expect("node Objects node found"));
+								if let Some(lights) = node.children().find(|node| node.is_element() && node.tag_name().name() == "Lights")
+								{
+									scene.parse_xml_lights(&lights);
+								}
+								if let Some(background) = node.children().find(|node| node.is_element() && node.tag_name().name() == "Background")
+								{
+									scene.set_background(Self::parse_xml_background(&background));
+								}
+								if let Some(medium) = node.children().find(|node| node.is_element() && node.tag_name().name() == "Medium")
+								{
+									let extent = medium.children().find(|node| node.is_element() && node.tag_name().name() == "Extent").map_or(scene.extent(), |node| Self::parse_xml_f64(&node));
+									scene.set_medium(scene.parse_xml_medium(&medium), extent);
+								}
 								ret = Some(scene);
 							},
 							_ => panic!("unexpected node: {}", node.tag_name().name()),
@@ -307,11 +718,12 @@ impl Scene
 		let vup = Vec3d::new(0.0, 1.0, 0.0);
 		let dist_to_focus = 10.0;
 		let aperture = 0.01;
-		let camera = Camera::with_time(lookfrom, lookat, vup, 20.0, Vec2u::new(1920, 1080), aperture, dist_to_focus, 0.0, 1.0);
+		let camera = Arc::new(PerspectiveCamera::with_time(lookfrom, lookat, vup, 20.0, Vec2u::new(1920, 1080), aperture, dist_to_focus, 0.0, 1.0));
 
 		let mut rng = rand::thread_rng();
 		let mut world = HittableList::new();
 		let mut objects: Vec::<Arc::<dyn Hittable>> = Vec::new();
+		let mut lights: Vec::<Arc::<dyn Hittable>> = Vec::new();
 
 		let ground_material: Arc::<dyn Material> = Arc::new(Lambertian::new(
 			Arc::new(CheckerTexture::new(
@@ -335,18 +747,24 @@ impl Scene
 						let albedo = Arc::new(SolidColor::new(Vec3d::random(0.0, 1.0) * Vec3d::random(0.0, 1.0) * 1.0));
 						let material: Arc::<dyn Material> = Arc::new(DiffuseLight::new(albedo));
 						let center2 = center + Vec3d::new(0.0, rng.gen_range(0.0..0.5), 0.0);
-						objects.push(Arc::new(MovingSphere::new(center, center2, 0.0, 1.0, 0.2, material.clone())));
+						let sphere: Arc::<dyn Hittable> = Arc::new(MovingSphere::new(center, center2, 0.0, 1.0, 0.2, material.clone()));
+						objects.push(sphere.clone());
+						lights.push(sphere);
 					}
 					else if choose_mat < 0.3
 					{
 						let material: Arc::<dyn Material> = Arc::new(DiffuseLight::new(Arc::new(ImageTexture::new("earthmap.jpg"))));
-						objects.push(Arc::new(Sphere::new(center, 0.2, material.clone())));
+						let sphere: Arc::<dyn Hittable> = Arc::new(Sphere::new(center, 0.2, material.clone()));
+						objects.push(sphere.clone());
+						lights.push(sphere);
 					}
 					else if choose_mat < 0.6
 					{
 						let albedo = Arc::new(NoiseTexture::new(Vec3d::random(0.0, 1.0) * Vec3d::random(0.0, 1.0) * 50.0, 4.0));
 						let material: Arc::<dyn Material> = Arc::new(DiffuseLight::new(albedo));
-						objects.push(Arc::new(Sphere::new(center, 0.2, material.clone())));
+						let sphere: Arc::<dyn Hittable> = Arc::new(Sphere::new(center, 0.2, material.clone()));
+						objects.push(sphere.clone());
+						lights.push(sphere);
 					}
 					else if choose_mat < 0.8
 					{
@@ -374,14 +792,20 @@ impl Scene
 		objects.push(Arc::new(Sphere::new(Vec3d::new(4.0, 1.0, 0.0), 1.0, m3.clone())));
 
 		let m4: Arc::<dyn Material> = Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(Vec3d::new(0.5, 0.5, 0.8) * 10.0))));
-		objects.push(Arc::new(Sphere::new(Vec3d::new(10.0, 10.0, 10.0), 5.0, m4.clone())));
+		let sun: Arc::<dyn Hittable> = Arc::new(Sphere::new(Vec3d::new(10.0, 10.0, 10.0), 5.0, m4.clone()));
+		objects.push(sun.clone());
+		lights.push(sun);
 
 		objects.push(Arc::new(Obj::new("cessna.obj", Vec3d::new(-3.0, 1.0, 3.0), Vec3d::newv(1.0 / 10.0))));
 		objects.push(Arc::new(Stl::new("frostmourne.stl", Vec3d::new(0.0, -1.0, 7.0), Vec3d::newv(1.0 / 15.0), m2)));
 
 		world.push(Arc::new(BvhNode::new(objects, 0.0, 1.0)));
 
-		Self{camera, textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(world), lights: Arc::new(HittableList::new())}
+		let background = Arc::new(PhysicalSky::new(Vec3d::new(1.0, 0.6, 0.4), 3.0, Vec3d::newv(0.3)));
+
+		let mut scene = Self{camera, background, textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(world), lights: None, point_lights: Vec::new(), medium: None, spectral: false};
+		scene.set_lights(lights);
+		scene
 	}
 
 	pub fn simple_light_scene() -> Self
@@ -391,7 +815,7 @@ impl Scene
 		let vup = Vec3d::new(0.0, 1.0, 0.0);
 		let dist_to_focus = 10.0;
 		let aperture = 0.01;
-		let camera = Camera::with_time(lookfrom, lookat, vup, 20.0, Vec2u::new(1920, 1080), aperture, dist_to_focus, 0.0, 1.0);
+		let camera = Arc::new(PerspectiveCamera::with_time(lookfrom, lookat, vup, 20.0, Vec2u::new(1920, 1080), aperture, dist_to_focus, 0.0, 1.0));
 
 		let mut world = HittableList::new();
 		let mut objects: Vec::<Arc::<dyn Hittable>> = Vec::new();
@@ -401,11 +825,14 @@ impl Scene
 		objects.push(Arc::new(Sphere::new(Vec3d::new(0.0,     2.0, 0.0),    2.0, pertext.clone())));
 
 		let difflight: Arc::<dyn Material> = Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(Vec3d::newv(4.0)))));
-		objects.push(Arc::new(XYRect::new(3.0, 5.0, 1.0, 3.0, -2.0, difflight.clone())));
+		let light: Arc::<dyn Hittable> = Arc::new(XYRect::new(3.0, 5.0, 1.0, 3.0, -2.0, difflight.clone()));
+		objects.push(light.clone());
 
 		world.push(Arc::new(BvhNode::new(objects, 0.0, 1.0)));
 
-		Self{camera, textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(world), lights: Arc::new(HittableList::new())}
+		let mut scene = Self{camera, background: Arc::new(SolidBackground::new(Vec3d::zero())), textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(world), lights: None, point_lights: Vec::new(), medium: None, spectral: false};
+		scene.set_lights(vec![light]);
+		scene
 	}
 
 	pub fn cornell_box() -> Self
@@ -415,20 +842,20 @@ impl Scene
 		let vup = Vec3d::new(0.0, 1.0, 0.0);
 		let dist_to_focus = 10.0;
 		let aperture = 0.01;
-		let camera = Camera::with_time(lookfrom, lookat, vup, 40.0, Vec2u::new(1000, 1000), aperture, dist_to_focus, 0.0, 1.0);
+		let camera = Arc::new(PerspectiveCamera::with_time(lookfrom, lookat, vup, 40.0, Vec2u::new(1000, 1000), aperture, dist_to_focus, 0.0, 1.0));
 
 		let mut world = HittableList::new();
 		let mut objects: Vec::<Arc::<dyn Hittable>> = Vec::new();
-		let mut lights = HittableList::new();
 
 		let red:   Arc::<dyn Material> = Arc::new(  Lambertian::new(Arc::new(SolidColor::new(Vec3d::new(0.64, 0.05, 0.05)))));
 		let white: Arc::<dyn Material> = Arc::new(  Lambertian::new(Arc::new(SolidColor::new(Vec3d::new(0.73, 0.73, 0.73)))));
 		let green: Arc::<dyn Material> = Arc::new(  Lambertian::new(Arc::new(SolidColor::new(Vec3d::new(0.12, 0.45, 0.15)))));
-		let light: Arc::<dyn Material> = Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(Vec3d::new(15.0, 15.0 ,15.0 )))));
+		let light: Arc::<dyn Material> = Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(Vec3d::new(15.0, 15.0 ,15.0 )))).with_sides(EmissionSides::Back));
 
 		objects.push(Arc::new(YZRect::new(  0.0, 555.0,   0.0, 555.0, 555.0, green.clone())));
 		objects.push(Arc::new(YZRect::new(  0.0, 555.0,   0.0, 555.0,   0.0, red.clone())));
-		objects.push(Arc::new(FlipFace::new(Arc::new(XZRect::new(213.0, 343.0, 227.0, 332.0, 554.0, light.clone())))));
+		let ceiling_light: Arc::<dyn Hittable> = Arc::new(XZRect::new(213.0, 343.0, 227.0, 332.0, 554.0, light.clone()));
+		objects.push(ceiling_light.clone());
 		objects.push(Arc::new(XZRect::new(  0.0, 555.0,   0.0, 555.0,   0.0, white.clone())));
 		objects.push(Arc::new(XZRect::new(  0.0, 555.0,   0.0, 555.0, 555.0, white.clone())));
 		objects.push(Arc::new(XYRect::new(  0.0, 555.0,   0.0, 555.0, 555.0, white.clone())));
@@ -445,10 +872,55 @@ impl Scene
 		objects.push(box2);
 		//objects.push(Arc::new(ConstantMedium::new(box2.clone(), 0.01, Arc::new(SolidColor::new(Vec3d::one())))));
 
-		lights.push(Arc::new(XZRect::new(213.0, 343.0, 227.0, 332.0, 554.0, white.clone())));
 		world.push(Arc::new(BvhNode::new(objects, 0.0, 1.0)));
 
-		Self{camera, textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(world), lights: Arc::new(lights)}
+		let mut scene = Self{camera, background: Arc::new(SolidBackground::new(Vec3d::zero())), textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(world), lights: None, point_lights: Vec::new(), medium: None, spectral: false};
+		scene.set_lights(vec![ceiling_light]);
+		scene
+	}
+
+	/* velvet and felt under the cornell box light, the sheen shows at grazing angles along the silhouettes */
+	pub fn cloth_scene() -> Self
+	{
+		let lookfrom = Vec3d::new(278.0, 278.0, -800.0);
+		let lookat = Vec3d::new(278.0, 278.0, 0.0);
+		let vup = Vec3d::new(0.0, 1.0, 0.0);
+		let dist_to_focus = 10.0;
+		let aperture = 0.01;
+		let camera = Arc::new(PerspectiveCamera::with_time(lookfrom, lookat, vup, 40.0, Vec2u::new(1000, 1000), aperture, dist_to_focus, 0.0, 1.0));
+
+		let mut world = HittableList::new();
+		let mut objects: Vec::<Arc::<dyn Hittable>> = Vec::new();
+
+		let white: Arc::<dyn Material> = Arc::new(  Lambertian::new(Arc::new(SolidColor::new(Vec3d::new(0.73, 0.73, 0.73)))));
+		let light: Arc::<dyn Material> = Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(Vec3d::new(15.0, 15.0 ,15.0 )))).with_sides(EmissionSides::Back));
+		let velvet: Arc::<dyn Material> = Arc::new(Cloth::new(Arc::new(SolidColor::new(Vec3d::new(0.35, 0.02, 0.05))))
+			.with_sheen(Arc::new(SolidColor::new(Vec3d::new(1.0, 0.6, 0.7))))
+			.with_roughness(Arc::new(SolidColor::new(Vec3d::newv(0.3)))));
+		let felt: Arc::<dyn Material> = Arc::new(Cloth::new(Arc::new(SolidColor::new(Vec3d::new(0.05, 0.15, 0.4))))
+			.with_sheen(Arc::new(SolidColor::new(Vec3d::newv(0.5))))
+			.with_roughness(Arc::new(SolidColor::new(Vec3d::newv(0.8)))));
+
+		objects.push(Arc::new(YZRect::new(  0.0, 555.0,   0.0, 555.0, 555.0, white.clone())));
+		objects.push(Arc::new(YZRect::new(  0.0, 555.0,   0.0, 555.0,   0.0, white.clone())));
+		let ceiling_light: Arc::<dyn Hittable> = Arc::new(XZRect::new(213.0, 343.0, 227.0, 332.0, 554.0, light.clone()));
+		objects.push(ceiling_light.clone());
+		objects.push(Arc::new(XZRect::new(  0.0, 555.0,   0.0, 555.0,   0.0, white.clone())));
+		objects.push(Arc::new(XZRect::new(  0.0, 555.0,   0.0, 555.0, 555.0, white.clone())));
+		objects.push(Arc::new(XYRect::new(  0.0, 555.0,   0.0, 555.0, 555.0, white.clone())));
+
+		objects.push(Arc::new(Sphere::new(Vec3d::new(380.0, 130.0, 300.0), 130.0, velvet.clone())));
+
+		let mut cube: Arc::<dyn Hittable> = Arc::new(Cube::new(Vec3d::new(0.0, 0.0, 0.0), Vec3d::new(165.0, 165.0, 165.0), felt.clone()));
+		cube = Arc::new(RotateY::new(cube.clone(), -18.0));
+		cube = Arc::new(Translate::new(cube.clone(), Vec3d::new(90.0, 0.0, 120.0)));
+		objects.push(cube);
+
+		world.push(Arc::new(BvhNode::new(objects, 0.0, 1.0)));
+
+		let mut scene = Self{camera, background: Arc::new(SolidBackground::new(Vec3d::zero())), textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(world), lights: None, point_lights: Vec::new(), medium: None, spectral: false};
+		scene.set_lights(vec![ceiling_light]);
+		scene
 	}
 
 	pub fn chapter2() -> Self
@@ -458,7 +930,7 @@ impl Scene
 		let vup = Vec3d::new(0.0, 1.0, 0.0);
 		let dist_to_focus = 10.0;
 		let aperture = 0.01;
-		let camera = Camera::with_time(lookfrom, lookat, vup, 40.0, Vec2u::new(1000, 1000), aperture, dist_to_focus, 0.0, 1.0);
+		let camera = Arc::new(PerspectiveCamera::with_time(lookfrom, lookat, vup, 40.0, Vec2u::new(1000, 1000), aperture, dist_to_focus, 0.0, 1.0));
 
 		let mut rng = rand::thread_rng();
 		let mut world = HittableList::new();
@@ -485,7 +957,8 @@ impl Scene
 		objects.push(Arc::new(BvhNode::new(boxes1, 0.0, 1.0)));
 
 		let light: Arc::<dyn Material> = Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(Vec3d::newv(7.0)))));
-		objects.push(Arc::new(XZRect::new(123.0, 423.0, 147.0, 412.0, 554.0, light.clone())));
+		let ceiling_light: Arc::<dyn Hittable> = Arc::new(XZRect::new(123.0, 423.0, 147.0, 412.0, 554.0, light.clone()));
+		objects.push(ceiling_light.clone());
 
 		let center1 = Vec3d::new(400.0, 400.0, 200.0);
 		let center2 = center1 + Vec3d::new(30.0, 0.0, 0.0);
@@ -496,9 +969,7 @@ impl Scene
 
 		/*let b1: Arc::<dyn Hittable> = Arc::new(Sphere::new(Vec3d::new(360.0, 150.0, 145.0), 70.0, Arc::new(Dielectric::new(1.5))));
 		objects.push(b1.clone());
-		objects.push(Arc::new(ConstantMedium::new(b1.clone(), 0.2, Arc::new(SolidColor::new(Vec3d::new(0.2, 0.4, 0.9))))));
-		let b2: Arc::<dyn Hittable> = Arc::new(Sphere::new(Vec3d::new(0.0, 0.0, 0.0), 5000.0, Arc::new(Dielectric::new(1.5))));
-		objects.push(Arc::new(ConstantMedium::new(b2.clone(), 0.0001, Arc::new(SolidColor::new(Vec3d::new(1.0, 1.0, 1.0))))));*/
+		objects.push(Arc::new(ConstantMedium::new(b1.clone(), 0.2, Arc::new(SolidColor::new(Vec3d::new(0.2, 0.4, 0.9))))));*/
 
 		objects.push(Arc::new(Sphere::new(Vec3d::new(400.0, 200.0, 400.0), 100.0, Arc::new(Lambertian::new(Arc::new(ImageTexture::new("earthmap.jpg")))))));
 		objects.push(Arc::new(Sphere::new(Vec3d::new(220.0, 280.0, 300.0), 80.0, Arc::new(Lambertian::new(Arc::new(NoiseTexture::new(Vec3d::new(1.0, 1.0, 1.0), 0.1)))))));
@@ -524,6 +995,10 @@ impl Scene
 
 		world.push(Arc::new(BvhNode::new(objects, 0.0, 1.0)));
 
-		Self{camera, textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(world), lights: Arc::new(HittableList::new())}
+		let mut scene = Self{camera, background: Arc::new(SolidBackground::new(Vec3d::zero())), textures: HashMap::new(), materials: HashMap::new(), objects: Arc::new(world), lights: None, point_lights: Vec::new(), medium: None, spectral: false};
+		scene.set_lights(vec![ceiling_light]);
+		/*let extent = scene.extent();
+		scene.set_medium(Arc::new(HomogeneousMedium::new(Vec3d::zero(), Vec3d::newv(0.0001))), extent);*/
+		scene
 	}
 }
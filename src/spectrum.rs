@@ -0,0 +1,468 @@
+use crate::math::vec::
+{
+	Vec3d,
+	Vec4d,
+};
+
+use std::sync::OnceLock;
+
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 780.0;
+pub const N_SPECTRUM_SAMPLES: usize = 4;
+
+fn gaussian(x: f64, mu: f64, sigma1: f64, sigma2: f64) -> f64
+{
+	let t = (x - mu) / if x < mu { sigma1 } else { sigma2 };
+	f64::exp(-0.5 * t * t)
+}
+
+/* multi-lobe fit of the CIE 1931 matching functions, Wyman, Sloan & Shirley 2013 */
+pub fn cie_xyz(lambda: f64) -> Vec3d
+{
+	Vec3d::new(1.056 * gaussian(lambda, 599.8, 37.9, 31.0) + 0.362 * gaussian(lambda, 442.0, 16.0, 26.7) - 0.065 * gaussian(lambda, 501.1, 20.4, 26.2),
+	           0.821 * gaussian(lambda, 568.8, 46.9, 40.5) + 0.286 * gaussian(lambda, 530.9, 16.3, 31.1),
+	           1.217 * gaussian(lambda, 437.0, 11.8, 36.0) + 0.681 * gaussian(lambda, 459.0, 26.0, 13.8))
+}
+
+/* linear sRGB, D65 white */
+pub fn xyz_to_rgb(xyz: Vec3d) -> Vec3d
+{
+	Vec3d::new( 3.2406 * xyz.x() - 1.5372 * xyz.y() - 0.4986 * xyz.z(),
+	           -0.9689 * xyz.x() + 1.8758 * xyz.y() + 0.0415 * xyz.z(),
+	            0.0557 * xyz.x() - 0.2040 * xyz.y() + 1.0570 * xyz.z())
+}
+
+/* integral of the rgb response over the sampled range, so a flat spectrum maps to white */
+fn rgb_integral() -> Vec3d
+{
+	static INTEGRAL: OnceLock<Vec3d> = OnceLock::new();
+	*INTEGRAL.get_or_init(||
+	{
+		let n = 1000;
+		let step = (LAMBDA_MAX - LAMBDA_MIN) / n as f64;
+		let mut sum = Vec3d::zero();
+		for i in 0..n
+		{
+			sum += xyz_to_rgb(cie_xyz(LAMBDA_MIN + (i as f64 + 0.5) * step)) * step;
+		}
+		sum
+	})
+}
+
+const N_REFLECTANCE_SAMPLES: usize = 32;
+
+/* rgb of a reflectance spectrum from a few fixed wavelengths, cheap enough to be used inside bsdfs */
+pub fn reflectance_to_rgb(func: &dyn Fn(f64) -> f64) -> Vec3d
+{
+	static WEIGHTS: OnceLock<[(f64, Vec3d); N_REFLECTANCE_SAMPLES]> = OnceLock::new();
+	let weights = WEIGHTS.get_or_init(||
+	{
+		let step = (LAMBDA_MAX - LAMBDA_MIN) / N_REFLECTANCE_SAMPLES as f64;
+		let mut weights = [(0.0, Vec3d::zero()); N_REFLECTANCE_SAMPLES];
+		let mut sum = Vec3d::zero();
+		for (i, weight) in weights.iter_mut().enumerate()
+		{
+			let lambda = LAMBDA_MIN + (i as f64 + 0.5) * step;
+			*weight = (lambda, xyz_to_rgb(cie_xyz(lambda)));
+			sum += weight.1;
+		}
+
+		/* normalized so a constant reflectance of one is white */
+		weights.map(|(lambda, weight)| (lambda, weight / sum))
+	});
+
+	weights.iter().fold(Vec3d::zero(), |rgb, (lambda, weight)| rgb + *weight * func(*lambda))
+}
+
+/* weight turning radiance carried by a single wavelength into an rgb estimate */
+pub fn rgb_weight(lambda: f64, pdf: f64) -> Vec3d
+{
+	xyz_to_rgb(cie_xyz(lambda)) / rgb_integral() / pdf
+}
+
+/* hero wavelength with equally spaced rotations over the visible range, rgb paths only use the hero for dispersion */
+#[derive(Debug, Copy, Clone)]
+pub struct SampledWavelengths
+{
+	lambda: [f64; N_SPECTRUM_SAMPLES],
+	pdf: [f64; N_SPECTRUM_SAMPLES],
+	spectral: bool,
+}
+
+impl SampledWavelengths
+{
+	pub fn sample_uniform(u: f64, spectral: bool) -> Self
+	{
+		let range = LAMBDA_MAX - LAMBDA_MIN;
+		let delta = range / N_SPECTRUM_SAMPLES as f64;
+		let mut lambda = [0.0; N_SPECTRUM_SAMPLES];
+		lambda[0] = LAMBDA_MIN + u * range;
+		for i in 1..N_SPECTRUM_SAMPLES
+		{
+			lambda[i] = lambda[i - 1] + delta;
+			if lambda[i] > LAMBDA_MAX
+			{
+				lambda[i] -= range;
+			}
+		}
+
+		Self{lambda, pdf: [1.0 / range; N_SPECTRUM_SAMPLES], spectral}
+	}
+
+	/* whether the path carries the radiance of these wavelengths rather than rgb */
+	pub fn spectral(&self) -> bool
+	{
+		self.spectral
+	}
+
+	pub fn hero(&self) -> f64
+	{
+		self.lambda[0]
+	}
+
+	pub fn lambda(&self, i: usize) -> f64
+	{
+		self.lambda[i]
+	}
+
+	pub fn secondary_terminated(&self) -> bool
+	{
+		self.pdf[1..].iter().all(|pdf| *pdf == 0.0)
+	}
+
+	/* wavelength dependent scattering, only the hero wavelength carries on */
+	pub fn terminate_secondary(&mut self)
+	{
+		if self.secondary_terminated()
+		{
+			return;
+		}
+
+		for i in 1..N_SPECTRUM_SAMPLES
+		{
+			self.pdf[i] = 0.0;
+		}
+	}
+
+	pub fn hero_rgb_weight(&self) -> Vec3d
+	{
+		rgb_weight(self.lambda[0], self.pdf[0])
+	}
+
+	/* average rgb estimate of the radiance carried by the wavelengths still alive */
+	pub fn to_rgb(self, l: Vec4d) -> Vec3d
+	{
+		let mut rgb = Vec3d::zero();
+		let mut count = 0;
+		for i in 0..N_SPECTRUM_SAMPLES
+		{
+			if self.pdf[i] > 0.0
+			{
+				rgb += rgb_weight(self.lambda[i], self.pdf[i]) * l.v[i];
+				count += 1;
+			}
+		}
+
+		if count == 0
+		{
+			return Vec3d::zero();
+		}
+
+		rgb / count as f64
+	}
+}
+
+pub trait Spectrum: Send + Sync
+{
+	/* lambda in nanometers */
+	fn value(&self, lambda: f64) -> f64;
+
+	fn sample(&self, lambda: &SampledWavelengths) -> Vec4d
+	{
+		Vec4d::new(self.value(lambda.lambda(0)), self.value(lambda.lambda(1)), self.value(lambda.lambda(2)), self.value(lambda.lambda(3)))
+	}
+}
+
+/* rgb response of a spectrum, a constant spectrum of one maps to white */
+pub fn spectrum_to_rgb(spectrum: &dyn Spectrum) -> Vec3d
+{
+	let n = 400;
+	let step = (LAMBDA_MAX - LAMBDA_MIN) / n as f64;
+	let mut sum = Vec3d::zero();
+	for i in 0..n
+	{
+		let lambda = LAMBDA_MIN + (i as f64 + 0.5) * step;
+		sum += xyz_to_rgb(cie_xyz(lambda)) * (spectrum.value(lambda) * step);
+	}
+	sum / rgb_integral()
+}
+
+pub struct ConstantSpectrum
+{
+	c: f64,
+}
+
+impl ConstantSpectrum
+{
+	pub fn new(c: f64) -> Self
+	{
+		Self{c}
+	}
+}
+
+impl Spectrum for ConstantSpectrum
+{
+	fn value(&self, _lambda: f64) -> f64
+	{
+		self.c
+	}
+}
+
+/* Planck's law normalized to one at the peak wavelength */
+pub struct BlackbodySpectrum
+{
+	temperature: f64,
+	normalization: f64,
+}
+
+impl BlackbodySpectrum
+{
+	pub fn new(temperature: f64) -> Self
+	{
+		let lambda_max = 2.8977721e-3 / temperature * 1e9;
+		Self{temperature, normalization: 1.0 / Self::planck(lambda_max, temperature)}
+	}
+
+	fn planck(lambda: f64, temperature: f64) -> f64
+	{
+		if temperature <= 0.0
+		{
+			return 0.0;
+		}
+
+		let c = 299792458.0;
+		let h = 6.62606957e-34;
+		let kb = 1.3806488e-23;
+		let l = lambda * 1e-9;
+		(2.0 * h * c * c) / (l.powi(5) * (f64::exp((h * c) / (l * kb * temperature)) - 1.0))
+	}
+}
+
+impl Spectrum for BlackbodySpectrum
+{
+	fn value(&self, lambda: f64) -> f64
+	{
+		Self::planck(lambda, self.temperature) * self.normalization
+	}
+}
+
+/* samples at regular steps from a first wavelength, linearly interpolated and clamped at the ends */
+pub struct SampledSpectrum
+{
+	lambda_start: f64,
+	lambda_step: f64,
+	values: Vec::<f64>,
+}
+
+impl SampledSpectrum
+{
+	pub fn new(lambda_start: f64, lambda_step: f64, values: Vec::<f64>) -> Self
+	{
+		Self{lambda_start, lambda_step, values}
+	}
+
+	/* CIE standard illuminant D65, relative power from 380 to 780 nm */
+	pub fn d65() -> Self
+	{
+		Self::new(380.0, 10.0, vec!
+		[
+			 49.98,  54.65,  82.75,  91.49,  93.43,  86.68, 104.86, 117.01, 117.81, 114.86,
+			115.92, 108.81, 109.35, 107.80, 104.79, 107.69, 104.41, 104.05, 100.00,  96.33,
+			 95.79,  88.69,  90.01,  89.60,  87.70,  83.29,  83.70,  80.03,  80.21,  82.28,
+			 78.28,  69.72,  71.61,  74.35,  61.60,  69.89,  75.09,  63.59,  46.42,  66.81,
+			 63.38,
+		].iter().map(|v| v / 100.0).collect())
+	}
+}
+
+impl Spectrum for SampledSpectrum
+{
+	fn value(&self, lambda: f64) -> f64
+	{
+		let x = (lambda - self.lambda_start) / self.lambda_step;
+		if x <= 0.0
+		{
+			return self.values[0];
+		}
+
+		let i = x as usize;
+		if i + 1 >= self.values.len()
+		{
+			return self.values[self.values.len() - 1];
+		}
+
+		let t = x - i as f64;
+		self.values[i] * (1.0 - t) + self.values[i + 1] * t
+	}
+}
+
+/* CIE standard illuminant A, a 2856 K blackbody */
+pub fn illuminant_a() -> BlackbodySpectrum
+{
+	BlackbodySpectrum::new(2856.0)
+}
+
+const SMITS_WHITE: [f64; 10]   = [1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000];
+const SMITS_CYAN: [f64; 10]    = [0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000];
+const SMITS_MAGENTA: [f64; 10] = [1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959];
+const SMITS_YELLOW: [f64; 10]  = [0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840];
+const SMITS_RED: [f64; 10]     = [0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149];
+const SMITS_GREEN: [f64; 10]   = [0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025];
+const SMITS_BLUE: [f64; 10]    = [1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496];
+
+/* rgb to spectrum upsampling with the basis spectra of Smits, "An RGB to Spectrum Conversion for Reflectances" */
+#[derive(Debug, Copy, Clone)]
+pub struct RgbSpectrum
+{
+	rgb: Vec3d,
+}
+
+impl RgbSpectrum
+{
+	pub fn new(rgb: Vec3d) -> Self
+	{
+		Self{rgb}
+	}
+
+	fn basis(table: &[f64; 10], lambda: f64) -> f64
+	{
+		let x = (lambda - 380.0) / (720.0 - 380.0) * 10.0;
+		table[f64::clamp(x, 0.0, 9.0) as usize]
+	}
+}
+
+impl Spectrum for RgbSpectrum
+{
+	fn value(&self, lambda: f64) -> f64
+	{
+		let (r, g, b) = (self.rgb.x(), self.rgb.y(), self.rgb.z());
+		let basis = |table| Self::basis(table, lambda);
+		if r <= g && r <= b
+		{
+			r * basis(&SMITS_WHITE) + if g <= b
+			{
+				(g - r) * basis(&SMITS_CYAN) + (b - g) * basis(&SMITS_BLUE)
+			}
+			else
+			{
+				(b - r) * basis(&SMITS_CYAN) + (g - b) * basis(&SMITS_GREEN)
+			}
+		}
+		else if g <= r && g <= b
+		{
+			g * basis(&SMITS_WHITE) + if r <= b
+			{
+				(r - g) * basis(&SMITS_MAGENTA) + (b - r) * basis(&SMITS_BLUE)
+			}
+			else
+			{
+				(b - g) * basis(&SMITS_MAGENTA) + (r - b) * basis(&SMITS_RED)
+			}
+		}
+		else
+		{
+			b * basis(&SMITS_WHITE) + if r <= g
+			{
+				(r - b) * basis(&SMITS_YELLOW) + (g - r) * basis(&SMITS_GREEN)
+			}
+			else
+			{
+				(g - b) * basis(&SMITS_YELLOW) + (r - g) * basis(&SMITS_RED)
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	#[test]
+	fn test_blackbody_normalized()
+	{
+		let spectrum = BlackbodySpectrum::new(5000.0);
+		let peak = 2.8977721e-3 / 5000.0 * 1e9;
+		assert!((spectrum.value(peak) - 1.0).abs() < 1e-9);
+		assert!(spectrum.value(peak - 10.0) < 1.0);
+		assert!(spectrum.value(peak + 10.0) < 1.0);
+	}
+
+	#[test]
+	fn test_illuminant_a_is_warm()
+	{
+		let rgb = spectrum_to_rgb(&illuminant_a());
+		assert!(rgb.x() > rgb.y() && rgb.y() > rgb.z());
+	}
+
+	#[test]
+	fn test_d65()
+	{
+		let spectrum = SampledSpectrum::d65();
+		assert!((spectrum.value(560.0) - 1.0).abs() < 1e-9);
+		assert!((spectrum.value(565.0) - 0.98165).abs() < 1e-9);
+		assert_eq!(spectrum.value(300.0), spectrum.value(380.0));
+		assert_eq!(spectrum.value(900.0), spectrum.value(780.0));
+
+		/* a flat spectrum is white, daylight is a little bluer */
+		let rgb = spectrum_to_rgb(&spectrum);
+		assert!(rgb.z() > rgb.y() && rgb.y() > rgb.x(), "{:?}", rgb);
+	}
+
+	#[test]
+	fn test_constant_is_white()
+	{
+		let rgb = spectrum_to_rgb(&ConstantSpectrum::new(1.0));
+		assert!(Vec3d::length(rgb - Vec3d::one()) < 1e-3, "{:?}", rgb);
+	}
+
+	#[test]
+	fn test_wavelengths_are_stratified()
+	{
+		let range = LAMBDA_MAX - LAMBDA_MIN;
+		for u in [0.0, 0.3, 0.99]
+		{
+			let lambda = SampledWavelengths::sample_uniform(u, true);
+			assert_eq!(lambda.hero(), LAMBDA_MIN + u * range);
+			for i in 1..N_SPECTRUM_SAMPLES
+			{
+				assert!((LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda.lambda(i)));
+				let delta = (lambda.lambda(i) - lambda.hero()).rem_euclid(range);
+				assert!((delta - i as f64 * range / N_SPECTRUM_SAMPLES as f64).abs() < 1e-9);
+			}
+		}
+	}
+
+	#[test]
+	fn test_hero_weight_is_unbiased()
+	{
+		/* a flat unit radiance carried by the hero alone averages to white */
+		let n = 4000;
+		let mean = (0..n).map(|i| SampledWavelengths::sample_uniform((i as f64 + 0.5) / n as f64, true).hero_rgb_weight()).fold(Vec3d::zero(), |sum, rgb| sum + rgb) / n as f64;
+		assert!(Vec3d::length(mean - Vec3d::one()) < 1e-3, "{:?}", mean);
+	}
+
+	#[test]
+	fn test_terminated_wavelengths_drop_out()
+	{
+		let mut lambda = SampledWavelengths::sample_uniform(0.4, true);
+		let l = Vec4d::new(1.0, 2.0, 3.0, 4.0);
+		assert!(!lambda.secondary_terminated());
+		assert_eq!(lambda.to_rgb(l), (0..N_SPECTRUM_SAMPLES).fold(Vec3d::zero(), |sum, i| sum + rgb_weight(lambda.lambda(i), 1.0 / (LAMBDA_MAX - LAMBDA_MIN)) * l.v[i]) / N_SPECTRUM_SAMPLES as f64);
+
+		lambda.terminate_secondary();
+		assert!(lambda.secondary_terminated());
+		assert_eq!(lambda.to_rgb(l), lambda.hero_rgb_weight());
+	}
+}
@@ -0,0 +1,33 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use std::sync::Arc;
+
+use super::Texture;
+
+/* broadcasts a single channel of a texture, e.g. roughness and metallic packed in one image */
+pub struct ChannelTexture
+{
+	texture: Arc::<dyn Texture>,
+	channel: usize,
+}
+
+impl ChannelTexture
+{
+	pub fn new(texture: Arc::<dyn Texture>, channel: usize) -> Self
+	{
+		assert!(channel < 3, "invalid texture channel {}", channel);
+		Self{texture, channel}
+	}
+}
+
+impl Texture for ChannelTexture
+{
+	fn value(&self, uv: Vec2d, p: Vec3d) -> Vec3d
+	{
+		Vec3d::newv(self.texture.value(uv, p).v[self.channel])
+	}
+}
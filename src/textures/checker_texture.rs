@@ -22,14 +22,12 @@ impl CheckerTexture
 		Self{odd, even}
 	}
 
-	/*pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
+	pub fn from(scene: &Scene, node: &roxmltree::Node) -> Self
 	{
-		Self
-		{
-			odd: scene.texture(Scene::parse_xml_child(node, "OddTexture").parse_xml_attr_str("name")),
-			even: scene.texture(Scene::parse_xml_child(node, "EvenTexture").parse_xml_attr_str("name")),
-		}
-	}*/
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name).unwrap_or_else(|| panic!("no {} node found for CheckerTexture", name));
+
+		Self::new(scene.parse_xml_color(&child("Odd")), scene.parse_xml_color(&child("Even")))
+	}
 }
 
 impl Texture for CheckerTexture
@@ -0,0 +1,168 @@
+use crate::math::
+{
+	aabb::Aabb,
+	vec::
+	{
+		Vec2d,
+		Vec3d,
+	},
+};
+
+use super::Texture;
+
+/* dense voxel grid spanning a box in world space, trilinearly interpolated between voxel centers and zero outside */
+pub struct GridTexture
+{
+	data: Vec::<f64>,
+	size: [usize; 3],
+	channels: usize,
+	bounds: Aabb,
+}
+
+impl GridTexture
+{
+	/* data is x fastest, then y, then z, with the channels of a voxel stored together */
+	pub fn new(data: Vec::<f64>, size: [usize; 3], channels: usize, bounds: Aabb) -> Self
+	{
+		assert!(data.len() == size[0] * size[1] * size[2] * channels, "grid data does not match its size");
+		assert!(channels == 1 || channels == 3, "grids must have one or three channels");
+		Self{data, size, channels, bounds}
+	}
+
+	/* Mitsuba .vol files with 32 bit float data */
+	pub fn from_vol(filename: &str) -> Self
+	{
+		let bytes = std::fs::read(filename).expect("can't read grid file");
+		if bytes.len() < 48 || &bytes[0..3] != b"VOL" || bytes[3] != 3
+		{
+			panic!("{} is not a version 3 vol file", filename);
+		}
+
+		let int = |offset: usize| i32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+		if int(4) != 1
+		{
+			panic!("{} does not hold 32 bit float data", filename);
+		}
+
+		let size = [int(8) as usize, int(12) as usize, int(16) as usize];
+		let channels = int(20) as usize;
+		let bounds = Self::read_f32(&bytes[24..48]);
+		Self::new(Self::read_f32(&bytes[48..]), size, channels, Aabb::new(Vec3d::new(bounds[0], bounds[1], bounds[2]), Vec3d::new(bounds[3], bounds[4], bounds[5])))
+	}
+
+	fn read_f32(bytes: &[u8]) -> Vec::<f64>
+	{
+		bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64).collect()
+	}
+
+	/* largest value of any channel, a majorant for densities read from the grid */
+	pub fn max_value(&self) -> f64
+	{
+		self.data.iter().fold(0.0, |max, v| f64::max(max, *v))
+	}
+
+	fn voxel(&self, x: usize, y: usize, z: usize) -> Vec3d
+	{
+		let idx = ((z * self.size[1] + y) * self.size[0] + x) * self.channels;
+		if self.channels == 1
+		{
+			Vec3d::newv(self.data[idx])
+		}
+		else
+		{
+			Vec3d::new(self.data[idx], self.data[idx + 1], self.data[idx + 2])
+		}
+	}
+}
+
+impl Texture for GridTexture
+{
+	fn value(&self, _uv: Vec2d, p: Vec3d) -> Vec3d
+	{
+		let (min, max) = (self.bounds.min(), self.bounds.max());
+		let local = (p - min) / (max - min);
+		if local.v.iter().any(|x| !(0.0..=1.0).contains(x))
+		{
+			return Vec3d::zero();
+		}
+
+		let mut base = [0; 3];
+		let mut t = [0.0; 3];
+		for axis in 0..3
+		{
+			let x = f64::max(local.v[axis] * self.size[axis] as f64 - 0.5, 0.0);
+			base[axis] = usize::min(x as usize, self.size[axis] - 1);
+			t[axis] = if base[axis] + 1 < self.size[axis] { x - base[axis] as f64 } else { 0.0 };
+		}
+
+		let next = |axis: usize| usize::min(base[axis] + 1, self.size[axis] - 1);
+		let lerp = |a: Vec3d, b: Vec3d, t: f64| a * (1.0 - t) + b * t;
+		let x0 = lerp(self.voxel(base[0], base[1], base[2]), self.voxel(next(0), base[1], base[2]), t[0]);
+		let x1 = lerp(self.voxel(base[0], next(1), base[2]), self.voxel(next(0), next(1), base[2]), t[0]);
+		let x2 = lerp(self.voxel(base[0], base[1], next(2)), self.voxel(next(0), base[1], next(2)), t[0]);
+		let x3 = lerp(self.voxel(base[0], next(1), next(2)), self.voxel(next(0), next(1), next(2)), t[0]);
+		lerp(lerp(x0, x1, t[1]), lerp(x2, x3, t[1]), t[2])
+	}
+}
+
+#[cfg(test)]
+mod tests
+{
+
+	use super::*;
+
+	/* 2x2x2 voxels over the unit cube, the value is the index of the voxel */
+	fn grid() -> GridTexture
+	{
+		GridTexture::new((0..8).map(|i| i as f64).collect(), [2, 2, 2], 1, Aabb::new(Vec3d::zero(), Vec3d::one()))
+	}
+
+	#[test]
+	fn test_voxel_centers()
+	{
+		let grid = grid();
+		assert_eq!(grid.value(Vec2d::zero(), Vec3d::new(0.25, 0.25, 0.25)), Vec3d::newv(0.0));
+		assert_eq!(grid.value(Vec2d::zero(), Vec3d::new(0.75, 0.25, 0.25)), Vec3d::newv(1.0));
+		assert_eq!(grid.value(Vec2d::zero(), Vec3d::new(0.25, 0.75, 0.25)), Vec3d::newv(2.0));
+		assert_eq!(grid.value(Vec2d::zero(), Vec3d::new(0.25, 0.25, 0.75)), Vec3d::newv(4.0));
+		assert_eq!(grid.max_value(), 7.0);
+	}
+
+	#[test]
+	fn test_trilinear()
+	{
+		let grid = grid();
+		assert!(Vec3d::length(grid.value(Vec2d::zero(), Vec3d::newv(0.5)) - Vec3d::newv(3.5)) < 1e-12);
+		assert!(Vec3d::length(grid.value(Vec2d::zero(), Vec3d::new(0.5, 0.25, 0.25)) - Vec3d::newv(0.5)) < 1e-12);
+
+		/* clamped to the outer voxel centers and zero outside */
+		assert_eq!(grid.value(Vec2d::zero(), Vec3d::new(0.0, 0.25, 0.25)), Vec3d::newv(0.0));
+		assert_eq!(grid.value(Vec2d::zero(), Vec3d::new(1.0, 0.75, 0.75)), Vec3d::newv(7.0));
+		assert_eq!(grid.value(Vec2d::zero(), Vec3d::new(1.5, 0.5, 0.5)), Vec3d::zero());
+	}
+
+	#[test]
+	fn test_from_vol()
+	{
+		let mut bytes = b"VOL".to_vec();
+		bytes.push(3);
+		for int in [1, 2, 1, 1, 3]
+		{
+			bytes.extend_from_slice(&i32::to_le_bytes(int));
+		}
+		for float in [-1.0, 0.0, 0.0, 1.0, 2.0, 2.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6]
+		{
+			bytes.extend_from_slice(&f32::to_le_bytes(float));
+		}
+		let filename = std::env::temp_dir().join("grid_texture_test.vol");
+		std::fs::write(&filename, bytes).unwrap();
+
+		let grid = GridTexture::from_vol(filename.to_str().unwrap());
+		assert_eq!(grid.size, [2, 1, 1]);
+		assert_eq!(grid.channels, 3);
+		assert_eq!(grid.bounds.min(), Vec3d::new(-1.0, 0.0, 0.0));
+		assert!(Vec3d::length(grid.value(Vec2d::zero(), Vec3d::new(1.0, 1.0, 1.0)) - Vec3d::new(0.4, 0.5, 0.6)) < 1e-6);
+		assert!((grid.max_value() - 0.6).abs() < 1e-6);
+		std::fs::remove_file(filename).unwrap();
+	}
+}
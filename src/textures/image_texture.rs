@@ -3,6 +3,7 @@ use crate::math::vec::
 	Vec2d,
 	Vec3d,
 };
+use crate::scene::Scene;
 
 use image::GenericImageView;
 
@@ -30,6 +31,11 @@ impl ImageTexture
 
 		Self{data, width, height, bytes_per_scanline}
 	}
+
+	pub fn from(_scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		Self::new(&Scene::parse_xml_attr_str(node, "filename".to_string()))
+	}
 }
 
 impl Texture for ImageTexture
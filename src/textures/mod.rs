@@ -1,6 +1,9 @@
+pub mod channel_texture;
 pub mod checker_texture;
+pub mod grid_texture;
 pub mod image_texture;
 pub mod noise_texture;
+pub mod scale_texture;
 pub mod solid_color;
 
 use crate::math::vec::
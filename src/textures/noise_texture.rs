@@ -4,6 +4,7 @@ use crate::math::vec::
 	Vec3d,
 };
 use crate::perlin::Perlin;
+use crate::scene::Scene;
 
 use super::Texture;
 
@@ -20,6 +21,15 @@ impl NoiseTexture
 	{
 		Self{noise: Perlin::new(), scale, color}
 	}
+
+	pub fn from(_scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		let child = |name: &str| node.children().find(|node| node.is_element() && node.tag_name().name() == name);
+
+		let color = child("Color").map_or(Vec3d::one(), |color| Scene::parse_xml_vec3d(&color));
+		let scale = Scene::parse_xml_f64(&child("Scale").expect("no Scale node found for NoiseTexture"));
+		Self::new(color, scale)
+	}
 }
 
 impl Texture for NoiseTexture
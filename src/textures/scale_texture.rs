@@ -0,0 +1,32 @@
+use crate::math::vec::
+{
+	Vec2d,
+	Vec3d,
+};
+
+use std::sync::Arc;
+
+use super::Texture;
+
+/* texture multiplied by a constant factor */
+pub struct ScaleTexture
+{
+	texture: Arc::<dyn Texture>,
+	scale: Vec3d,
+}
+
+impl ScaleTexture
+{
+	pub fn new(texture: Arc::<dyn Texture>, scale: Vec3d) -> Self
+	{
+		Self{texture, scale}
+	}
+}
+
+impl Texture for ScaleTexture
+{
+	fn value(&self, uv: Vec2d, p: Vec3d) -> Vec3d
+	{
+		self.texture.value(uv, p) * self.scale
+	}
+}
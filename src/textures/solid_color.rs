@@ -18,6 +18,11 @@ impl SolidColor
 	{
 		Self{color}
 	}
+
+	pub fn from(_scene: &Scene, node: &roxmltree::Node) -> Self
+	{
+		Self::new(Scene::parse_xml_vec3d(&node.children().find(|node| node.is_element() && node.tag_name().name() == "Color").expect("no Color node found for SolidColor")))
+	}
 }
 
 impl Texture for SolidColor